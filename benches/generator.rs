@@ -1,10 +1,57 @@
 #[macro_use]
 extern crate criterion;
 
+use std::hint::black_box;
+
 use criterion::Criterion;
 use draughts::board::generator::Generator;
+use draughts::board::mutable::Board;
+use draughts::board::mv::Move;
+use draughts::board::perft::perft;
 use draughts::board::position::Position;
 
+// copying the 40 byte position is cheaper than making and unmaking a move: the walk to depth 5
+// takes about 3ms copying against 4ms on one board, and one go costs about a fifth of a make and
+// unmake, so perft and the searches copy
+
+// counts the leaves, copying the position at every node
+fn walk_copy(
+    generator: &Generator,
+    position: &Position,
+    depth: usize,
+    lists: &mut [Vec<Move>],
+) -> u64 {
+    let (list, rest) = lists.split_first_mut().unwrap();
+    generator.legal_moves2(position, list);
+    if depth == 1 {
+        return list.len() as u64;
+    }
+    list.iter()
+        .map(|mv| walk_copy(generator, &position.go(mv), depth - 1, rest))
+        .sum()
+}
+
+// counts the leaves, making and unmaking the moves on one board
+fn walk_board(
+    generator: &Generator,
+    board: &mut Board,
+    depth: usize,
+    lists: &mut [Vec<Move>],
+) -> u64 {
+    let (list, rest) = lists.split_first_mut().unwrap();
+    generator.legal_moves2(board.position(), list);
+    if depth == 1 {
+        return list.len() as u64;
+    }
+    let mut total = 0;
+    for mv in list.iter() {
+        let undo = board.make(mv);
+        total += walk_board(generator, board, depth - 1, rest);
+        board.unmake(&undo);
+    }
+    total
+}
+
 fn generator_benchmark(c: &mut Criterion) {
     let position = Position::parse("wBckkk55rrrWt").unwrap();
     c.bench_function("black king", move |b| b.iter(|| position.piece_at(0)));
@@ -29,6 +76,45 @@ fn generator_benchmark(c: &mut Criterion) {
     bench_generator!(multi_long_capture, "w 5/5/3b1/5/5/5/5/1b3/5/W4");
     bench_generator!(coup_turc, "b 5/el2/5/Bebew/2w2/5/eh2/3we/ew3/5");
     bench_generator!(goerres_bayar, "wcebeaka22b25rreteie");
+
+    let generator = Generator::create();
+    let initial = Position::initial();
+    c.bench_function("perft 4", move |b| {
+        b.iter(|| perft(&generator, &initial, 4))
+    });
+    let generator = Generator::create();
+    let mut lists: Vec<Vec<Move>> = (0..5).map(|_| Vec::with_capacity(63)).collect();
+    c.bench_function("walk 5 copy", move |b| {
+        b.iter(|| walk_copy(&generator, &initial, 5, &mut lists))
+    });
+    let generator = Generator::create();
+    let mut lists: Vec<Vec<Move>> = (0..5).map(|_| Vec::with_capacity(63)).collect();
+    let mut board = Board::create(&initial);
+    c.bench_function("walk 5 make unmake", move |b| {
+        b.iter(|| walk_board(&generator, &mut board, 5, &mut lists))
+    });
+
+    let generator = Generator::create();
+    let study = Position::parse("w 5/3be/5/3be/web2/wewbe/ew3/3bb/5/3ww").unwrap();
+    let moves: Vec<Move> = generator.legal_moves(&study);
+    let copied = moves.clone();
+    c.bench_function("go", move |b| {
+        b.iter(|| {
+            for mv in &copied {
+                black_box(study.go(mv));
+            }
+        })
+    });
+    let mut board = Board::create(&study);
+    c.bench_function("make unmake", move |b| {
+        b.iter(|| {
+            for mv in &moves {
+                let undo = board.make(mv);
+                black_box(board.position());
+                board.unmake(&undo);
+            }
+        })
+    });
 }
 
 criterion_group!(benches, generator_benchmark);
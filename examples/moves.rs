@@ -1,124 +1,42 @@
+use std::env;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Cursor};
+
 use draughts::board::generator::Generator;
-use draughts::board::position::Position;
+use draughts::pdn::reader::PdnReader;
+use draughts::pdn::writer::write_game;
 
-fn moves() -> Vec<String> {
-    vec![
-        "34-29".into(),
-        "19-24".into(),
-        "40-34".into(),
-        "14-19".into(),
-        "45-40".into(),
-        "16-21".into(),
-        "50-45".into(),
-        "21-26".into(),
-        "34-30".into(),
-        "20-25".into(),
-        "29x20".into(),
-        "25x14".into(),
-        "30-25".into(),
-        "15-20".into(),
-        "40-34".into(),
-        "10-15".into(),
-        "44-40".into(),
-        "11-16".into(),
-        "34-30".into(),
-        "7-11".into(),
-        "30-24".into(),
-        "20x29".into(),
-        "33x24".into(),
-        "19x30".into(),
-        "25x34".into(),
-        "14-19".into(),
-        "38-33".into(),
-        "1-7".into(),
-        "42-38".into(),
-        "17-21".into(),
-        "47-42".into(),
-        "21-27".into(),
-        "32x21".into(),
-        "26x17".into(),
-        "31-26".into(),
-        "5-10".into(),
-        "37-32".into(),
-        "10-14".into(),
-        "41-37".into(),
-        "19-23".into(),
-        "46-41".into(),
-        "14-19".into(),
-        "35-30".into(),
-        "9-14".into(),
-        "30-24".into(),
-        "19x30".into(),
-        "34x25".into(),
-        "13-19".into(),
-        "33-29".into(),
-        "23x34".into(),
-        "39x30".into(),
-        "8-13".into(),
-        "40-35".into(),
-        "2-8".into(),
-        "49-44".into(),
-        "15-20".into(),
-        "44-39".into(),
-        "4-10".into(),
-        "39-33".into(),
-        "10-15".into(),
-        "30-24".into(),
-        "19x30".into(),
-        "25x34".into(),
-        "13-19".into(),
-        "43-39".into(),
-        "8-13".into(),
-        "36-31".into(),
-        "3-8".into(),
-        "41-36".into(),
-        "17-21".into(),
-        "26x17".into(),
-        "12x21".into(),
-        "45-40".into(),
-        "7-12".into(),
-        "31-26".into(),
-        "20-25".into(),
-        "26x17".into(),
-        "12x21".into(),
-        "37-31".into(),
-        "21-26".into(),
-        "32-27".into(),
-        "26x37".into(),
-        "42x31".into(),
-        "8-12".into(),
-        "31-26".into(),
-        "12-17".into(),
-        "27-21".into(),
-        "16x27".into(),
-        "38-32".into(),
-        "27x29".into(),
-        "34x21".into(),
-        "11-16".into(),
-        "21-17".into(),
-        "13-18".into(),
-        "36-31".into(),
-        "19-23".into(),
-        "31-27".into(),
-        "23-28".into(),
-        "40-34".into(),
-        "15-20".into(),
-    ]
-}
+const GAME: &str = "[Event \"Example\"]
+[GameType \"20\"]
+
+1. 34-29 19-24 2. 40-34 14-19 3. 45-40 16-21 4. 50-45 21-26 5. 34-30
+20-25 6. 29x20 25x14 7. 30-25 15-20 8. 40-34 10-15 9. 44-40 11-16 10.
+34-30 7-11 11. 30-24 20x29 12. 33x24 19x30 13. 25x34 14-19 14. 38-33 1-7
+15. 42-38 17-21 16. 47-42 21-27 17. 32x21 26x17 18. 31-26 5-10 19. 37-32
+10-14 20. 41-37 19-23 21. 46-41 14-19 22. 35-30 9-14 23. 30-24 19x30 24.
+34x25 13-19 25. 33-29 23x34 26. 39x30 8-13 27. 40-35 2-8 28. 49-44 15-20
+29. 44-39 4-10 30. 39-33 10-15 31. 30-24 19x30 32. 25x34 13-19 33. 43-39
+8-13 34. 36-31 3-8 35. 41-36 17-21 36. 26x17 12x21 37. 45-40 7-12 38.
+31-26 20-25 39. 26x17 12x21 40. 37-31 21-26 41. 32-27 26x37 42. 42x31
+8-12 43. 31-26 12-17 44. 27-21 16x27 45. 38-32 27x29 46. 34x21 11-16 47.
+21-17 13-18 48. 36-31 19-23 49. 31-27 23-28 50. 40-34 15-20 *
+";
 
 pub fn main() {
-    println!("yo: {}", moves().len());
+    let reader: Box<dyn BufRead> = match env::args().nth(1) {
+        Some(path) => Box::new(BufReader::new(File::open(path).expect("Cannot open file"))),
+        None => Box::new(Cursor::new(GAME)),
+    };
     let generator = Generator::create();
-    let mut position = Position::initial();
-    println!("{}", position.ascii());
-    for move_string in moves() {
-        let legal = generator.legal_moves(&position);
-        let mv = legal
-            .into_iter()
-            .find(|m| m.as_string() == move_string)
-            .unwrap();
-        println!("{}", mv.as_full_string());
-        position = position.go(&mv);
-        println!("{}", position.ascii());
+    for game in PdnReader::create(reader) {
+        let game = game.unwrap();
+        println!("{} moves", game.moves.len());
+        let positions = game.positions().unwrap();
+        println!("{}", positions[0].ascii());
+        for (pdn_move, position) in game.moves.iter().zip(&positions[1..]) {
+            println!("{}", pdn_move.mv.as_full_string());
+            println!("{}", position.ascii());
+        }
+        println!("{}", write_game(&generator, &game).unwrap());
     }
 }
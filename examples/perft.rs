@@ -0,0 +1,45 @@
+use std::env;
+use std::time::Instant;
+
+use draughts::board::generator::Generator;
+use draughts::board::perft::{divide, PerftCount};
+use draughts::board::position::Position;
+
+pub fn main() {
+    let mut args = env::args().skip(1);
+    let depth = args
+        .next()
+        .map(|depth| depth.parse().expect("Invalid depth"))
+        .unwrap_or(6);
+    let position = match args.next() {
+        Some(fen) if fen.contains(':') => Position::parse_pdn(&fen),
+        Some(fen) => Position::parse(&fen),
+        None => Ok(Position::initial()),
+    }
+    .expect("Invalid position");
+
+    let generator = Generator::create();
+    println!("{}", position.ascii());
+    let start = Instant::now();
+    let mut total = PerftCount::default();
+    for (mv, count) in divide(&generator, &position, depth) {
+        println!(
+            "{:>12} {:>12} captures {:>10} promotions {:>8}",
+            generator.to_pdn_string(&position, &mv),
+            count.nodes,
+            count.captures,
+            count.promotions
+        );
+        total += count;
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "perft({}) = {} captures {} promotions {} ({} ms, {:.0} nodes/s)",
+        depth,
+        total.nodes,
+        total.captures,
+        total.promotions,
+        elapsed.as_millis(),
+        total.nodes as f64 / elapsed.as_secs_f64().max(1e-9)
+    );
+}
@@ -2,10 +2,11 @@ use std::cmp::{max, min};
 use std::sync::mpsc;
 use std::thread;
 
-use super::judge::{Eval, Judge, MAX_EVAL, MIN_EVAL};
+use super::judge::{backup, child_cut, no_moves_eval, Eval, Judge, MAX_EVAL, MIN_EVAL};
 use super::meta::Meta;
 use super::scope::{Depth, Scope};
 use super::search::SearchResult;
+use super::stop::Stop;
 use crate::board::position::Position;
 
 pub fn makes_cut<TScope>(
@@ -14,6 +15,7 @@ pub fn makes_cut<TScope>(
     position: &Position,
     scope: &TScope,
     cut: Eval,
+    stop: &Stop,
 ) -> SearchResult
 where
     TScope: Scope,
@@ -26,6 +28,10 @@ where
         return SearchResult::evaluation(MAX_EVAL);
     }
 
+    if let Some(exact) = judge.probe(position) {
+        return SearchResult::evaluation(exact);
+    }
+
     let memory = judge.recall(position, scope.depth());
     if memory.depth >= scope.depth() {
         if memory.lower >= cut {
@@ -37,10 +43,13 @@ where
     }
 
     meta.add_nodes(1);
+    if stop.poll(meta.get_nodes()) {
+        return SearchResult::evaluation(MIN_EVAL);
+    }
 
     let mut moves = judge.moves(position, scope.depth());
     if moves.is_empty() {
-        return SearchResult::evaluation(MIN_EVAL);
+        return SearchResult::evaluation(no_moves_eval(judge.losing()));
     }
 
     let quiet = judge.quiet_position(position, &moves);
@@ -67,10 +76,16 @@ where
         for mv in moves {
             let quiet = !single && judge.quiet_move(position, &mv);
             let score = if let Some(next) = scope.next(len, quiet, cut - current_score) {
-                -makes_cut(judge, meta, &position.go(&mv), &next, -cut + 1).evaluation
+                backup(
+                    makes_cut(judge, meta, &position.go(&mv), &next, child_cut(cut), stop)
+                        .evaluation,
+                )
             } else {
                 current_score
             };
+            if stop.stopped() {
+                return SearchResult::evaluation(best);
+            }
             if score > best {
                 best = score;
                 pending = Some(mv);
@@ -106,6 +121,7 @@ pub fn makes_cut_parallel<TJudge, TScope>(
     position: &Position,
     scope: &TScope,
     cut: Eval,
+    stop: &Stop,
 ) -> SearchResult
 where
     TJudge: 'static + Judge + Clone + Send,
@@ -119,6 +135,10 @@ where
         return SearchResult::evaluation(MAX_EVAL);
     }
 
+    if let Some(exact) = judges[0].probe(position) {
+        return SearchResult::evaluation(exact);
+    }
+
     let memory = judges[0].recall(position, scope.depth());
     if memory.depth >= scope.depth() {
         if memory.lower >= cut {
@@ -130,10 +150,13 @@ where
     }
 
     meta.add_nodes(1);
+    if stop.poll(meta.get_nodes()) {
+        return SearchResult::evaluation(MIN_EVAL);
+    }
 
     let mut moves = judges[0].moves(position, scope.depth());
     if moves.is_empty() {
-        return SearchResult::evaluation(MIN_EVAL);
+        return SearchResult::evaluation(no_moves_eval(judges[0].losing()));
     }
 
     let quiet = judges[0].quiet_position(position, &moves);
@@ -172,14 +195,24 @@ where
                     let tx = tx.clone();
                     let position = *position;
                     let scope: TScope = scope.clone();
+                    let stop = stop.clone();
 
                     thread::spawn(move || {
                         let quiet = judge.quiet_move(&position, &mv);
                         let mut meta = Meta::create();
                         let score = if let Some(next) = scope.next(len, quiet, cut - current_score)
                         {
-                            -makes_cut(&mut judge, &mut meta, &position.go(&mv), &next, -cut + 1)
-                                .evaluation
+                            backup(
+                                makes_cut(
+                                    &mut judge,
+                                    &mut meta,
+                                    &position.go(&mv),
+                                    &next,
+                                    child_cut(cut),
+                                    &stop,
+                                )
+                                .evaluation,
+                            )
                         } else {
                             current_score
                         };
@@ -199,6 +232,9 @@ where
                     judge.consolidate();
                     judges.push(judge);
                     open -= 1;
+                    if stop.stopped() {
+                        break;
+                    }
                     if score > best {
                         best = score;
                         pending = Some(mv);
@@ -221,22 +257,31 @@ where
                     pending = Some(mv);
                 }
             }
+            if stop.stopped() {
+                return SearchResult::evaluation(best);
+            }
         } else {
             let single = moves.len() == 1;
             for mv in moves {
                 let quiet = !single && judges[0].quiet_move(position, &mv);
                 let score = if let Some(next) = scope.next(len, quiet, cut - current_score) {
-                    -makes_cut_parallel::<TJudge, TScope>(
-                        judges,
-                        meta,
-                        &position.go(&mv),
-                        &next,
-                        -cut + 1,
+                    backup(
+                        makes_cut_parallel::<TJudge, TScope>(
+                            judges,
+                            meta,
+                            &position.go(&mv),
+                            &next,
+                            child_cut(cut),
+                            stop,
+                        )
+                        .evaluation,
                     )
-                    .evaluation
                 } else {
                     current_score
                 };
+                if stop.stopped() {
+                    return SearchResult::evaluation(best);
+                }
                 if score > best {
                     best = score;
                     pending = Some(mv);
@@ -2,11 +2,12 @@ use std::cmp::min;
 use std::cmp::Ordering::{Equal, Greater, Less};
 
 use super::alphabeta::{makes_cut, makes_cut_parallel};
-use super::judge::{Eval, Judge, MAX_EVAL, MIN_EVAL};
+use super::judge::{backup, child_cut, Eval, Judge, MAX_EVAL, MIN_EVAL};
 use super::meta::Meta;
 use super::mtdf::{mtd_f, mtd_f_parallel};
 use super::scope::{Depth, Scope};
 use super::search::SearchResult;
+use super::stop::Stop;
 use crate::board::mv::Move;
 use crate::board::position::Position;
 
@@ -14,6 +15,7 @@ pub struct BnsResult {
     pub lower: Eval,
     pub meta: Meta,
     pub mv: Move,
+    pub complete: bool,
 }
 
 type MoveCount = u8;
@@ -26,6 +28,16 @@ struct BnsState {
 }
 
 impl BnsState {
+    // the cut is the best guess we have when the search is aborted
+    fn aborted(&self, meta: Meta) -> BnsResult {
+        BnsResult {
+            lower: self.cut,
+            meta,
+            mv: self.mv,
+            complete: false,
+        }
+    }
+
     fn initial(cut: Eval, mv: Move) -> BnsState {
         BnsState {
             lower: MIN_EVAL,
@@ -123,6 +135,7 @@ pub fn best_node_search<TScope>(
     position: &Position,
     depth: Depth,
     initial: &SearchResult,
+    stop: &Stop,
 ) -> BnsResult
 where
     TScope: Scope,
@@ -131,14 +144,23 @@ where
     let mut meta = Meta::create();
     let mut state = match initial.mv {
         Some(mv) if depth > 1 => {
-            let mtd = mtd_f::<TScope>(judge, &position.go(&mv), depth - 1, -initial.evaluation);
+            let mtd = mtd_f::<TScope>(
+                judge,
+                &position.go(&mv),
+                depth - 1,
+                -initial.evaluation,
+                stop,
+            );
             meta.add_nodes(mtd.meta.get_nodes() + 1);
+            if !mtd.complete {
+                return BnsState::initial(initial.evaluation, mv).aborted(meta);
+            }
             moves.sort_by(|&mv1, &mv2| match (mv1 == mv, mv2 == mv) {
                 (true, false) => Less,
                 (false, true) => Greater,
                 _ => Equal,
             });
-            BnsState::initial(-mtd.evaluation, mv)
+            BnsState::initial(backup(mtd.evaluation), mv)
         }
         _ => BnsState::initial(initial.evaluation, moves[0]),
     };
@@ -148,7 +170,20 @@ where
         let mut best = SearchResult::evaluation(MIN_EVAL - 1);
         let mut beta = state.cut - 1;
         for mv in &moves[..] {
-            let score = -makes_cut(judge, &mut meta, &position.go(mv), scope, -beta).evaluation;
+            let score = backup(
+                makes_cut(
+                    judge,
+                    &mut meta,
+                    &position.go(mv),
+                    scope,
+                    child_cut(beta + 1),
+                    stop,
+                )
+                .evaluation,
+            );
+            if stop.check() {
+                return state.aborted(meta);
+            }
             if score > best.evaluation {
                 best = SearchResult::with_move(*mv, score);
             }
@@ -168,6 +203,7 @@ where
                 lower: next.lower,
                 meta,
                 mv: next.mv,
+                complete: true,
             };
         }
         state = next;
@@ -179,6 +215,7 @@ pub fn best_node_search_parallel<TJudge, TScope>(
     position: &Position,
     depth: Depth,
     initial: &SearchResult,
+    stop: &Stop,
 ) -> BnsResult
 where
     TJudge: 'static + Judge + Clone + Send,
@@ -193,14 +230,18 @@ where
                 &position.go(&mv),
                 depth - 1,
                 -initial.evaluation,
+                stop,
             );
             meta.add_nodes(mtd.meta.get_nodes() + 1);
+            if !mtd.complete {
+                return BnsState::initial(initial.evaluation, mv).aborted(meta);
+            }
             moves.sort_by(|&mv1, &mv2| match (mv1 == mv, mv2 == mv) {
                 (true, false) => Less,
                 (false, true) => Greater,
                 _ => Equal,
             });
-            BnsState::initial(-mtd.evaluation, mv)
+            BnsState::initial(backup(mtd.evaluation), mv)
         }
         _ => BnsState::initial(initial.evaluation, moves[0]),
     };
@@ -210,14 +251,20 @@ where
         let mut best = SearchResult::evaluation(MIN_EVAL - 1);
         let mut beta = state.cut - 1;
         for mv in &moves[..] {
-            let score = -makes_cut_parallel::<TJudge, TScope>(
-                judges,
-                &mut meta,
-                &position.go(mv),
-                scope,
-                -beta,
-            )
-            .evaluation;
+            let score = backup(
+                makes_cut_parallel::<TJudge, TScope>(
+                    judges,
+                    &mut meta,
+                    &position.go(mv),
+                    scope,
+                    child_cut(beta + 1),
+                    stop,
+                )
+                .evaluation,
+            );
+            if stop.check() {
+                return state.aborted(meta);
+            }
             if score > best.evaluation {
                 best = SearchResult::with_move(*mv, score);
             }
@@ -237,6 +284,7 @@ where
                 lower: next.lower,
                 meta,
                 mv: next.mv,
+                complete: true,
             };
         }
         state = next;
@@ -7,6 +7,64 @@ pub const MAX_EVAL: Eval = 15_000;
 pub const ZERO_EVAL: Eval = 0;
 pub const DRAW_EVAL: Eval = 0;
 pub const MIN_EVAL: Eval = -15_000;
+// forced wins and losses count the plies to the end of the game away from MAX_EVAL and MIN_EVAL
+pub const MAX_PLIES: Eval = 1_000;
+
+pub fn win_eval(plies: Eval) -> Eval {
+    MAX_EVAL - plies
+}
+
+pub fn loss_eval(plies: Eval) -> Eval {
+    MIN_EVAL + plies
+}
+
+// the side to move without moves loses, or wins when playing to lose
+pub fn no_moves_eval(losing: bool) -> Eval {
+    if losing {
+        win_eval(0)
+    } else {
+        loss_eval(0)
+    }
+}
+
+// the plies to a forced end of the game, if the evaluation is one rather than a heuristic
+pub fn plies_to_end(evaluation: Eval) -> Option<Eval> {
+    if evaluation > MAX_EVAL - MAX_PLIES {
+        Some(MAX_EVAL - evaluation)
+    } else if evaluation < MIN_EVAL + MAX_PLIES {
+        Some(evaluation - MIN_EVAL)
+    } else {
+        None
+    }
+}
+
+pub fn is_exact(evaluation: Eval) -> bool {
+    plies_to_end(evaluation).is_some()
+}
+
+// the evaluation of a position seen from the position before it, one ply further from the end
+pub fn backup(evaluation: Eval) -> Eval {
+    let evaluation = -evaluation;
+    if evaluation > MAX_EVAL - MAX_PLIES {
+        evaluation - 1
+    } else if evaluation < MIN_EVAL + MAX_PLIES {
+        evaluation + 1
+    } else {
+        evaluation
+    }
+}
+
+// the cut for the position after a move, so that the move reaches cut exactly when that one fails
+pub fn child_cut(cut: Eval) -> Eval {
+    let unbacked = if cut > MAX_EVAL - MAX_PLIES {
+        cut + 1
+    } else if cut <= MIN_EVAL + MAX_PLIES {
+        cut - 1
+    } else {
+        cut
+    };
+    1 - unbacked
+}
 
 pub struct PositionMemory {
     pub depth: Depth,
@@ -55,10 +113,39 @@ pub trait Judge {
     fn remember(&mut self, _: &Position, _: Depth, _: Eval, _: Option<Move>, _: bool) {}
     fn consolidate(&mut self) {}
     fn evaluate(&self, position: &Position) -> Eval;
+    // an exact evaluation from the endgame tablebase, if the position is in it
+    fn probe(&self, _position: &Position) -> Option<Eval> {
+        None
+    }
     fn moves(&self, position: &Position, depth: Depth) -> Vec<Move>;
     fn display_name(&self) -> &str;
+    // whether running out of moves wins, as in antidraughts
+    fn losing(&self) -> bool {
+        false
+    }
     fn quiet_move(&self, position: &Position, mv: &Move) -> bool;
     fn quiet_position(&self, position: &Position, moves: &[Move]) -> bool {
         moves.len() > 1 && self.quiet_move(position, &moves[0])
     }
 }
+
+#[test]
+fn distances() {
+    assert_eq!(backup(loss_eval(0)), win_eval(1));
+    assert_eq!(backup(win_eval(1)), loss_eval(2));
+    assert_eq!(backup(25), -25);
+    assert_eq!(plies_to_end(win_eval(7)), Some(7));
+    assert_eq!(plies_to_end(loss_eval(4)), Some(4));
+    assert_eq!(plies_to_end(ZERO_EVAL), None);
+    for cut in [MIN_EVAL, loss_eval(3), -100, 1, win_eval(5), MAX_EVAL] {
+        for child in [MIN_EVAL, loss_eval(4), -101, 0, 100, win_eval(2), MAX_EVAL] {
+            assert_eq!(
+                backup(child) >= cut,
+                child < child_cut(cut),
+                "{} {}",
+                cut,
+                child
+            );
+        }
+    }
+}
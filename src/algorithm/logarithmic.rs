@@ -46,7 +46,7 @@ impl Scope for LogarithmicScope {
     }
 
     fn next(&self, moves: usize, quiet: bool, gap: Eval) -> Option<LogarithmicScope> {
-        let shift = (gap / 1000).abs() as usize;
+        let shift = (gap / 1000).unsigned_abs() as usize;
         let qmoves = if quiet { moves } else { moves.min(2) };
         match (self.nodes / qmoves) >> shift {
             0 => None,
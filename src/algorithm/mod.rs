@@ -6,5 +6,9 @@ pub mod judge;
 pub mod logarithmic;
 pub mod meta;
 pub mod mtdf;
+pub mod multipv;
+pub mod pv;
 pub mod scope;
 pub mod search;
+pub mod stop;
+pub mod tt;
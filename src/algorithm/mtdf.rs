@@ -2,6 +2,7 @@ use super::alphabeta::{makes_cut, makes_cut_parallel};
 use super::judge::{Eval, Judge, MAX_EVAL, MIN_EVAL};
 use super::meta::Meta;
 use super::scope::{Depth, Scope};
+use super::stop::Stop;
 use crate::board::mv::Move;
 use crate::board::position::Position;
 
@@ -45,14 +46,16 @@ pub struct MtdResult {
     pub mv: Move,
     pub evaluation: Eval,
     pub meta: Meta,
+    pub complete: bool,
 }
 
 impl MtdResult {
-    fn create(mv: Move, evaluation: Eval, meta: Meta) -> MtdResult {
+    fn create(mv: Move, evaluation: Eval, meta: Meta, complete: bool) -> MtdResult {
         MtdResult {
             mv,
             evaluation,
             meta,
+            complete,
         }
     }
 }
@@ -62,6 +65,7 @@ pub fn mtd_f<TScope>(
     position: &Position,
     depth: Depth,
     guess: Eval,
+    stop: &Stop,
 ) -> MtdResult
 where
     TScope: Scope,
@@ -71,21 +75,25 @@ where
     let mut meta = Meta::create();
     let mut mv = None;
     loop {
-        let result = makes_cut(judge, &mut meta, position, scope, state.guess);
-        state = state.next(result.evaluation);
-        if let Some(best_move) = result.mv {
-            mv = Some(best_move);
+        let result = makes_cut(judge, &mut meta, position, scope, state.guess, stop);
+        let stopped = stop.check();
+        if !stopped {
+            state = state.next(result.evaluation);
+            if let Some(best_move) = result.mv {
+                mv = Some(best_move);
+            }
         }
-        if state.finished() {
+        if stopped || state.finished() {
             let mv = match mv {
                 Some(mv) => mv,
                 None => judge
                     .moves(position, depth)
-                    .get(0)
+                    .first()
                     .cloned()
                     .unwrap_or_else(Move::null),
             };
-            return MtdResult::create(mv, state.lower, meta);
+            let evaluation = if stopped { state.guess } else { state.lower };
+            return MtdResult::create(mv, evaluation, meta, !stopped);
         }
     }
 }
@@ -95,6 +103,7 @@ pub fn mtd_f_parallel<TJudge, TScope>(
     position: &Position,
     depth: Depth,
     guess: Eval,
+    stop: &Stop,
 ) -> MtdResult
 where
     TJudge: 'static + Judge + Clone + Send,
@@ -105,22 +114,32 @@ where
     let mut meta = Meta::create();
     let mut mv = None;
     loop {
-        let result =
-            makes_cut_parallel::<TJudge, TScope>(judges, &mut meta, position, scope, state.guess);
-        state = state.next(result.evaluation);
-        if let Some(best_move) = result.mv {
-            mv = Some(best_move);
+        let result = makes_cut_parallel::<TJudge, TScope>(
+            judges,
+            &mut meta,
+            position,
+            scope,
+            state.guess,
+            stop,
+        );
+        let stopped = stop.check();
+        if !stopped {
+            state = state.next(result.evaluation);
+            if let Some(best_move) = result.mv {
+                mv = Some(best_move);
+            }
         }
-        if state.finished() {
+        if stopped || state.finished() {
             let mv = match mv {
                 Some(mv) => mv,
                 None => judges[0]
                     .moves(position, depth)
-                    .get(0)
+                    .first()
                     .cloned()
                     .unwrap_or_else(Move::null),
             };
-            return MtdResult::create(mv, state.lower, meta);
+            let evaluation = if stopped { state.guess } else { state.lower };
+            return MtdResult::create(mv, evaluation, meta, !stopped);
         }
     }
 }
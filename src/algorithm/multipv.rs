@@ -0,0 +1,232 @@
+use super::alphabeta::{makes_cut, makes_cut_parallel};
+use super::judge::{backup, child_cut, Eval, Judge};
+use super::meta::Meta;
+use super::mtdf::{mtd_f, mtd_f_parallel};
+use super::scope::{Depth, Scope};
+use super::stop::Stop;
+use crate::board::mv::Move;
+use crate::board::position::Position;
+
+pub type Ranking = Vec<(Move, Eval)>;
+
+pub struct MultiPvResult {
+    pub ranking: Ranking,
+    pub meta: Meta,
+    pub complete: bool,
+}
+
+// moves ranked in the previous iteration go first, so the threshold is tight early on
+fn order(moves: Vec<Move>, previous: &[(Move, Eval)]) -> Vec<(Move, Option<Eval>)> {
+    let mut ordered: Vec<(Move, Option<Eval>)> = previous
+        .iter()
+        .filter(|(mv, _)| moves.contains(mv))
+        .map(|&(mv, evaluation)| (mv, Some(evaluation)))
+        .collect();
+    for mv in moves {
+        if !previous.iter().any(|&(ranked, _)| ranked == mv) {
+            ordered.push((mv, None));
+        }
+    }
+    ordered
+}
+
+fn insert(ranking: &mut Ranking, count: usize, mv: Move, evaluation: Eval) {
+    let index = ranking
+        .iter()
+        .position(|&(_, ranked)| ranked < evaluation)
+        .unwrap_or(ranking.len());
+    ranking.insert(index, (mv, evaluation));
+    ranking.truncate(count);
+}
+
+fn threshold(ranking: &Ranking, count: usize) -> Option<Eval> {
+    if ranking.len() < count {
+        None
+    } else {
+        ranking.last().map(|&(_, evaluation)| evaluation)
+    }
+}
+
+pub fn multi_pv<TScope>(
+    judge: &mut dyn Judge,
+    position: &Position,
+    depth: Depth,
+    count: usize,
+    previous: &[(Move, Eval)],
+    stop: &Stop,
+) -> MultiPvResult
+where
+    TScope: Scope,
+{
+    let count = count.max(1);
+    let scope = &TScope::from_depth(depth);
+    let mut meta = Meta::create();
+    let mut ranking = vec![];
+    let estimate = judge.evaluate(position);
+    for (mv, guess) in order(judge.moves(position, depth), previous) {
+        let next = position.go(&mv);
+        if let Some(threshold) = threshold(&ranking, count) {
+            let cut = child_cut(threshold + 1);
+            let result = makes_cut(judge, &mut meta, &next, scope, cut, stop);
+            if stop.check() {
+                return MultiPvResult {
+                    ranking,
+                    meta,
+                    complete: false,
+                };
+            }
+            if result.evaluation >= cut {
+                continue;
+            }
+        }
+        let guess = guess
+            .or_else(|| ranking.first().map(|&(_, evaluation)| evaluation))
+            .unwrap_or(estimate);
+        let mtd = mtd_f::<TScope>(judge, &next, depth, -guess, stop);
+        meta.add_nodes(mtd.meta.get_nodes() + 1);
+        if !mtd.complete {
+            return MultiPvResult {
+                ranking,
+                meta,
+                complete: false,
+            };
+        }
+        insert(&mut ranking, count, mv, backup(mtd.evaluation));
+    }
+    MultiPvResult {
+        ranking,
+        meta,
+        complete: true,
+    }
+}
+
+pub fn multi_pv_parallel<TJudge, TScope>(
+    judges: &mut Vec<TJudge>,
+    position: &Position,
+    depth: Depth,
+    count: usize,
+    previous: &[(Move, Eval)],
+    stop: &Stop,
+) -> MultiPvResult
+where
+    TJudge: 'static + Judge + Clone + Send,
+    TScope: 'static + Scope + Send,
+{
+    let count = count.max(1);
+    let scope = &TScope::from_depth(depth);
+    let mut meta = Meta::create();
+    let mut ranking = vec![];
+    let estimate = judges[0].evaluate(position);
+    for (mv, guess) in order(judges[0].moves(position, depth), previous) {
+        let next = position.go(&mv);
+        if let Some(threshold) = threshold(&ranking, count) {
+            let cut = child_cut(threshold + 1);
+            let result =
+                makes_cut_parallel::<TJudge, TScope>(judges, &mut meta, &next, scope, cut, stop);
+            if stop.check() {
+                return MultiPvResult {
+                    ranking,
+                    meta,
+                    complete: false,
+                };
+            }
+            if result.evaluation >= cut {
+                continue;
+            }
+        }
+        let guess = guess
+            .or_else(|| ranking.first().map(|&(_, evaluation)| evaluation))
+            .unwrap_or(estimate);
+        let mtd = mtd_f_parallel::<TJudge, TScope>(judges, &next, depth, -guess, stop);
+        meta.add_nodes(mtd.meta.get_nodes() + 1);
+        if !mtd.complete {
+            return MultiPvResult {
+                ranking,
+                meta,
+                complete: false,
+            };
+        }
+        insert(&mut ranking, count, mv, backup(mtd.evaluation));
+    }
+    MultiPvResult {
+        ranking,
+        meta,
+        complete: true,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::algorithm::depth::DepthScope;
+    use crate::algorithm::judge::ZERO_EVAL;
+    use crate::board::generator::Generator;
+    use crate::board::piece::Color::White;
+    use crate::board::piece::{BLACK_KING, BLACK_MAN, WHITE_KING, WHITE_MAN};
+
+    struct AdvanceJudge {
+        generator: Generator,
+    }
+
+    impl Judge for AdvanceJudge {
+        fn evaluate(&self, position: &Position) -> Eval {
+            let score: Eval = (0..50)
+                .map(|field| match position.piece_at(field) {
+                    WHITE_MAN => 100 + (49 - field as Eval) / 5,
+                    WHITE_KING => 300,
+                    BLACK_MAN => -100 - field as Eval / 5,
+                    BLACK_KING => -300,
+                    _ => 0,
+                })
+                .sum();
+            if position.side_to_move() == White {
+                score
+            } else {
+                -score
+            }
+        }
+        fn moves(&self, position: &Position, _: Depth) -> Vec<Move> {
+            self.generator.legal_moves(position)
+        }
+        fn display_name(&self) -> &str {
+            "Advance"
+        }
+        fn quiet_move(&self, _: &Position, mv: &Move) -> bool {
+            mv.num_taken() == 0
+        }
+    }
+
+    #[test]
+    fn ranks_root_moves() {
+        let mut judge = AdvanceJudge {
+            generator: Generator::create(),
+        };
+        let position =
+            Position::parse_pdn("W:W28,32,33,34,37,38,42:B13,17,18,19,22,23,24").unwrap();
+        let stop = Stop::create();
+        let depth = 3;
+
+        let mut exact: Ranking = judge
+            .moves(&position, depth)
+            .into_iter()
+            .map(|mv| {
+                let mtd =
+                    mtd_f::<DepthScope>(&mut judge, &position.go(&mv), depth, ZERO_EVAL, &stop);
+                (mv, backup(mtd.evaluation))
+            })
+            .collect();
+        exact.sort_by_key(|&(_, evaluation)| std::cmp::Reverse(evaluation));
+        assert!(exact.len() > 2);
+
+        let all = multi_pv::<DepthScope>(&mut judge, &position, depth, 100, &[], &stop);
+        assert!(all.complete);
+        assert_eq!(all.ranking.len(), exact.len());
+        let scores = |ranking: &Ranking| ranking.iter().map(|&(_, e)| e).collect::<Vec<_>>();
+        assert_eq!(scores(&all.ranking), scores(&exact));
+
+        let best = multi_pv::<DepthScope>(&mut judge, &position, depth, 2, &all.ranking, &stop);
+        assert_eq!(scores(&best.ranking), scores(&exact)[..2]);
+        assert!(best.ranking[0] == all.ranking[0]);
+        assert!(best.meta.get_nodes() < all.meta.get_nodes());
+    }
+}
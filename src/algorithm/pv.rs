@@ -0,0 +1,108 @@
+use std::collections::HashSet;
+
+use super::judge::Judge;
+use super::scope::Depth;
+use crate::board::generator::Generator;
+use crate::board::mv::Move;
+use crate::board::position::Position;
+
+const MAX_LENGTH: usize = 32;
+
+// follows the remembered moves, stopping at the first one that is not legal
+pub fn principal_variation(
+    judge: &dyn Judge,
+    generator: &Generator,
+    position: &Position,
+    mv: Move,
+) -> Vec<Move> {
+    let mut pv = vec![];
+    let mut seen = HashSet::new();
+    let mut position = *position;
+    let mut next = Some(mv);
+    while let Some(mv) = next {
+        if pv.len() >= MAX_LENGTH
+            || !seen.insert(position.key())
+            || !generator.legal_moves(&position).contains(&mv)
+        {
+            break;
+        }
+        pv.push(mv);
+        position = position.go(&mv);
+        let memory = judge.recall(&position, Depth::MAX);
+        next = if memory.has_move() {
+            generator
+                .legal_moves(&position)
+                .into_iter()
+                .find(|mv| mv.from() == memory.from && mv.to() == memory.to)
+        } else {
+            None
+        };
+    }
+    pv
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::algorithm::judge::{Eval, PositionMemory, ZERO_EVAL};
+    use crate::board::position::Field;
+    use crate::board::zobrist::Key;
+    use std::collections::HashMap;
+
+    struct MemoryJudge {
+        generator: Generator,
+        memory: HashMap<Key, (Field, Field)>,
+    }
+
+    impl Judge for MemoryJudge {
+        fn recall(&self, position: &Position, _: Depth) -> PositionMemory {
+            match self.memory.get(&position.key()) {
+                Some(&(from, to)) => PositionMemory::create(1, ZERO_EVAL, ZERO_EVAL, from, to),
+                None => PositionMemory::empty(),
+            }
+        }
+        fn evaluate(&self, _: &Position) -> Eval {
+            ZERO_EVAL
+        }
+        fn moves(&self, position: &Position, _: Depth) -> Vec<Move> {
+            self.generator.legal_moves(position)
+        }
+        fn display_name(&self) -> &str {
+            "Memory"
+        }
+        fn quiet_move(&self, _: &Position, _: &Move) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn follow_memory() {
+        let generator = Generator::create();
+        let initial = Position::initial();
+        let line = [
+            Move::shift(31, 27),
+            Move::shift(18, 22),
+            Move::take_one(27, 18, 22),
+        ];
+        let mut judge = MemoryJudge {
+            generator: Generator::create(),
+            memory: HashMap::new(),
+        };
+        let first = initial.go(&line[0]);
+        judge
+            .memory
+            .insert(first.key(), (line[1].from(), line[1].to()));
+        let second = first.go(&line[1]);
+        judge
+            .memory
+            .insert(second.key(), (line[1].from(), line[1].to()));
+        assert!(principal_variation(&judge, &generator, &initial, line[0]) == line[..2]);
+
+        judge
+            .memory
+            .insert(second.key(), (line[2].from(), line[2].to()));
+        assert!(principal_variation(&judge, &generator, &initial, line[0]) == line);
+
+        assert!(principal_variation(&judge, &generator, &initial, line[1]).is_empty());
+    }
+}
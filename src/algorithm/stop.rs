@@ -0,0 +1,85 @@
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::Arc;
+use std::time::Instant;
+
+use super::meta::Nodes;
+
+const POLL_NODES: Nodes = 1024;
+
+#[derive(Clone, Default)]
+pub struct Stop {
+    flag: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl Stop {
+    pub fn create() -> Stop {
+        Stop::default()
+    }
+
+    pub fn with_deadline(deadline: Option<Instant>) -> Stop {
+        Stop {
+            flag: Arc::new(AtomicBool::new(false)),
+            deadline,
+        }
+    }
+
+    // clears the flag for every clone sharing it
+    pub fn restart(&mut self, deadline: Option<Instant>) {
+        self.flag.store(false, Relaxed);
+        self.deadline = deadline;
+    }
+
+    pub fn stop(&self) {
+        self.flag.store(true, Relaxed);
+    }
+
+    pub fn stopped(&self) -> bool {
+        self.flag.load(Relaxed)
+    }
+
+    pub fn check(&self) -> bool {
+        if self.stopped() {
+            return true;
+        }
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.stop();
+            return true;
+        }
+        false
+    }
+
+    // reading the clock at every node is too expensive
+    pub fn poll(&self, nodes: Nodes) -> bool {
+        if nodes.is_multiple_of(POLL_NODES) {
+            self.check()
+        } else {
+            self.stopped()
+        }
+    }
+}
+
+#[test]
+fn shared_flag() {
+    let mut stop = Stop::create();
+    let handle = stop.clone();
+    assert!(!stop.stopped());
+    handle.stop();
+    assert!(stop.stopped());
+    assert!(stop.poll(1));
+    stop.restart(None);
+    assert!(!handle.stopped());
+}
+
+#[test]
+fn deadline() {
+    let stop = Stop::with_deadline(Some(Instant::now()));
+    assert!(!stop.poll(1));
+    assert!(stop.poll(POLL_NODES));
+    assert!(stop.poll(1));
+    assert!(Stop::with_deadline(Some(Instant::now())).check());
+    assert!(!Stop::with_deadline(None).check());
+}
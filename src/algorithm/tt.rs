@@ -0,0 +1,300 @@
+use std::mem::size_of;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering::Relaxed};
+
+use super::judge::{Eval, PositionMemory, MAX_EVAL, MIN_EVAL};
+use super::scope::Depth;
+use crate::board::mv::Move;
+use crate::board::position::Field;
+use crate::board::zobrist::Key;
+
+pub const DEFAULT_MEGABYTES: usize = 64;
+const SLOTS: usize = 4;
+const AGES: u8 = 0x80;
+const USED: u64 = 1 << 56;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Data {
+    lower: Eval,
+    upper: Eval,
+    depth: Depth,
+    from: u8,
+    to: u8,
+    age: u8,
+}
+
+impl Data {
+    fn pack(&self) -> u64 {
+        u64::from(self.lower as u16)
+            | u64::from(self.upper as u16) << 16
+            | u64::from(self.depth) << 32
+            | u64::from(self.from) << 40
+            | u64::from(self.to) << 48
+            | USED
+            | u64::from(self.age) << 57
+    }
+
+    fn unpack(bits: u64) -> Data {
+        Data {
+            lower: bits as u16 as Eval,
+            upper: (bits >> 16) as u16 as Eval,
+            depth: (bits >> 32) as u8,
+            from: (bits >> 40) as u8,
+            to: (bits >> 48) as u8,
+            age: (bits >> 57) as u8,
+        }
+    }
+
+    fn merge(&mut self, other: &Data) {
+        if self.depth > other.depth {
+            return;
+        }
+        if self.depth == other.depth {
+            self.lower = self.lower.max(other.lower);
+            self.upper = self.upper.min(other.upper);
+            self.age = other.age;
+            if other.from != 0 || other.to != 0 {
+                self.from = other.from;
+                self.to = other.to;
+            }
+        } else {
+            *self = *other;
+        }
+    }
+}
+
+#[derive(Default)]
+struct Slot {
+    check: AtomicU64,
+    data: AtomicU64,
+}
+
+impl Slot {
+    // a torn write from a concurrent store fails the check, so no lock is needed
+    fn load(&self, key: Key) -> Option<Data> {
+        let data = self.data.load(Relaxed);
+        let check = self.check.load(Relaxed);
+        if data & USED != 0 && check ^ data == key {
+            Some(Data::unpack(data))
+        } else {
+            None
+        }
+    }
+
+    fn store(&self, key: Key, data: &Data) {
+        let data = data.pack();
+        self.check.store(key ^ data, Relaxed);
+        self.data.store(data, Relaxed);
+    }
+}
+
+pub struct TranspositionTable {
+    slots: Box<[Slot]>,
+    mask: usize,
+    age: AtomicU8,
+}
+
+impl TranspositionTable {
+    pub fn create(megabytes: usize) -> TranspositionTable {
+        let bytes = megabytes.max(1) << 20;
+        let mut buckets = 1;
+        while 2 * buckets * SLOTS * size_of::<Slot>() <= bytes {
+            buckets *= 2;
+        }
+        TranspositionTable {
+            slots: (0..buckets * SLOTS).map(|_| Slot::default()).collect(),
+            mask: buckets - 1,
+            age: AtomicU8::new(0),
+        }
+    }
+
+    fn bucket(&self, key: Key) -> &[Slot] {
+        let start = (key as usize & self.mask) * SLOTS;
+        &self.slots[start..start + SLOTS]
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn new_search(&self) {
+        self.age.fetch_add(1, Relaxed);
+    }
+
+    pub fn clear(&self) {
+        for slot in self.slots.iter() {
+            slot.data.store(0, Relaxed);
+            slot.check.store(0, Relaxed);
+        }
+    }
+
+    // permille of sampled slots written during the current search
+    pub fn usage(&self) -> usize {
+        let age = self.age.load(Relaxed) % AGES;
+        let sample = self.slots.len().min(1000);
+        let used = self.slots[..sample]
+            .iter()
+            .filter(|slot| {
+                let data = slot.data.load(Relaxed);
+                data & USED != 0 && Data::unpack(data).age == age
+            })
+            .count();
+        1000 * used / sample
+    }
+
+    pub fn recall(&self, key: Key) -> PositionMemory {
+        match self.bucket(key).iter().find_map(|slot| slot.load(key)) {
+            Some(data) => PositionMemory::create(
+                data.depth,
+                data.lower,
+                data.upper,
+                data.from as Field,
+                data.to as Field,
+            ),
+            None => PositionMemory::empty(),
+        }
+    }
+
+    // wins and losses count from the position itself, so they are stored and recalled unchanged
+    pub fn remember(&self, key: Key, depth: Depth, evaluation: Eval, mv: Option<Move>, low: bool) {
+        let mv = mv.unwrap_or_else(Move::null);
+        let age = self.age.load(Relaxed) % AGES;
+        let data = Data {
+            lower: if low { MIN_EVAL } else { evaluation },
+            upper: if low { evaluation } else { MAX_EVAL },
+            depth,
+            from: mv.from() as u8,
+            to: mv.to() as u8,
+            age,
+        };
+
+        let bucket = self.bucket(key);
+        for slot in bucket {
+            if let Some(mut found) = slot.load(key) {
+                found.merge(&data);
+                slot.store(key, &found);
+                return;
+            }
+        }
+
+        // replace the shallowest entry, preferring entries from earlier searches
+        let priority = |slot: &Slot| {
+            let bits = slot.data.load(Relaxed);
+            if bits & USED == 0 {
+                return isize::MIN;
+            }
+            let found = Data::unpack(bits);
+            let stale = (age.wrapping_sub(found.age) % AGES) as isize;
+            found.depth as isize - 4 * stale
+        };
+        let victim = bucket.iter().min_by_key(|slot| priority(slot)).unwrap();
+        victim.store(key, &data);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn pack_roundtrip() {
+        let data = Data {
+            lower: MIN_EVAL,
+            upper: -17,
+            depth: 27,
+            from: 49,
+            to: 0,
+            age: 127,
+        };
+        assert_eq!(Data::unpack(data.pack()), data);
+    }
+
+    #[test]
+    fn size() {
+        let table = TranspositionTable::create(1);
+        assert_eq!(table.capacity() * size_of::<Slot>(), 1 << 20);
+    }
+
+    #[test]
+    fn recall_and_merge() {
+        let table = TranspositionTable::create(1);
+        assert_eq!(table.recall(42).depth, 0);
+        assert!(!table.recall(42).has_move());
+
+        table.remember(42, 3, 100, Some(Move::shift(31, 27)), false);
+        let memory = table.recall(42);
+        assert_eq!(memory.depth, 3);
+        assert_eq!(memory.lower, 100);
+        assert_eq!(memory.upper, MAX_EVAL);
+        assert_eq!((memory.from, memory.to), (31, 27));
+
+        table.remember(42, 3, 120, None, true);
+        let memory = table.recall(42);
+        assert_eq!((memory.lower, memory.upper), (100, 120));
+        assert_eq!((memory.from, memory.to), (31, 27));
+
+        table.remember(42, 2, -50, None, true);
+        assert_eq!(table.recall(42).upper, 120);
+
+        table.remember(42, 4, -50, None, true);
+        let memory = table.recall(42);
+        assert_eq!(
+            (memory.depth, memory.lower, memory.upper),
+            (4, MIN_EVAL, -50)
+        );
+
+        table.clear();
+        assert_eq!(table.recall(42).depth, 0);
+    }
+
+    #[test]
+    fn replacement() {
+        let table = TranspositionTable::create(1);
+        let buckets = (table.mask + 1) as Key;
+        let keys: Vec<Key> = (1..=SLOTS as Key + 1).map(|i| i * buckets + 7).collect();
+        for (i, &key) in keys[..SLOTS].iter().enumerate() {
+            table.remember(key, 10 + i as Depth, 1, None, false);
+        }
+        table.remember(keys[SLOTS], 5, 1, None, false);
+        assert_eq!(table.recall(keys[0]).depth, 0);
+        assert_eq!(table.recall(keys[SLOTS]).depth, 5);
+        assert_eq!(table.recall(keys[1]).depth, 11);
+
+        for _ in 0..4 {
+            table.new_search();
+        }
+        table.remember(keys[0], 1, 1, None, false);
+        assert_eq!(table.recall(keys[0]).depth, 1);
+        assert_eq!(table.recall(keys[SLOTS]).depth, 0);
+        table.remember(keys[SLOTS], 2, 1, None, false);
+        assert_eq!(table.recall(keys[0]).depth, 1);
+        assert_eq!(table.recall(keys[1]).depth, 0);
+        assert_eq!(table.recall(keys[2]).depth, 12);
+        assert_eq!(table.usage(), 2);
+    }
+
+    #[test]
+    fn concurrent() {
+        let table = Arc::new(TranspositionTable::create(1));
+        let threads: Vec<_> = (0..4)
+            .map(|t| {
+                let table = Arc::clone(&table);
+                thread::spawn(move || {
+                    for i in 0..20_000u64 {
+                        let key = (i * 4 + t).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+                        let evaluation = (key % 1000) as Eval;
+                        table.remember(key, (key % 20) as Depth, evaluation, None, false);
+                        let memory = table.recall(key ^ 1);
+                        if memory.depth > 0 {
+                            assert_eq!(memory.lower, ((key ^ 1) % 1000) as Eval);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::env;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::process;
+
+use draughts::board::generator::Generator;
+use draughts::board::position::Position;
+use draughts::book::Book;
+use draughts::pdn::reader::PdnReader;
+
+const PLIES: usize = 20;
+const MIN_GAMES: u32 = 2;
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: book [-p plies] [-m min games] <book> <games.pdn>...\n\
+         Without games it shows the book moves from the initial position"
+    );
+    process::exit(1);
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+pub fn main() {
+    let mut args = env::args().skip(1);
+    let mut plies = PLIES;
+    let mut min_games = MIN_GAMES;
+    let mut paths = vec![];
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "-p" => plies = value().parse().unwrap_or_else(|_| usage()),
+            "-m" => min_games = value().parse().unwrap_or_else(|_| usage()),
+            path if !path.starts_with('-') => paths.push(path.to_owned()),
+            _ => usage(),
+        }
+    }
+    if paths.is_empty() {
+        usage();
+    }
+    let book_path = Path::new(&paths[0]);
+
+    let book = if paths.len() == 1 {
+        Book::read(book_path).unwrap_or_else(|e| fail(&e))
+    } else {
+        let mut book = Book::create();
+        for path in &paths[1..] {
+            let file = File::open(path).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+            for game in PdnReader::create(BufReader::new(file)) {
+                let game = game.unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+                book.add_game(&game, plies).unwrap_or_else(|e| fail(&e));
+            }
+        }
+        book.prune(min_games);
+        book.write(book_path).unwrap_or_else(|e| fail(&e));
+        println!("{} positions", book.len());
+        book
+    };
+
+    let generator = Generator::create();
+    let initial = Position::initial();
+    for (mv, book_move) in book.moves(&generator, &initial) {
+        println!(
+            "{:<6} {:>6} games {:>5.1}% (+{} ={} -{})",
+            generator.to_short_string(&initial, &mv),
+            book_move.games(),
+            100.0 * book_move.score(),
+            book_move.wins,
+            book_move.draws,
+            book_move.losses
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use std::env;
+use std::net::TcpListener;
+use std::process;
+
+use draughts::algorithm::meta::Nodes;
+use draughts::engine::randaap::RandAap;
+use draughts::engine::sherlock::Sherlock;
+use draughts::engine::slonenok::Slonenok;
+use draughts::engine::{Engine, EngineResult};
+use draughts::uci::dxp::{serve, DxpConnection};
+
+const PORT: u16 = 27531;
+const NODES: Nodes = 1_000_000;
+
+fn usage() -> ! {
+    eprintln!("Usage: dxp [sherlock|slonenok|randaap] [port] [nodes]");
+    process::exit(1);
+}
+
+pub fn main() {
+    let args: Vec<String> = env::args().collect();
+    let name = args
+        .get(1)
+        .map_or("sherlock", String::as_str)
+        .to_lowercase();
+    let port = args
+        .get(2)
+        .map_or(Ok(PORT), |port| port.parse())
+        .unwrap_or_else(|_| usage());
+    let nodes = args
+        .get(3)
+        .map_or(Ok(NODES), |nodes| nodes.parse())
+        .unwrap_or_else(|_| usage());
+    let mut engine: Box<dyn Engine<Item = EngineResult>> = match name.as_str() {
+        "sherlock" => Box::new(Sherlock::create(nodes)),
+        "slonenok" => Box::new(Slonenok::create(nodes)),
+        "randaap" => Box::new(RandAap::create(nodes)),
+        _ => usage(),
+    };
+    let display_name = engine.display_name().to_owned();
+
+    let listener = TcpListener::bind(("0.0.0.0", port)).unwrap_or_else(|e| {
+        eprintln!("Cannot listen on port {}: {}", port, e);
+        process::exit(1);
+    });
+    println!("{} listening on port {}", display_name, port);
+    for stream in listener.incoming() {
+        let result = stream
+            .map_err(|e| e.to_string())
+            .and_then(DxpConnection::create)
+            .and_then(|mut connection| serve(&mut connection, &mut *engine, &display_name));
+        if let Err(message) = result {
+            eprintln!("{}", message);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::env;
+use std::io::{self, BufReader};
+use std::process;
+
+use draughts::algorithm::meta::Nodes;
+use draughts::engine::giveaway::Giveaway;
+use draughts::engine::randaap::RandAap;
+use draughts::engine::sherlock::Sherlock;
+use draughts::engine::slonenok::Slonenok;
+use draughts::uci::hub::{run, EngineFactory, HubOptions, ServedEngine};
+
+pub fn main() {
+    let name = env::args()
+        .nth(1)
+        .unwrap_or_else(|| String::from("sherlock"));
+    let factory: Box<EngineFactory> = match name.to_lowercase().as_str() {
+        "sherlock" => Box::new(|options: &HubOptions| -> ServedEngine {
+            let mut engine = Sherlock::with_hash_size(Nodes::MAX, options.hash);
+            if let Some(ref tablebase) = options.tablebase {
+                engine.set_tablebase(tablebase.clone());
+            }
+            Box::new(engine)
+        }),
+        "slonenok" => Box::new(|options: &HubOptions| -> ServedEngine {
+            let mut engine = Slonenok::with_hash_size(Nodes::MAX, options.hash);
+            if let Some(ref tablebase) = options.tablebase {
+                engine.set_tablebase(tablebase.clone());
+            }
+            Box::new(engine)
+        }),
+        "randaap" => {
+            Box::new(|_: &HubOptions| -> ServedEngine { Box::new(RandAap::create(Nodes::MAX)) })
+        }
+        "giveaway" => Box::new(|options: &HubOptions| -> ServedEngine {
+            Box::new(Giveaway::with_hash_size(Nodes::MAX, options.hash))
+        }),
+        _ => {
+            eprintln!("Usage: hub [sherlock|slonenok|randaap|giveaway]");
+            process::exit(1);
+        }
+    };
+    run(&*factory, BufReader::new(io::stdin()), io::stdout());
+}
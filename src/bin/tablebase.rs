@@ -0,0 +1,42 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+use std::time::Instant;
+
+use draughts::tablebase::generate::generate;
+use draughts::tablebase::Table;
+
+const PIECES: usize = 4;
+
+fn usage() -> ! {
+    eprintln!("Usage: tablebase <directory> [pieces]");
+    process::exit(1);
+}
+
+pub fn main() {
+    let args: Vec<String> = env::args().collect();
+    let dir = Path::new(args.get(1).unwrap_or_else(|| usage()));
+    let pieces = args
+        .get(2)
+        .map_or(Ok(PIECES), |pieces| pieces.parse())
+        .unwrap_or_else(|_| usage());
+    fs::create_dir_all(dir).unwrap_or_else(|e| {
+        eprintln!("{}: {}", dir.display(), e);
+        process::exit(1);
+    });
+
+    let start = Instant::now();
+    let result = generate(dir, pieces, &mut |material| {
+        println!(
+            "{} {} positions ({:.1}s)",
+            Table::file_name(material),
+            material.size(),
+            start.elapsed().as_secs_f64()
+        );
+    });
+    if let Err(message) = result {
+        eprintln!("{}", message);
+        process::exit(1);
+    }
+}
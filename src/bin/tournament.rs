@@ -0,0 +1,227 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
+use std::time::Duration;
+
+use draughts::algorithm::meta::Nodes;
+use draughts::board::game::ScoreAdjudication;
+use draughts::board::generator::Generator;
+use draughts::book::{Book, Selection};
+use draughts::engine::book::BookEngine;
+use draughts::engine::randaap::RandAap;
+use draughts::engine::sherlock::Sherlock;
+use draughts::engine::slonenok::Slonenok;
+use draughts::engine::time::TimeControl;
+use draughts::pdn::writer::write_game;
+use draughts::tablebase::Tablebase;
+use draughts::tournament::stats::Sprt;
+use draughts::tournament::{parse_openings, Format, Tournament, TournamentEngine};
+use draughts::uci::external::{HubConfig, HubEngine};
+use draughts::uci::scan;
+
+const NODES: Nodes = 100_000;
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: tournament [-g] [-o openings] [-p games.pdn] [-r rounds] [-n nodes] [-t seconds] [-s elo0 elo1] [-a score plies] [-b tablebase] [-k book selection] engine...\n\
+         Engines: sherlock, slonenok, randaap, scan:<path>, hub:<path>\n\
+         sherlock:<weights> and slonenok:<weights> play with the weights in the text or json file\n\
+         -g plays a gauntlet of the first engine instead of a round robin\n\
+         -a adjudicates a win once both engines agree on the score for the given plies\n\
+         -b lets sherlock and slonenok probe the tablebase in the directory\n\
+         -k plays from the book, selecting best, weighted or within a score margin like 0.05"
+    );
+    process::exit(1);
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn selection(value: &str) -> Option<Selection> {
+    match value {
+        "best" => Some(Selection::Best),
+        "weighted" => Some(Selection::Weighted),
+        margin => margin.parse().ok().map(Selection::Margin),
+    }
+}
+
+// the engine with the name of its weights file, as in "Sherlock (tuned)"
+fn weights_name(engine: &str, path: &str) -> String {
+    let stem = Path::new(path)
+        .file_stem()
+        .map_or(path.into(), |stem| stem.to_string_lossy());
+    format!("{} ({})", engine, stem)
+}
+
+fn engine(
+    name: &str,
+    nodes: Nodes,
+    tablebase: &Option<Arc<Tablebase>>,
+) -> Result<TournamentEngine, String> {
+    Ok(match name.split_once(':') {
+        Some(("scan", path)) => Box::new(scan::create(Path::new(path), nodes)?),
+        Some(("hub", path)) => {
+            let mut engine = HubEngine::create(&HubConfig::create(path))?;
+            engine.set_max_nodes(nodes);
+            Box::new(engine)
+        }
+        Some(("sherlock", path)) => {
+            let mut engine = Sherlock::create(nodes);
+            engine.load_weights(Path::new(path))?;
+            engine.set_display_name(&weights_name("Sherlock", path));
+            if let Some(tablebase) = tablebase {
+                engine.set_tablebase(tablebase.clone());
+            }
+            Box::new(engine)
+        }
+        Some(("slonenok", path)) => {
+            let mut engine = Slonenok::create(nodes);
+            engine.load_weights(Path::new(path))?;
+            engine.set_display_name(&weights_name("Slonënok", path));
+            if let Some(tablebase) = tablebase {
+                engine.set_tablebase(tablebase.clone());
+            }
+            Box::new(engine)
+        }
+        _ => match name {
+            "sherlock" => {
+                let mut engine = Sherlock::create(nodes);
+                if let Some(tablebase) = tablebase {
+                    engine.set_tablebase(tablebase.clone());
+                }
+                Box::new(engine)
+            }
+            "slonenok" => {
+                let mut engine = Slonenok::create(nodes);
+                if let Some(tablebase) = tablebase {
+                    engine.set_tablebase(tablebase.clone());
+                }
+                Box::new(engine)
+            }
+            "randaap" => Box::new(RandAap::create(nodes)),
+            _ => return Err(format!("Unknown engine {}", name)),
+        },
+    })
+}
+
+pub fn main() {
+    let mut args = env::args().skip(1);
+    let mut format = Format::RoundRobin;
+    let mut openings = None;
+    let mut pdn = None;
+    let mut rounds = 1;
+    let mut nodes = NODES;
+    let mut time_control = TimeControl::Unlimited;
+    let mut sprt = None;
+    let mut adjudication = None;
+    let mut tablebase = None;
+    let mut book = None;
+    let mut names = vec![];
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "-g" => format = Format::Gauntlet,
+            "-o" => openings = Some(value()),
+            "-p" => pdn = Some(value()),
+            "-r" => rounds = value().parse().unwrap_or_else(|_| usage()),
+            "-n" => nodes = value().parse().unwrap_or_else(|_| usage()),
+            "-t" => {
+                let seconds: f64 = value().parse().unwrap_or_else(|_| usage());
+                time_control = TimeControl::PerMove(Duration::from_secs_f64(seconds));
+            }
+            "-s" => {
+                let elo0 = value().parse().unwrap_or_else(|_| usage());
+                let elo1 = value().parse().unwrap_or_else(|_| usage());
+                sprt = Some(Sprt::create(elo0, elo1));
+            }
+            "-a" => {
+                let win = value().parse().unwrap_or_else(|_| usage());
+                let plies = value().parse().unwrap_or_else(|_| usage());
+                adjudication = Some(ScoreAdjudication {
+                    win,
+                    draw: None,
+                    plies,
+                });
+            }
+            "-b" => {
+                let dir = value();
+                let loaded = Tablebase::load(Path::new(&dir)).unwrap_or_else(|e| fail(&e));
+                tablebase = Some(Arc::new(loaded));
+            }
+            "-k" => {
+                let path = value();
+                let loaded = Book::read(Path::new(&path)).unwrap_or_else(|e| fail(&e));
+                let selection = selection(&value()).unwrap_or_else(|| usage());
+                book = Some((Arc::new(loaded), selection));
+            }
+            // paths keep their case
+            name if !name.starts_with('-') => names.push(match name.split_once(':') {
+                Some((kind, path)) => format!("{}:{}", kind.to_lowercase(), path),
+                None => name.to_lowercase(),
+            }),
+            _ => usage(),
+        }
+    }
+    if names.len() < 2 {
+        usage();
+    }
+
+    let engines = names
+        .iter()
+        .map(|name| {
+            let engine = engine(name, nodes, &tablebase)?;
+            Ok(match book {
+                Some((ref book, selection)) => {
+                    Box::new(BookEngine::create(engine, book.clone(), selection))
+                }
+                None => engine,
+            })
+        })
+        .collect::<Result<Vec<TournamentEngine>, String>>()
+        .unwrap_or_else(|e| fail(&e));
+    let mut tournament = Tournament::create(engines, format);
+    tournament.set_rounds(rounds);
+    tournament.set_time_control(&time_control);
+    if let Some(sprt) = sprt {
+        tournament.set_sprt(sprt);
+    }
+    if let Some(adjudication) = adjudication {
+        tournament.set_adjudication(adjudication);
+    }
+    if let Some(path) = openings {
+        let text = fs::read_to_string(&path).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+        tournament.set_openings(parse_openings(&text).unwrap_or_else(|e| fail(&e)));
+    }
+    let mut pdn =
+        pdn.map(|path| File::create(&path).unwrap_or_else(|e| fail(&format!("{}: {}", path, e))));
+
+    let generator = Generator::create();
+    let result = tournament.run(&mut |tournament, record| {
+        let names = tournament.names();
+        println!(
+            "{:>4}. {} - {} {} ({})",
+            tournament.games().len() + 1,
+            names[record.white],
+            names[record.black],
+            record.outcome.pdn(),
+            record.rule.description()
+        );
+        if let Some(ref mut file) = pdn {
+            let text = write_game(&generator, &record.game).unwrap_or_else(|e| fail(&e));
+            writeln!(file, "{}", text).unwrap_or_else(|e| fail(&e.to_string()));
+        }
+    });
+
+    println!();
+    print!("{}", tournament.crosstable());
+    println!();
+    print!("{}", tournament.summary());
+    if sprt.is_some() {
+        println!("{:?}", result);
+    }
+}
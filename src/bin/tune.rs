@@ -0,0 +1,119 @@
+use std::env;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::process;
+
+use draughts::algorithm::tt::TranspositionTable;
+use draughts::board::generator::Generator;
+use draughts::engine::sherlock::SherlockJudge;
+use draughts::engine::slonenok::SlonenokJudge;
+use draughts::engine::weights::{self, Weights};
+use draughts::pdn::reader::PdnReader;
+use draughts::tuning::{
+    fit_scale, game_samples, loss, read_samples, resolve, tune, Sample, Tunable,
+};
+
+const SKIP: usize = 10;
+const PASSES: usize = 100;
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: tune [-e sherlock|slonenok] [-s skip plies] [-p passes] [-w weights] <tuned weights> <samples>...\n\
+         Samples are games in .pdn files or lines of a pdn fen and a result like \"W:W31,32:B19 2-0\"\n\
+         -s skips the opening plies of the games\n\
+         -w starts from the weights in the file instead of the defaults\n\
+         The tuned weights are written as json when the file name ends in .json"
+    );
+    process::exit(1);
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn read(path: &str, skip: usize) -> Result<Vec<Sample>, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    if !path.ends_with(".pdn") {
+        return read_samples(BufReader::new(file)).map_err(|e| format!("{}: {}", path, e));
+    }
+    let mut samples = vec![];
+    for game in PdnReader::create(BufReader::new(file)) {
+        let game = game.map_err(|e| format!("{}: {}", path, e))?;
+        samples.extend(game_samples(&game, skip)?);
+    }
+    Ok(samples)
+}
+
+// the defaults unless a file is given
+fn start<W: Weights>(path: Option<&str>) -> W {
+    path.map_or_else(W::default, |path| {
+        weights::load(Path::new(path)).unwrap_or_else(|e| fail(&e))
+    })
+}
+
+fn run<J: Tunable>(judge: &mut J, samples: &[Sample], passes: usize, out: &Path) {
+    let samples = resolve(judge, samples);
+    println!("{} quiet positions", samples.len());
+    let scale = fit_scale(judge, &samples);
+    println!(
+        "scale {:.4}, loss {:.6}",
+        scale,
+        loss(judge, &samples, scale)
+    );
+    // saves after every pass, since a pass can take long
+    tune(judge, &samples, scale, passes, |pass, loss, tuned| {
+        println!("pass {}: loss {:.6}", pass, loss);
+        weights::save(tuned, out).unwrap_or_else(|e| fail(&e));
+    });
+    weights::save(judge.weights(), out).unwrap_or_else(|e| fail(&e));
+    for (name, value) in J::Weights::names().iter().zip(judge.weights().values()) {
+        println!("{} {}", name, value);
+    }
+}
+
+pub fn main() {
+    let mut args = env::args().skip(1);
+    let mut engine = String::from("sherlock");
+    let mut skip = SKIP;
+    let mut passes = PASSES;
+    let mut start_path = None;
+    let mut paths = vec![];
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "-e" => engine = value().to_lowercase(),
+            "-s" => skip = value().parse().unwrap_or_else(|_| usage()),
+            "-p" => passes = value().parse().unwrap_or_else(|_| usage()),
+            "-w" => start_path = Some(value()),
+            path if !path.starts_with('-') => paths.push(path.to_owned()),
+            _ => usage(),
+        }
+    }
+    if paths.len() < 2 {
+        usage();
+    }
+    let out = Path::new(&paths[0]);
+
+    let mut samples = vec![];
+    for path in &paths[1..] {
+        samples.extend(read(path, skip).unwrap_or_else(|e| fail(&e)));
+    }
+    println!("{} positions", samples.len());
+
+    match engine.as_str() {
+        "sherlock" => {
+            let table = TranspositionTable::create(1).into();
+            let weights = start(start_path.as_deref());
+            let mut judge = SherlockJudge::with_table(Generator::create(), table, weights);
+            run(&mut judge, &samples, passes, out);
+        }
+        "slonenok" => {
+            let weights = start(start_path.as_deref());
+            let mut judge = SlonenokJudge::with_hash_size(Generator::create(), 1, weights);
+            run(&mut judge, &samples, passes, out);
+        }
+        _ => usage(),
+    }
+}
@@ -1,4 +1,4 @@
-use super::position::Field;
+use super::position::{Field, Size};
 
 pub type Coord = i8;
 
@@ -9,41 +9,63 @@ pub struct Coords {
 }
 
 pub trait MinXy {
-    fn min_x(&self) -> Coord;
-    fn max_x(&self) -> Coord;
-    fn min_y(&self) -> Coord;
-    fn max_y(&self) -> Coord;
+    fn min_x(&self, size: Size) -> Coord;
+    fn max_x(&self, size: Size) -> Coord;
+    fn min_y(&self, size: Size) -> Coord;
+    fn max_y(&self, size: Size) -> Coord;
+}
+
+fn rows(size: Size) -> Coord {
+    size.rows() as Coord
 }
 
 fn min_x(y: Coord) -> Coord {
     y.abs()
 }
-fn max_x(y: Coord) -> Coord {
-    9 - y.abs()
+fn max_x(size: Size, y: Coord) -> Coord {
+    rows(size) - 1 - y.abs()
 }
-fn min_y(x: Coord) -> Coord {
-    -max_y(x)
+fn min_y(size: Size, x: Coord) -> Coord {
+    -max_y(size, x)
 }
-fn max_y(x: Coord) -> Coord {
-    if x > 4 {
-        9 - x
+fn max_y(size: Size, x: Coord) -> Coord {
+    if x > rows(size) / 2 - 1 {
+        rows(size) - 1 - x
     } else {
         x
     }
 }
 
 impl MinXy for Coords {
-    fn min_x(&self) -> Coord {
+    fn min_x(&self, _size: Size) -> Coord {
         min_x(self.y)
     }
-    fn max_x(&self) -> Coord {
-        max_x(self.y)
+    fn max_x(&self, size: Size) -> Coord {
+        max_x(size, self.y)
     }
-    fn min_y(&self) -> Coord {
-        min_y(self.x)
+    fn min_y(&self, size: Size) -> Coord {
+        min_y(size, self.x)
     }
-    fn max_y(&self) -> Coord {
-        max_y(self.x)
+    fn max_y(&self, size: Size) -> Coord {
+        max_y(size, self.x)
+    }
+}
+
+impl Coords {
+    pub fn on(size: Size, n: Field) -> Coords {
+        let n = n as Coord;
+        let per_row = rows(size) / 2;
+        let ny = (per_row * rows(size) - 1 - n) / per_row; // rows from bottom
+        let nx = (ny % 2) + (2 * (n % per_row)); // columns from left
+        Coords {
+            x: (nx + ny) / 2,
+            y: (ny - nx) / 2,
+        }
+    }
+
+    pub fn field_on(&self, size: Size) -> Field {
+        let per_row = rows(size) / 2;
+        ((rows(size) - 1 - self.x - self.y) * per_row + (self.x - self.y) / 2) as Field
     }
 }
 
@@ -63,7 +85,7 @@ fn partial_eq() {
 
 impl From<Coords> for Field {
     fn from(c: Coords) -> Field {
-        (45 - (5 * (c.x + c.y)) - ((c.y - c.x) / 2)) as Field
+        c.field_on(Size::Ten)
     }
 }
 
@@ -79,13 +101,7 @@ fn into_field() {
 
 impl From<Field> for Coords {
     fn from(n: Field) -> Coords {
-        let n = n as Coord;
-        let ny = (49 - n) / 5; // rows from bottom
-        let nx = (ny % 2) + (2 * (n % 5)); // columns from left
-        Coords {
-            x: (nx + ny) / 2,
-            y: (ny - nx) / 2,
-        }
+        Coords::on(Size::Ten, n)
     }
 }
 
@@ -105,13 +121,30 @@ fn min_max() {
     assert_eq!(min_x(-4), 4);
     assert_eq!(min_x(0), 0);
     assert_eq!(min_x(1), 1);
-    assert_eq!(max_x(-4), 5);
-    assert_eq!(max_x(0), 9);
-    assert_eq!(max_x(1), 8);
-    assert_eq!(min_y(0), 0);
-    assert_eq!(min_y(1), -1);
-    assert_eq!(min_y(5), -4);
-    assert_eq!(max_y(0), 0);
-    assert_eq!(max_y(1), 1);
-    assert_eq!(max_y(5), 4);
+    assert_eq!(max_x(Size::Ten, -4), 5);
+    assert_eq!(max_x(Size::Ten, 0), 9);
+    assert_eq!(max_x(Size::Ten, 1), 8);
+    assert_eq!(min_y(Size::Ten, 0), 0);
+    assert_eq!(min_y(Size::Ten, 1), -1);
+    assert_eq!(min_y(Size::Ten, 5), -4);
+    assert_eq!(max_y(Size::Ten, 0), 0);
+    assert_eq!(max_y(Size::Ten, 1), 1);
+    assert_eq!(max_y(Size::Ten, 5), 4);
+    assert_eq!(max_x(Size::Eight, 0), 7);
+    assert_eq!(max_y(Size::Eight, 4), 3);
+}
+
+#[test]
+fn eight_by_eight() {
+    assert_eq!(Coords::on(Size::Eight, 28), Coords { x: 0, y: 0 });
+    assert_eq!(Coords::on(Size::Eight, 0), Coords { x: 4, y: 3 });
+    assert_eq!(Coords::on(Size::Eight, 3), Coords { x: 7, y: 0 });
+    assert_eq!(Coords::on(Size::Eight, 31), Coords { x: 3, y: -3 });
+    for field in 0..32 {
+        let coords = Coords::on(Size::Eight, field);
+        assert_eq!(coords.field_on(Size::Eight), field);
+        assert!(coords.x <= coords.max_x(Size::Eight));
+        assert!(coords.y <= coords.max_y(Size::Eight));
+        assert!(coords.y >= coords.min_y(Size::Eight));
+    }
 }
@@ -1,5 +1,5 @@
 use super::piece::{Color, BLACK_KING, BLACK_MAN, EMPTY, WHITE_KING, WHITE_MAN};
-use super::position::{Field, Position};
+use super::position::{Field, Position, Size};
 
 pub type DecimalData = [u8; 16];
 
@@ -11,12 +11,22 @@ const CENTER_PIECES: [u8; 5] = [EMPTY, WHITE_MAN, WHITE_KING, BLACK_MAN, BLACK_K
 const BOTTOM_PIECES: [u8; 5] = [EMPTY, WHITE_MAN, WHITE_KING, BLACK_KING, EMPTY];
 const BIT_COUNT: [usize; 10] = [10, 12, 12, 12, 12, 12, 12, 12, 12, 10];
 const BIT_START: [usize; 10] = [2, 20, 32, 44, 56, 68, 80, 92, 104, 116];
+// rows of four need 8 bits on the sides and 10 in between, each reaching into a second byte
+const EIGHT_BIT_COUNT: [usize; 8] = [8, 10, 10, 10, 10, 10, 10, 8];
+const EIGHT_BIT_START: [usize; 8] = [2, 20, 30, 40, 50, 60, 70, 82];
 const BIT_MASK: [u8; 9] = [0x0, 0x1, 0x3, 0x7, 0xf, 0x1f, 0x3f, 0x7f, 0xff];
 
 fn endian(i: usize) -> usize {
     15 - i
 }
 
+fn layout(size: Size) -> (&'static [usize], &'static [usize]) {
+    match size {
+        Size::Ten => (&BIT_COUNT, &BIT_START),
+        Size::Eight => (&EIGHT_BIT_COUNT, &EIGHT_BIT_START),
+    }
+}
+
 pub fn to_decimal(position: &Position) -> DecimalData {
     let sign = if position.side_to_move() == Color::White {
         0
@@ -28,14 +38,16 @@ pub fn to_decimal(position: &Position) -> DecimalData {
     decimal[endian(2)] = 0x40;
     decimal[endian(15)] = 0x01;
 
-    let rows = (0..50)
-        .step_by(5)
+    let size = position.size();
+    let (bit_count, bit_start) = layout(size);
+    let rows = (0..size.fields())
+        .step_by(size.per_row())
         .map(|start| to_decimal_row(position, start))
         .enumerate();
     for (i, row) in rows {
-        let bit_start = BIT_START[i];
+        let bit_start = bit_start[i];
         let higher_start = bit_start % 8;
-        let lower_bits = BIT_COUNT[i] + higher_start - 8;
+        let lower_bits = bit_count[i] + higher_start - 8;
         decimal[endian(bit_start / 8)] |= (row >> lower_bits) as u8;
         decimal[endian(bit_start / 8 + 1)] |= (row << (8 - lower_bits)) as u8;
     }
@@ -44,32 +56,42 @@ pub fn to_decimal(position: &Position) -> DecimalData {
 }
 
 fn to_decimal_row(position: &Position, start: Field) -> u16 {
-    let (p, pieces) = match start {
-        0 => (4, TOP_PLACES),
-        5..=40 if start % 5 == 0 => (5, CENTER_PLACES),
-        45 => (4, BOTTOM_PLACES),
-        _ => unreachable!(),
+    let size = position.size();
+    let per_row = size.per_row();
+    let (p, pieces) = if start == 0 {
+        (4, TOP_PLACES)
+    } else if start == size.fields() - per_row {
+        (4, BOTTOM_PLACES)
+    } else {
+        (5, CENTER_PLACES)
     };
-    (start..start + 5).fold(0u16, |a, field| {
+    (start..start + per_row).fold(0u16, |a, field| {
         p * a + pieces[position.piece_at(field) as usize]
     })
 }
 
 pub fn to_position(decimal: &DecimalData) -> Position {
-    let mut position = Position::create();
+    to_position_with_size(decimal, Size::Ten)
+}
+
+// the encoding does not tell the size of the board
+pub fn to_position_with_size(decimal: &DecimalData, size: Size) -> Position {
+    let mut position = Position::with_size(size);
     if decimal[endian(0)] >= 0x80 {
         position = position.toggle_side();
     }
 
-    for i in 0..10 {
-        let (p, pieces) = match i {
-            0 => (4, TOP_PIECES),
-            1..=8 => (5, CENTER_PIECES),
-            9 => (4, BOTTOM_PIECES),
-            _ => unreachable!(),
+    let (rows, per_row) = (size.rows(), size.per_row());
+    for i in 0..rows {
+        let (p, pieces) = if i == 0 {
+            (4, TOP_PIECES)
+        } else if i == rows - 1 {
+            (4, BOTTOM_PIECES)
+        } else {
+            (5, CENTER_PIECES)
         };
-        let mut row = from_decimal_row(decimal, i);
-        for field in (0..5).map(|j| 5 * i + j).rev() {
+        let mut row = from_decimal_row(decimal, size, i);
+        for field in (0..per_row).map(|j| per_row * i + j).rev() {
             position = position.put_piece(field, pieces[row % p]);
             row /= p;
         }
@@ -78,11 +100,12 @@ pub fn to_position(decimal: &DecimalData) -> Position {
     position
 }
 
-fn from_decimal_row(decimal: &DecimalData, i: usize) -> usize {
-    let bit_start = BIT_START[i];
+fn from_decimal_row(decimal: &DecimalData, size: Size, i: usize) -> usize {
+    let (bit_count, bit_start) = layout(size);
+    let bit_start = bit_start[i];
     let higher_start = bit_start % 8;
     let higher_bits = 8 - higher_start;
-    let lower_bits = BIT_COUNT[i] - higher_bits;
+    let lower_bits = bit_count[i] - higher_bits;
     let lower_left = 8 - lower_bits;
 
     let higher = ((decimal[endian(bit_start / 8)] & BIT_MASK[higher_bits]) as usize) << lower_bits;
@@ -102,6 +125,7 @@ fn to_decimal_test() {
 mod test {
     use super::super::generator::Generator;
     use super::super::mv::Move;
+    use super::super::rules::Rules;
     use super::*;
     use decimal::d128;
 
@@ -113,9 +137,9 @@ mod test {
         assert!(initial == p);
     }
 
-    fn moving_roundtrip_test(pick: &dyn Fn(&[Move]) -> &Move) {
-        let generator = Generator::create();
-        let mut position = Position::initial();
+    fn moving_roundtrip_test(rules: Rules, pick: &dyn Fn(&[Move]) -> &Move) {
+        let generator = Generator::with_rules(rules);
+        let mut position = rules.initial();
         let mut i = 0;
         loop {
             let d = unsafe { d128::from_raw_bytes(to_decimal(&position)) };
@@ -132,18 +156,28 @@ mod test {
 
             position = position.go(pick(&moves));
             let d = to_decimal(&position);
-            let p = to_position(&d);
+            let p = to_position_with_size(&d, rules.size);
             assert!(position == p);
         }
     }
 
     #[test]
     fn moving_roundtrip_first_test() {
-        moving_roundtrip_test(&|moves: &[Move]| &moves[0])
+        moving_roundtrip_test(Rules::international(), &|moves: &[Move]| &moves[0])
     }
 
     #[test]
     fn moving_roundtrip_last_test() {
-        moving_roundtrip_test(&|moves: &[Move]| &moves[moves.len() - 1])
+        moving_roundtrip_test(Rules::international(), &|moves: &[Move]| {
+            &moves[moves.len() - 1]
+        })
+    }
+
+    #[test]
+    fn eight_roundtrip_test() {
+        moving_roundtrip_test(Rules::russian(), &|moves: &[Move]| &moves[0]);
+        moving_roundtrip_test(Rules::brazilian(), &|moves: &[Move]| {
+            &moves[moves.len() - 1]
+        });
     }
 }
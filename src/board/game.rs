@@ -0,0 +1,483 @@
+use super::generator::Generator;
+use super::mv::Move;
+use super::piece::{Color, BLACK_KING, BLACK_MAN, WHITE_KING, WHITE_MAN};
+use super::position::{Position, Size};
+use super::rules::Rules;
+use crate::algorithm::judge::Eval;
+use crate::tablebase::Tablebase;
+
+const REPETITIONS: usize = 3;
+const KING_MOVES: usize = 25;
+const SMALL_ENDGAME_MOVES: usize = 16;
+const TINY_ENDGAME_MOVES: usize = 5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    WhiteWins,
+    Draw,
+    BlackWins,
+}
+
+impl Outcome {
+    pub fn pdn(&self) -> &'static str {
+        match self {
+            Outcome::WhiteWins => "2-0",
+            Outcome::Draw => "1-1",
+            Outcome::BlackWins => "0-2",
+        }
+    }
+
+    // also accepts the chess style results some databases use
+    pub fn parse_pdn(result: &str) -> Option<Outcome> {
+        match result {
+            "2-0" | "1-0" => Some(Outcome::WhiteWins),
+            "1-1" | "1/2-1/2" => Some(Outcome::Draw),
+            "0-2" | "0-1" => Some(Outcome::BlackWins),
+            _ => None,
+        }
+    }
+
+    pub fn loss(color: Color) -> Outcome {
+        match color {
+            Color::White => Outcome::BlackWins,
+            Color::Black => Outcome::WhiteWins,
+        }
+    }
+
+    pub fn win(color: Color) -> Outcome {
+        match color {
+            Color::White => Outcome::WhiteWins,
+            Color::Black => Outcome::BlackWins,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rule {
+    NoMoves,
+    // no answer or an illegal move
+    Forfeit,
+    Repetition,
+    // 25 moves each with only kings, without captures
+    KingMoves,
+    // 16 moves each for three pieces against a lone king, 5 for two pieces or fewer
+    SmallEndgame,
+    MoveLimit,
+    Score,
+    Tablebase,
+}
+
+impl Rule {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Rule::NoMoves => "no moves left",
+            Rule::Forfeit => "forfeit",
+            Rule::Repetition => "threefold repetition",
+            Rule::KingMoves => "25 king moves",
+            Rule::SmallEndgame => "small endgame",
+            Rule::MoveLimit => "move limit",
+            Rule::Score => "adjudicated by score",
+            Rule::Tablebase => "adjudicated by tablebase",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GameResult {
+    pub outcome: Outcome,
+    pub rule: Rule,
+}
+
+// scores are reported by the side to move
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ScoreAdjudication {
+    pub win: Eval,
+    pub draw: Option<Eval>,
+    pub plies: usize,
+}
+
+pub type Probe = dyn Fn(&Position) -> Option<Outcome>;
+
+type Material = [usize; 5];
+
+fn material(position: &Position) -> Material {
+    let mut material = [0; 5];
+    for field in 0..position.size().fields() {
+        material[position.piece_at(field) as usize] += 1;
+    }
+    material
+}
+
+// the number of moves each side may still play once the material arises
+fn endgame_moves(material: &Material) -> Option<usize> {
+    let white = material[WHITE_MAN as usize] + material[WHITE_KING as usize];
+    let black = material[BLACK_MAN as usize] + material[BLACK_KING as usize];
+    let (pieces, kings) = if white == 1 && material[WHITE_KING as usize] == 1 {
+        (black, material[BLACK_KING as usize])
+    } else if black == 1 && material[BLACK_KING as usize] == 1 {
+        (white, material[WHITE_KING as usize])
+    } else {
+        return None;
+    };
+    match pieces {
+        _ if kings == 0 => None,
+        3 => Some(SMALL_ENDGAME_MOVES),
+        1 | 2 => Some(TINY_ENDGAME_MOVES),
+        _ => None,
+    }
+}
+
+pub struct Game {
+    generator: Generator,
+    positions: Vec<Position>,
+    moves: Vec<Move>,
+    // from white's point of view
+    scores: Vec<Option<Eval>>,
+    king_plies: usize,
+    endgame_plies: usize,
+    move_limit: Option<usize>,
+    adjudication: Option<ScoreAdjudication>,
+    tablebase: Option<Box<Probe>>,
+    result: Option<GameResult>,
+}
+
+impl Game {
+    pub fn create(start: &Position) -> Game {
+        Game::with_rules(start, Rules::international())
+    }
+
+    pub fn with_rules(start: &Position, rules: Rules) -> Game {
+        let mut game = Game {
+            generator: Generator::with_rules(rules),
+            positions: vec![*start],
+            moves: vec![],
+            scores: vec![],
+            king_plies: 0,
+            endgame_plies: 0,
+            move_limit: None,
+            adjudication: None,
+            tablebase: None,
+            result: None,
+        };
+        game.check_no_moves();
+        game
+    }
+
+    // a draw after this many moves each
+    pub fn set_move_limit(&mut self, moves: usize) {
+        self.move_limit = Some(moves);
+    }
+
+    pub fn set_adjudication(&mut self, adjudication: ScoreAdjudication) {
+        self.adjudication = Some(adjudication);
+    }
+
+    pub fn set_tablebase(&mut self, tablebase: Box<Probe>) {
+        self.tablebase = Some(tablebase);
+        self.check_tablebase();
+    }
+
+    pub fn start(&self) -> &Position {
+        &self.positions[0]
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.positions[self.positions.len() - 1]
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn result(&self) -> Option<GameResult> {
+        self.result
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        self.generator.legal_moves(self.position())
+    }
+
+    pub fn play(&mut self, mv: &Move) -> Result<Option<GameResult>, String> {
+        self.play_scored(mv, None)
+    }
+
+    // the evaluation is from the point of view of the side making the move
+    pub fn play_scored(
+        &mut self,
+        mv: &Move,
+        evaluation: Option<Eval>,
+    ) -> Result<Option<GameResult>, String> {
+        if self.result.is_some() {
+            return Err(String::from("The game is over"));
+        }
+        let position = *self.position();
+        if !self.legal_moves().contains(mv) {
+            return Err(format!("Illegal move {}", mv));
+        }
+
+        let piece = position.piece_at(mv.from());
+        if mv.num_taken() == 0 && (piece == WHITE_KING || piece == BLACK_KING) {
+            self.king_plies += 1;
+        } else {
+            self.king_plies = 0;
+        }
+        let next = position.go(mv);
+        if material(&next) == material(&position) {
+            self.endgame_plies += 1;
+        } else {
+            self.endgame_plies = 0;
+        }
+        let score = evaluation.map(|evaluation| match position.side_to_move() {
+            Color::White => evaluation,
+            Color::Black => -evaluation,
+        });
+
+        self.positions.push(next);
+        self.moves.push(*mv);
+        self.scores.push(score);
+
+        self.check_no_moves();
+        self.check_draws();
+        self.check_tablebase();
+        self.check_scores();
+        Ok(self.result)
+    }
+
+    // the side to move loses, unless the game is already over
+    pub fn forfeit(&mut self) -> GameResult {
+        self.end(Outcome::loss(self.position().side_to_move()), Rule::Forfeit);
+        self.result.unwrap()
+    }
+
+    fn end(&mut self, outcome: Outcome, rule: Rule) {
+        if self.result.is_none() {
+            self.result = Some(GameResult { outcome, rule });
+        }
+    }
+
+    fn check_no_moves(&mut self) {
+        if self.legal_moves().is_empty() {
+            let side = self.position().side_to_move();
+            let outcome = if self.generator.rules().losing {
+                Outcome::win(side)
+            } else {
+                Outcome::loss(side)
+            };
+            self.end(outcome, Rule::NoMoves);
+        }
+    }
+
+    fn check_draws(&mut self) {
+        let position = *self.position();
+        let repetitions = self.positions.iter().filter(|p| **p == position).count();
+        if repetitions >= REPETITIONS {
+            self.end(Outcome::Draw, Rule::Repetition);
+        }
+        // the king moves and endgame rules are those of the international game
+        let rules = self.generator.rules();
+        if !rules.losing && rules.size == Size::Ten {
+            self.check_king_moves(&position);
+        }
+        if self
+            .move_limit
+            .is_some_and(|moves| self.moves.len() >= 2 * moves)
+        {
+            self.end(Outcome::Draw, Rule::MoveLimit);
+        }
+    }
+
+    fn check_king_moves(&mut self, position: &Position) {
+        // the endgame rules supersede the king moves rule
+        match endgame_moves(&material(position)) {
+            Some(moves) => {
+                if self.endgame_plies >= 2 * moves {
+                    self.end(Outcome::Draw, Rule::SmallEndgame);
+                }
+            }
+            None => {
+                if self.king_plies >= 2 * KING_MOVES {
+                    self.end(Outcome::Draw, Rule::KingMoves);
+                }
+            }
+        }
+    }
+
+    fn check_tablebase(&mut self) {
+        if !Tablebase::solves(self.generator.rules()) {
+            return;
+        }
+        if let Some(outcome) = self
+            .tablebase
+            .as_ref()
+            .and_then(|probe| probe(self.position()))
+        {
+            self.end(outcome, Rule::Tablebase);
+        }
+    }
+
+    fn check_scores(&mut self) {
+        let adjudication = match self.adjudication {
+            Some(adjudication) if adjudication.plies > 0 => adjudication,
+            _ => return,
+        };
+        if self.scores.len() < adjudication.plies {
+            return;
+        }
+        let recent = &self.scores[self.scores.len() - adjudication.plies..];
+        if recent
+            .iter()
+            .all(|score| score.is_some_and(|s| s >= adjudication.win))
+        {
+            self.end(Outcome::WhiteWins, Rule::Score);
+        } else if recent
+            .iter()
+            .all(|score| score.is_some_and(|s| s <= -adjudication.win))
+        {
+            self.end(Outcome::BlackWins, Rule::Score);
+        } else if let Some(draw) = adjudication.draw {
+            if recent
+                .iter()
+                .all(|score| score.is_some_and(|s| s.abs() <= draw))
+            {
+                self.end(Outcome::Draw, Rule::Score);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn play(game: &mut Game, moves: &str) -> Option<GameResult> {
+        let generator = Generator::create();
+        let mut result = None;
+        for notation in moves.split_whitespace() {
+            let mv = generator.parse_move(game.position(), notation).unwrap();
+            result = game.play(&mv).unwrap();
+        }
+        result
+    }
+
+    #[test]
+    fn no_moves() {
+        let game = Game::create(&Position::create().put_piece(0, BLACK_MAN));
+        assert_eq!(
+            game.result(),
+            Some(GameResult {
+                outcome: Outcome::BlackWins,
+                rule: Rule::NoMoves
+            })
+        );
+
+        let mut game = Game::create(&Position::parse_pdn("W:W28:B22").unwrap());
+        let result = play(&mut game, "28x17").unwrap();
+        assert_eq!(result.outcome, Outcome::WhiteWins);
+        assert_eq!(result.rule, Rule::NoMoves);
+        assert!(game.play(&Move::null()).is_err());
+
+        // giving the last piece away wins antidraughts
+        let position = Position::parse_pdn("W:W28:B22").unwrap();
+        let mut game = Game::with_rules(&position, Rules::antidraughts());
+        let result = play(&mut game, "28x17").unwrap();
+        assert_eq!(result.outcome, Outcome::BlackWins);
+    }
+
+    #[test]
+    fn repetition() {
+        let position = Position::parse_pdn("W:WK46,K50:BK1,K3").unwrap();
+        let mut game = Game::create(&position);
+        assert!(play(&mut game, "46-41 1-7 41-46 7-1 46-41 1-7").is_none());
+        let result = play(&mut game, "41-46 7-1").unwrap();
+        assert_eq!(result.outcome, Outcome::Draw);
+        assert_eq!(result.rule, Rule::Repetition);
+        assert_eq!(game.moves().len(), 8);
+    }
+
+    #[test]
+    fn king_moves() {
+        let position = Position::parse_pdn("W:WK46,31:BK5,20").unwrap();
+        let mut game = Game::create(&position);
+        game.king_plies = 2 * KING_MOVES - 1;
+        let result = play(&mut game, "46-41").unwrap();
+        assert_eq!(result.outcome, Outcome::Draw);
+        assert_eq!(result.rule, Rule::KingMoves);
+
+        // a man move resets the count
+        let mut game = Game::create(&position);
+        game.king_plies = 2 * KING_MOVES - 1;
+        assert!(play(&mut game, "31-27").is_none());
+        assert_eq!(game.king_plies, 0);
+    }
+
+    #[test]
+    fn small_endgame() {
+        let position = Position::parse_pdn("W:WK46,K50,K45:BK5").unwrap();
+        let mut game = Game::create(&position);
+        game.endgame_plies = 2 * SMALL_ENDGAME_MOVES - 1;
+        let result = play(&mut game, "46-41").unwrap();
+        assert_eq!(result.outcome, Outcome::Draw);
+        assert_eq!(result.rule, Rule::SmallEndgame);
+
+        let tiny = Position::parse_pdn("B:WK46,K50:BK5").unwrap();
+        assert_eq!(endgame_moves(&material(&tiny)), Some(TINY_ENDGAME_MOVES));
+        let men = Position::parse_pdn("B:W46,47:BK5").unwrap();
+        assert_eq!(endgame_moves(&material(&men)), None);
+    }
+
+    #[test]
+    fn adjudication() {
+        let mut game = Game::create(&Position::initial());
+        game.set_adjudication(ScoreAdjudication {
+            win: 500,
+            draw: None,
+            plies: 2,
+        });
+        let moves = game.legal_moves();
+        assert!(game.play_scored(&moves[0], Some(600)).unwrap().is_none());
+        let moves = game.legal_moves();
+        let result = game.play_scored(&moves[0], Some(-600)).unwrap().unwrap();
+        assert_eq!(result.outcome, Outcome::WhiteWins);
+        assert_eq!(result.rule, Rule::Score);
+
+        let mut game = Game::create(&Position::initial());
+        game.set_tablebase(Box::new(|position: &Position| {
+            if position.side_to_move() == Color::Black {
+                Some(Outcome::Draw)
+            } else {
+                None
+            }
+        }));
+        assert!(game.result().is_none());
+        let moves = game.legal_moves();
+        assert_eq!(game.play(&moves[0]).unwrap().unwrap().rule, Rule::Tablebase);
+
+        let mut game = Game::create(&Position::initial());
+        game.set_move_limit(1);
+        assert!(play(&mut game, "32-28").is_none());
+        assert_eq!(play(&mut game, "19-23").unwrap().rule, Rule::MoveLimit);
+        assert_eq!(game.forfeit().rule, Rule::MoveLimit);
+    }
+
+    #[test]
+    fn other_rules() {
+        // no king moves rule or tablebase when giving pieces away
+        let position = Position::parse_pdn("W:WK46,31:BK5,20").unwrap();
+        let mut game = Game::with_rules(&position, Rules::antidraughts());
+        game.set_tablebase(Box::new(|_: &Position| Some(Outcome::Draw)));
+        assert!(game.result().is_none());
+        game.king_plies = 2 * KING_MOVES - 1;
+        assert!(play(&mut game, "46-41").is_none());
+
+        let position = Position::parse_pdn("W:WK46,K50,K45:BK5").unwrap();
+        let mut game = Game::with_rules(&position, Rules::antidraughts());
+        game.endgame_plies = 2 * SMALL_ENDGAME_MOVES - 1;
+        assert!(play(&mut game, "46-41").is_none());
+
+        // the killer game keeps them
+        let position = Position::parse_pdn("W:WK46,31:BK5,20").unwrap();
+        let mut game = Game::with_rules(&position, Rules::killer());
+        game.king_plies = 2 * KING_MOVES - 1;
+        assert_eq!(play(&mut game, "46-41").unwrap().rule, Rule::KingMoves);
+    }
+}
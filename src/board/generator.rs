@@ -1,24 +1,35 @@
-use super::mv::Move;
+use super::mv::{Captures, Move};
 use super::piece::Color::{Black, White};
 use super::piece::{
     piece_is, piece_own, Color, BLACK_KING, BLACK_MAN, EMPTY, WHITE_KING, WHITE_MAN,
 };
-use super::position::{Field, Position};
+use super::position::{promote, Field, Position};
+use super::rules::{Majority, Rules};
 use super::steps::Steps;
 
 #[derive(Clone)]
 pub struct Generator {
+    rules: Rules,
     steps: Steps,
 }
 
 impl Generator {
     pub fn create() -> Generator {
+        Generator::with_rules(Rules::international())
+    }
+
+    pub fn with_rules(rules: Rules) -> Generator {
         Generator {
-            steps: Steps::create(),
+            rules,
+            steps: Steps::create(rules.size),
         }
     }
 
-    fn trim_list(list: &mut Vec<Move>) {
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    fn trim_list(&self, list: &mut Vec<Move>) {
         if list.is_empty() {
             return;
         }
@@ -28,9 +39,14 @@ impl Generator {
             return;
         }
 
+        // under a free majority any capture will do
+        let required = match self.rules.majority {
+            Majority::Most => num_taken,
+            Majority::Free => 1,
+        };
         let mut i = 0;
         while i < list.len() {
-            if list[i].num_taken() < num_taken {
+            if list[i].num_taken() < required {
                 list.swap_remove(i);
             } else {
                 i += 1;
@@ -44,21 +60,51 @@ impl Generator {
         list.dedup();
     }
 
+    fn short_jumps(&self, field: Field, king: bool, color_to_capture: &Color) -> &[(Field, Field)] {
+        if king || self.rules.men_capture_backwards {
+            self.steps.short_jumps(field)
+        } else if *color_to_capture == Black {
+            self.steps.white_jumps(field)
+        } else {
+            self.steps.black_jumps(field)
+        }
+    }
+
+    fn promotes(&self, field: Field, color_to_capture: &Color) -> bool {
+        let man = if *color_to_capture == Black {
+            WHITE_MAN
+        } else {
+            BLACK_MAN
+        };
+        promote(self.rules.size, field, man) != man
+    }
+
     fn explode_jump(
         &self,
         position: &Position,
         mv: Move,
+        king: bool,
         color_to_capture: &Color,
         list: &mut Vec<Move>,
     ) {
+        if !king && self.rules.promote_during_capture && self.promotes(mv.to(), color_to_capture) {
+            return self.explode_king_jump(position, mv.crown(), color_to_capture, list);
+        }
+
         let mut exploded = false;
-        for &(via, to) in self.steps.short_jumps(mv.to()) {
+        for &(via, to) in self.short_jumps(mv.to(), king, color_to_capture) {
             if piece_is(position.piece_at(via), color_to_capture)
                 && position.is_empty(to)
                 && !mv.goes_via(via)
             {
                 exploded = true;
-                self.explode_jump(position, mv.take_more(via, to), color_to_capture, list);
+                self.explode_jump(
+                    position,
+                    mv.take_more(via, to),
+                    king,
+                    color_to_capture,
+                    list,
+                );
             }
         }
 
@@ -67,6 +113,20 @@ impl Generator {
         }
     }
 
+    fn explode_king_jump(
+        &self,
+        position: &Position,
+        mv: Move,
+        color_to_capture: &Color,
+        list: &mut Vec<Move>,
+    ) {
+        if self.rules.flying_kings {
+            self.explode_long_jump(position, mv, color_to_capture, list);
+        } else {
+            self.explode_jump(position, mv, true, color_to_capture, list);
+        }
+    }
+
     fn add_short_jumps(
         &self,
         position: &Position,
@@ -75,13 +135,15 @@ impl Generator {
         color_to_capture: &Color,
     ) -> bool {
         let mut captures = false;
-        for &(via, to) in self.steps.short_jumps(field) {
+        for &(via, to) in self.short_jumps(field, false, color_to_capture) {
             if position.is_empty(to) && piece_is(position.piece_at(via), color_to_capture) {
                 captures = true;
-                let without_man = &position.put_piece(field, EMPTY);
+                let mut without_man = *position;
+                without_man.remove_piece(field, position.piece_at(field));
                 self.explode_jump(
-                    without_man,
+                    &without_man,
                     Move::take_one(field, to, via),
+                    false,
                     color_to_capture,
                     list,
                 );
@@ -100,6 +162,8 @@ impl Generator {
         let mut exploded = false;
         for path in self.steps.paths(mv.to()) {
             let mut via: Option<Field> = None;
+            let mut behind = true;
+            let start = moves.len();
             for &to in path.iter() {
                 match (piece_own(position.piece_at(to), color_to_capture), via) {
                     (Some(false), _) | (Some(true), Some(_)) => break,
@@ -109,17 +173,23 @@ impl Generator {
                             break;
                         } else {
                             exploded = true;
+                            let landing = moves.len();
                             self.explode_long_jump(
                                 position,
                                 mv.take_more(via, to),
                                 color_to_capture,
                                 moves,
                             );
+                            if !self.may_stop(position, via, behind) {
+                                drop_stops(moves, landing, mv.num_taken() + 1);
+                            }
+                            behind = false;
                         }
                     }
                     (None, None) => (),
                 }
             }
+            self.keep_continued(moves, start, mv.num_taken() + 1);
         }
 
         if !exploded {
@@ -127,6 +197,20 @@ impl Generator {
         }
     }
 
+    // without a majority to trim them, a king may only stop short when no landing goes on capturing
+    fn keep_continued(&self, moves: &mut Vec<Move>, start: usize, num_taken: Captures) {
+        if self.rules.majority == Majority::Free
+            && moves[start..].iter().any(|mv| mv.num_taken() > num_taken)
+        {
+            drop_stops(moves, start, num_taken);
+        }
+    }
+
+    // under killer rules a king that takes a king last lands right behind it
+    fn may_stop(&self, position: &Position, via: Field, behind: bool) -> bool {
+        behind || !self.rules.killer || !matches!(position.piece_at(via), WHITE_KING | BLACK_KING)
+    }
+
     fn add_king_moves(
         &self,
         position: &Position,
@@ -135,29 +219,46 @@ impl Generator {
         captures: &mut bool,
         color_to_capture: &Color,
     ) {
-        let without_king = &position.put_piece(field, EMPTY);
+        let mut without_king = *position;
+        without_king.remove_piece(field, position.piece_at(field));
         for path in self.steps.paths(field) {
             let mut via: Option<Field> = None;
+            let mut behind = true;
+            let start = list.len();
             for &to in path.iter() {
                 match (piece_own(position.piece_at(to), color_to_capture), via) {
                     (Some(false), _) | (Some(true), Some(_)) => break,
                     (Some(true), None) => via = Some(to),
                     (None, Some(via)) => {
-                        self.explode_long_jump(
-                            without_king,
+                        let landing = list.len();
+                        self.explode_king_jump(
+                            &without_king,
                             Move::take_one(field, to, via),
                             color_to_capture,
                             list,
                         );
+                        if !self.may_stop(position, via, behind) {
+                            drop_stops(list, landing, 1);
+                        }
+                        behind = false;
                         *captures = true;
+                        if !self.rules.flying_kings {
+                            break;
+                        }
                     }
                     (None, None) => {
                         if !*captures {
                             list.push(Move::shift(field, to));
                         }
+                        if !self.rules.flying_kings {
+                            break;
+                        }
                     }
                 }
             }
+            if via.is_some() {
+                self.keep_continued(list, start, 1);
+            }
         }
     }
 
@@ -165,7 +266,7 @@ impl Generator {
         let mut list = Vec::with_capacity(31);
         let mut captures = false;
         if position.side_to_move() == White {
-            for field in 0..50 {
+            for field in 0..self.rules.size.fields() {
                 match position.piece_at(field) {
                     WHITE_MAN => {
                         if self.add_short_jumps(position, field, &mut list, &Black) {
@@ -187,7 +288,7 @@ impl Generator {
                 }
             }
         } else {
-            for field in 0..50 {
+            for field in 0..self.rules.size.fields() {
                 match position.piece_at(field) {
                     BLACK_MAN => {
                         if self.add_short_jumps(position, field, &mut list, &White) {
@@ -210,7 +311,7 @@ impl Generator {
             }
         }
 
-        Generator::trim_list(&mut list);
+        self.trim_list(&mut list);
         list
     }
 
@@ -218,7 +319,7 @@ impl Generator {
         list.clear();
         let mut captures = false;
         if position.side_to_move() == White {
-            for field in 0..50 {
+            for field in 0..self.rules.size.fields() {
                 match position.piece_at(field) {
                     WHITE_MAN => {
                         if self.add_short_jumps(position, field, list, &Black) {
@@ -240,7 +341,7 @@ impl Generator {
                 }
             }
         } else {
-            for field in 0..50 {
+            for field in 0..self.rules.size.fields() {
                 match position.piece_at(field) {
                     BLACK_MAN => {
                         if self.add_short_jumps(position, field, list, &White) {
@@ -263,7 +364,7 @@ impl Generator {
             }
         }
 
-        Generator::trim_list(list);
+        self.trim_list(list);
     }
 
     pub fn to_short_string(&self, position: &Position, mv: &Move) -> String {
@@ -292,17 +393,191 @@ impl Generator {
 
         mv.as_full_string()
     }
+
+    pub fn to_pdn_string(&self, position: &Position, mv: &Move) -> String {
+        if mv.num_taken() == 0
+            || self
+                .legal_moves(position)
+                .iter()
+                .filter(|lm| lm.from() == mv.from() && lm.to() == mv.to())
+                .count()
+                == 1
+        {
+            return mv.as_string();
+        }
+
+        self.capture_path(position, mv)
+            .iter()
+            .map(|field| format!("{}", field + 1))
+            .collect::<Vec<_>>()
+            .join("x")
+    }
+
+    pub fn capture_path(&self, position: &Position, mv: &Move) -> Vec<Field> {
+        let mut path = vec![mv.from()];
+        if mv.num_taken() > 0 {
+            let piece = position.piece_at(mv.from());
+            let king = matches!(piece, WHITE_KING | BLACK_KING);
+            let color_to_capture = if piece_is(piece, &White) {
+                Black
+            } else {
+                White
+            };
+            let without_piece = &position.put_piece(mv.from(), EMPTY);
+            self.find_capture_path(
+                without_piece,
+                mv,
+                king && self.rules.flying_kings,
+                &color_to_capture,
+                &mut vec![],
+                &mut path,
+            );
+        }
+        if path.len() == 1 || path[path.len() - 1] != mv.to() {
+            path.push(mv.to());
+        }
+        path
+    }
+
+    fn find_capture_path(
+        &self,
+        position: &Position,
+        mv: &Move,
+        king: bool,
+        color_to_capture: &Color,
+        captured: &mut Vec<Field>,
+        path: &mut Vec<Field>,
+    ) -> bool {
+        let at = path[path.len() - 1];
+        if captured.len() == mv.num_taken() as usize {
+            return at == mv.to();
+        }
+        let king = king
+            || (mv.is_crowned() && self.rules.flying_kings && self.promotes(at, color_to_capture));
+
+        for line in self.steps.paths(at) {
+            let mut via: Option<Field> = None;
+            for &field in line.iter() {
+                match via {
+                    None if position.is_empty(field) => {
+                        if !king {
+                            break;
+                        }
+                    }
+                    None => {
+                        if mv.goes_via(field) && !captured.contains(&field) {
+                            via = Some(field);
+                        } else {
+                            break;
+                        }
+                    }
+                    Some(via) => {
+                        if !position.is_empty(field) {
+                            break;
+                        }
+                        captured.push(via);
+                        path.push(field);
+                        if self.find_capture_path(
+                            position,
+                            mv,
+                            king,
+                            color_to_capture,
+                            captured,
+                            path,
+                        ) {
+                            return true;
+                        }
+                        captured.pop();
+                        path.pop();
+                        if !king {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    pub fn parse_move(&self, position: &Position, notation: &str) -> Result<Move, String> {
+        let invalid = || format!("Invalid move {}", notation);
+        let capture = notation.contains('x');
+        let parts = notation
+            .split(['-', 'x'])
+            .map(|part| match part.parse::<Field>() {
+                Ok(number) if (1..=self.rules.size.fields()).contains(&number) => {
+                    Ok(Some(number - 1))
+                }
+                _ if part.is_empty() => Ok(None),
+                _ => Err(invalid()),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if parts.len() < 2 {
+            return Err(invalid());
+        }
+
+        let from = parts[0];
+        let to = parts[parts.len() - 1];
+        let mut taken = vec![];
+        if parts.len() > 2 && parts.iter().any(Option::is_some) {
+            let landings = parts.iter().cloned().collect::<Option<Vec<_>>>();
+            let landings = landings.ok_or_else(invalid)?;
+            let without_piece = &position.put_piece(landings[0], EMPTY);
+            for pair in landings.windows(2) {
+                let line = self
+                    .steps
+                    .paths(pair[0])
+                    .iter()
+                    .find(|line| line.contains(&pair[1]))
+                    .ok_or_else(invalid)?;
+                taken.extend(
+                    line.iter()
+                        .take_while(|&&field| field != pair[1])
+                        .filter(|&&field| !without_piece.is_empty(field)),
+                );
+            }
+        }
+
+        let mut candidates = self.legal_moves(position).into_iter().filter(|mv| {
+            (mv.num_taken() > 0) == capture
+                && from.is_none_or(|from| mv.from() == from)
+                && to.is_none_or(|to| mv.to() == to)
+                && taken.iter().all(|&via| mv.goes_via(via))
+        });
+        match (candidates.next(), candidates.next()) {
+            (Some(mv), None) => Ok(mv),
+            (None, _) => Err(format!("Illegal move {}", notation)),
+            _ => Err(format!("Ambiguous move {}", notation)),
+        }
+    }
+}
+
+// removes the captures from start on that end after num_taken pieces
+fn drop_stops(moves: &mut Vec<Move>, start: usize, num_taken: Captures) {
+    let mut i = start;
+    while i < moves.len() {
+        if moves[i].num_taken() == num_taken {
+            moves.remove(i);
+        } else {
+            i += 1;
+        }
+    }
 }
 
 const CROSSES: &str = "xxxxxxxxxxxx";
 
 #[cfg(test)]
 fn verify(position: &Position, moves: &[Move]) {
-    let legal = Generator::create().legal_moves(position);
+    verify_rules(Rules::international(), position, moves);
+}
+
+#[cfg(test)]
+fn verify_rules(rules: Rules, position: &Position, moves: &[Move]) {
+    let legal = Generator::with_rules(rules).legal_moves(position);
     let count = legal.len();
     assert!(count >= moves.len());
     assert!(legal.into_iter().fold(true, |ok, mv| {
-        let expected = moves.iter().fold(false, |v, m| v || mv == *m);
+        let expected = moves.contains(&mv);
         if !expected {
             println!("Unexpected move {}", mv);
         }
@@ -314,7 +589,7 @@ fn verify(position: &Position, moves: &[Move]) {
 #[test]
 fn one_white_man_side() {
     let position = Position::create().put_piece(35, WHITE_MAN);
-    verify(&position, &vec![Move::shift(35, 30)]);
+    verify(&position, &[Move::shift(35, 30)]);
 }
 
 #[test]
@@ -323,19 +598,19 @@ fn one_white_man_blocked() {
         .put_piece(35, WHITE_MAN)
         .put_piece(30, BLACK_MAN)
         .put_piece(26, BLACK_MAN);
-    verify(&position, &vec![]);
+    verify(&position, &[]);
 }
 
 #[test]
 fn one_white_man_center() {
     let position = Position::create().put_piece(36, WHITE_MAN);
-    verify(&position, &vec![Move::shift(36, 30), Move::shift(36, 31)]);
+    verify(&position, &[Move::shift(36, 30), Move::shift(36, 31)]);
 }
 
 #[test]
 fn one_black_man_side() {
     let position = Position::create().put_piece(35, BLACK_MAN).toggle_side();
-    verify(&position, &vec![Move::shift(35, 40)]);
+    verify(&position, &[Move::shift(35, 40)]);
 }
 
 #[test]
@@ -344,7 +619,7 @@ fn one_single_capture_white_man() {
         .put_piece(15, WHITE_MAN)
         .put_piece(40, BLACK_MAN)
         .put_piece(45, WHITE_MAN);
-    verify(&position, &vec![Move::take_one(45, 36, 40)]);
+    verify(&position, &[Move::take_one(45, 36, 40)]);
 }
 
 #[test]
@@ -354,7 +629,7 @@ fn one_double_capture_white_man() {
         .put_piece(31, BLACK_MAN)
         .put_piece(40, BLACK_MAN)
         .put_piece(45, WHITE_MAN);
-    verify(&position, &vec![Move::take(45, 27, &[40, 31])]);
+    verify(&position, &[Move::take(45, 27, &[40, 31])]);
 }
 
 #[test]
@@ -366,7 +641,7 @@ fn double_and_triple_capture_white_man() {
         .put_piece(41, BLACK_MAN)
         .put_piece(42, BLACK_MAN)
         .put_piece(45, WHITE_MAN);
-    verify(&position, &vec![Move::take(45, 38, &[40, 41, 42])]);
+    verify(&position, &[Move::take(45, 38, &[40, 41, 42])]);
 }
 
 #[test]
@@ -377,7 +652,7 @@ fn two_captures_white_man() {
         .put_piece(46, WHITE_MAN);
     verify(
         &position,
-        &vec![Move::take_one(46, 35, 40), Move::take_one(46, 37, 41)],
+        &[Move::take_one(46, 35, 40), Move::take_one(46, 37, 41)],
     );
 }
 
@@ -390,7 +665,7 @@ fn two_captures_black_man() {
         .toggle_side();
     verify(
         &position,
-        &vec![Move::take_one(36, 25, 30), Move::take_one(36, 27, 31)],
+        &[Move::take_one(36, 25, 30), Move::take_one(36, 27, 31)],
     );
 }
 
@@ -405,7 +680,7 @@ fn white_king_moves() {
         .put_piece(43, WHITE_KING);
     verify(
         &position,
-        &vec![
+        &[
             Move::shift(43, 34),
             Move::shift(43, 39),
             Move::shift(43, 48),
@@ -421,7 +696,7 @@ fn black_king_moves() {
         .put_piece(11, WHITE_MAN)
         .put_piece(17, WHITE_KING)
         .toggle_side();
-    verify(&position, &vec![Move::shift(0, 5), Move::shift(0, 6)]);
+    verify(&position, &[Move::shift(0, 5), Move::shift(0, 6)]);
 }
 
 #[test]
@@ -430,7 +705,7 @@ fn study1() {
         .ok()
         .unwrap()
         .go(&Move::shift(48, 43));
-    verify(&position, &vec![Move::take_one(39, 48, 43)]);
+    verify(&position, &[Move::take_one(39, 48, 43)]);
 }
 
 #[test]
@@ -441,7 +716,7 @@ fn study2() {
         .go(&Move::shift(48, 43))
         .go(&Move::take_one(39, 48, 43))
         .go(&Move::shift(49, 43));
-    verify(&position, &vec![Move::take(48, 15, &[31, 20])]);
+    verify(&position, &[Move::take(48, 15, &[31, 20])]);
 }
 
 #[test]
@@ -454,7 +729,7 @@ fn study3() {
         .go(&Move::shift(49, 43))
         .go(&Move::take(48, 15, &[31, 20]))
         .go(&Move::take(43, 38, &[28, 18, 8, 3]));
-    verify(&position, &vec![Move::take_one(22, 31, 27)]);
+    verify(&position, &[Move::take_one(22, 31, 27)]);
 }
 
 #[test]
@@ -469,7 +744,7 @@ fn study4() {
         .go(&Move::take(43, 38, &[28, 18, 8, 3]))
         .go(&Move::take_one(22, 31, 27))
         .go(&Move::shift(25, 20));
-    verify(&position, &vec![Move::take_one(15, 26, 20)]);
+    verify(&position, &[Move::take_one(15, 26, 20)]);
 }
 
 #[test]
@@ -477,7 +752,7 @@ fn multi_long_capture() {
     let position = Position::parse("w 5/5/3b1/5/5/5/5/1b3/5/W4").ok().unwrap();
     verify(
         &position,
-        &vec![Move::take(45, 4, &[36, 13]), Move::take(45, 9, &[36, 13])],
+        &[Move::take(45, 4, &[36, 13]), Move::take(45, 9, &[36, 13])],
     );
 }
 
@@ -486,7 +761,7 @@ fn coup_turc() {
     let position = Position::parse("b 5/el2/5/Bebew/2w2/5/eh2/3we/ew3/5")
         .ok()
         .unwrap();
-    verify(&position, &vec![Move::take(15, 27, &[31, 38, 19, 22])]);
+    verify(&position, &[Move::take(15, 27, &[31, 38, 19, 22])]);
 }
 
 #[test]
@@ -494,7 +769,7 @@ fn coup_tour() {
     let position = Position::parse("w 3We/5/5/5/l3/5/l3/ew3/b4/5")
         .ok()
         .unwrap();
-    verify(&position, &vec![Move::take(36, 45, &[20, 21, 30, 31, 40])]);
+    verify(&position, &[Move::take(36, 45, &[20, 21, 30, 31, 40])]);
 }
 
 #[test]
@@ -504,7 +779,7 @@ fn to_start_field() {
         .unwrap();
     verify(
         &position,
-        &vec![
+        &[
             Move::take(34, 29, &[39, 42, 22, 23]),
             Move::take(34, 34, &[39, 42, 22, 23]),
         ],
@@ -518,7 +793,7 @@ fn ambiguous_capture() {
         .unwrap();
     verify(
         &position,
-        &vec![
+        &[
             Move::take(3, 32, &[26, 36, 37]),
             Move::take(3, 32, &[26, 37, 40]),
         ],
@@ -532,13 +807,122 @@ fn no_double_jump() {
         .unwrap();
     verify(
         &position,
-        &vec![
+        &[
             Move::take(37, 19, &[23, 33, 42, 43]),
             Move::take(37, 37, &[32, 33, 42, 43]),
         ],
     );
 }
 
+#[test]
+fn crowned_while_capturing() {
+    use super::position::Size;
+    let position = Position::with_size(Size::Eight)
+        .put_piece(10, WHITE_MAN)
+        .put_piece(6, BLACK_MAN)
+        .put_piece(8, BLACK_MAN);
+    let crowned = Move::take(10, 12, &[6, 8]).crown();
+    verify_rules(Rules::russian(), &position, &[crowned]);
+    assert_eq!(position.go(&crowned).piece_at(12), WHITE_KING);
+    verify_rules(Rules::brazilian(), &position, &[Move::take_one(10, 1, 6)]);
+}
+
+#[test]
+fn backward_capture() {
+    use super::position::Size;
+    let position = Position::with_size(Size::Eight)
+        .put_piece(13, WHITE_MAN)
+        .put_piece(17, BLACK_MAN);
+    verify_rules(Rules::russian(), &position, &[Move::take_one(13, 22, 17)]);
+    verify_rules(
+        Rules::english(),
+        &position,
+        &[Move::shift(13, 8), Move::shift(13, 9)],
+    );
+}
+
+#[test]
+fn short_kings() {
+    use super::position::Size;
+    let position = Position::with_size(Size::Eight).put_piece(28, WHITE_KING);
+    verify_rules(Rules::english(), &position, &[Move::shift(28, 24)]);
+    let long: Vec<Move> = [24, 21, 17, 14, 10, 7, 3]
+        .iter()
+        .map(|&to| Move::shift(28, to))
+        .collect();
+    verify_rules(Rules::brazilian(), &position, &long);
+}
+
+#[test]
+fn free_majority() {
+    use super::position::Size;
+    // one man takes one, the other two
+    let position = Position::with_size(Size::Eight)
+        .put_piece(28, WHITE_MAN)
+        .put_piece(24, BLACK_MAN)
+        .put_piece(31, WHITE_MAN)
+        .put_piece(26, BLACK_MAN)
+        .put_piece(18, BLACK_MAN);
+    verify_rules(
+        Rules::russian(),
+        &position,
+        &[Move::take_one(28, 21, 24), Move::take(31, 15, &[26, 18])],
+    );
+    verify_rules(
+        Rules::brazilian(),
+        &position,
+        &[Move::take(31, 15, &[26, 18])],
+    );
+}
+
+#[test]
+fn killer() {
+    let position = Position::parse_pdn("W:WK46:BK32").unwrap();
+    verify_rules(Rules::killer(), &position, &[Move::take_one(45, 27, 31)]);
+    // a man may be passed at any distance
+    let position = Position::parse_pdn("W:WK46:B32").unwrap();
+    let moves: Vec<Move> = [27, 22, 18, 13, 9, 4]
+        .iter()
+        .map(|&to| Move::take_one(45, to, 31))
+        .collect();
+    verify_rules(Rules::killer(), &position, &moves);
+    // only the king taken last counts
+    let position = Position::parse_pdn("W:WK46:B32,K33").unwrap();
+    verify_rules(Rules::killer(), &position, &[Move::take(45, 38, &[31, 32])]);
+    let position = Position::parse_pdn("W:WK46:BK32,18").unwrap();
+    verify_rules(
+        Rules::killer(),
+        &position,
+        &[
+            Move::take(45, 0, &[31, 17]),
+            Move::take(45, 6, &[31, 17]),
+            Move::take(45, 11, &[31, 17]),
+        ],
+    );
+    // a king taken last after a man
+    let position = Position::parse_pdn("W:WK46:B32,K18").unwrap();
+    verify_rules(Rules::killer(), &position, &[Move::take(45, 11, &[31, 17])]);
+    verify_rules(
+        Rules::international(),
+        &position,
+        &[
+            Move::take(45, 0, &[31, 17]),
+            Move::take(45, 6, &[31, 17]),
+            Move::take(45, 11, &[31, 17]),
+        ],
+    );
+    // from a distance, and for black
+    let position = Position::parse_pdn("W:WK46:BK23").unwrap();
+    verify_rules(Rules::killer(), &position, &[Move::take_one(45, 18, 22)]);
+    let position = Position::parse_pdn("B:WK19:BK5").unwrap();
+    verify_rules(Rules::killer(), &position, &[Move::take_one(4, 22, 18)]);
+    let moves: Vec<Move> = [22, 27, 31, 36, 40, 45]
+        .iter()
+        .map(|&to| Move::take_one(4, to, 18))
+        .collect();
+    verify_rules(Rules::international(), &position, &moves);
+}
+
 #[test]
 fn short_from() {
     let gen = Generator::create();
@@ -564,3 +948,55 @@ fn short_forced() {
     let mv = Move::take_one(27, 18, 22);
     assert_eq!("x", gen.to_short_string(&position, &mv));
 }
+
+#[test]
+fn parse_simple_moves() {
+    let gen = Generator::create();
+    let position = Position::initial();
+    assert!(gen.parse_move(&position, "34-29").unwrap() == Move::shift(33, 28));
+    assert!(gen.parse_move(&position, "35-").unwrap() == Move::shift(34, 29));
+    assert!(gen.parse_move(&position, "-26").unwrap() == Move::shift(30, 25));
+    assert!(gen.parse_move(&position, "34-28").is_err());
+    assert!(gen.parse_move(&position, "34x29").is_err());
+    assert!(gen.parse_move(&position, "34").is_err());
+    assert!(gen.parse_move(&position, "34-51").is_err());
+    assert!(gen.parse_move(&position, "-").is_err());
+}
+
+#[test]
+fn parse_ambiguous_capture() {
+    let gen = Generator::create();
+    let position = Position::parse("b 3B1/5/5/5/5/ewebe/5/eh2/w4/5")
+        .ok()
+        .unwrap();
+    let first = Move::take(3, 32, &[26, 36, 37]);
+    let second = Move::take(3, 32, &[26, 37, 40]);
+    assert!(gen.parse_move(&position, "4x33").is_err());
+    assert_eq!(gen.to_pdn_string(&position, &first), "4x31x42x33");
+    assert_eq!(gen.to_pdn_string(&position, &second), "4x36x47x33");
+    assert!(gen.parse_move(&position, "4x31x42x33").unwrap() == first);
+    assert!(gen.parse_move(&position, "4x36x47x33").unwrap() == second);
+    assert!(gen.parse_move(&position, "4x36x42x33").is_err());
+}
+
+#[test]
+fn notation_roundtrip() {
+    let gen = Generator::create();
+    for fen in &[
+        "wkkkk55rrrr",
+        "w 5/3be/5/3be/web2/wewbe/ew3/3bb/5/3ww",
+        "w 5/5/3b1/5/5/5/5/1b3/5/W4",
+        "b 5/el2/5/Bebew/2w2/5/eh2/3we/ew3/5",
+        "w 3We/5/5/5/l3/5/l3/ew3/b4/5",
+        "b 2b2/b4/3bb/5/wewww/3we/4B/ww2w/eww2/5",
+        "w 5/5/5/5/3be/5/2le/2w2/2le/5",
+    ] {
+        let position = Position::parse(fen).unwrap();
+        for mv in gen.legal_moves(&position) {
+            let pdn = gen.to_pdn_string(&position, &mv);
+            assert!(gen.parse_move(&position, &pdn).unwrap() == mv);
+            let short = gen.to_short_string(&position, &mv);
+            assert!(gen.parse_move(&position, &short).unwrap() == mv);
+        }
+    }
+}
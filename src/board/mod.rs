@@ -1,9 +1,14 @@
-mod coords;
+pub(crate) mod coords;
 pub mod decimal;
+pub mod game;
 pub mod generator;
+pub mod mutable;
 pub mod mv;
+pub mod perft;
 pub mod piece;
 pub mod position;
+pub mod rules;
 pub mod stars;
 pub mod stats;
 mod steps;
+pub mod zobrist;
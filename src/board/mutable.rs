@@ -0,0 +1,130 @@
+use super::mv::Move;
+use super::piece::{color, Color, Piece, BLACK_KING, BLACK_MAN, WHITE_KING, WHITE_MAN};
+use super::position::{promote, promote_now, Field, Position};
+
+// what make destroys: the piece before a promotion and which of the taken pieces were kings
+#[derive(Clone, Copy)]
+pub struct Undo {
+    mv: Move,
+    piece: Piece,
+    kings: u32,
+}
+
+impl Undo {
+    pub fn mv(&self) -> &Move {
+        &self.mv
+    }
+}
+
+// a position changed in place, for walking a tree without copying at every node
+#[derive(Clone, Copy)]
+pub struct Board {
+    position: Position,
+}
+
+impl Board {
+    pub fn create(position: &Position) -> Board {
+        Board {
+            position: *position,
+        }
+    }
+
+    // the hashable snapshot
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    pub fn piece_at(&self, field: Field) -> Piece {
+        self.position.piece_at(field)
+    }
+
+    pub fn make(&mut self, mv: &Move) -> Undo {
+        let piece = self.position.piece_at(mv.from());
+        let mut kings = 0;
+        for (i, &taken) in mv.taken().iter().enumerate() {
+            let taken_piece = self.position.piece_at(taken);
+            if taken_piece == WHITE_KING || taken_piece == BLACK_KING {
+                kings |= 1 << i;
+            }
+            self.position.remove_piece(taken, taken_piece);
+        }
+        self.position.remove_piece(mv.from(), piece);
+        let landed = if mv.is_crowned() {
+            promote_now(piece)
+        } else {
+            promote(self.position.size(), mv.to(), piece)
+        };
+        self.position.place_piece(mv.to(), landed);
+        self.position.switch_side();
+        Undo {
+            mv: *mv,
+            piece,
+            kings,
+        }
+    }
+
+    pub fn unmake(&mut self, undo: &Undo) {
+        let mv = &undo.mv;
+        self.position.switch_side();
+        let landed = self.position.piece_at(mv.to());
+        self.position.remove_piece(mv.to(), landed);
+        self.position.place_piece(mv.from(), undo.piece);
+        let (man, king) = match color(undo.piece) {
+            Some(Color::White) => (BLACK_MAN, BLACK_KING),
+            _ => (WHITE_MAN, WHITE_KING),
+        };
+        for (i, &taken) in mv.taken().iter().enumerate() {
+            let piece = if undo.kings & (1 << i) != 0 {
+                king
+            } else {
+                man
+            };
+            self.position.place_piece(taken, piece);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::generator::Generator;
+
+    #[test]
+    fn make_unmake() {
+        let generator = Generator::create();
+        for fen in [
+            "W:W28,K1:B22",
+            "W:W6,7,28:B12,K22,33",
+            "B:W16,K28,38:BK4,33,43",
+        ] {
+            let position = Position::parse_pdn(fen).unwrap();
+            let mut board = Board::create(&position);
+            for mv in generator.legal_moves(&position) {
+                let undo = board.make(&mv);
+                assert!(*board.position() == position.go(&mv), "{}", mv.as_string());
+                assert_eq!(board.position().key(), position.go(&mv).key());
+                board.unmake(&undo);
+                assert!(*board.position() == position);
+                assert_eq!(board.position().key(), position.key());
+            }
+        }
+    }
+
+    #[test]
+    fn long_capture() {
+        let generator = Generator::create();
+        let position = Position::parse_pdn(
+            "W:WK48:BK5,K7,K8,K9,K10,K14,K16,K17,K18,K19,K20,K27,K28,K29,K30,K37,K38,K39,K40,K41,K46,K49",
+        )
+        .unwrap();
+        let moves = generator.legal_moves(&position);
+        assert!(moves.iter().all(|mv| mv.num_taken() == 17));
+        let mut board = Board::create(&position);
+        for mv in moves {
+            let undo = board.make(&mv);
+            assert!(*board.position() == position.go(&mv), "{}", mv.as_string());
+            board.unmake(&undo);
+            assert!(*board.position() == position);
+        }
+    }
+}
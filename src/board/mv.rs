@@ -5,8 +5,9 @@ use super::position::Field;
 pub type Captures = u8;
 
 const NULL_FIELD: Field = 0;
-const MAX_TAKEN: usize = 12;
-const MAX_CAPTURES: u8 = 12;
+// a king can take every piece of the opponent
+const MAX_TAKEN: usize = 20;
+const MAX_CAPTURES: u8 = 20;
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Move {
@@ -14,6 +15,7 @@ pub struct Move {
     to: Field,
     num_taken: Captures,
     taken: [Field; MAX_TAKEN],
+    crowned: bool,
 }
 
 impl Move {
@@ -23,6 +25,7 @@ impl Move {
             to,
             num_taken: 0,
             taken: [NULL_FIELD; MAX_TAKEN],
+            crowned: false,
         }
     }
 
@@ -34,6 +37,7 @@ impl Move {
             to,
             num_taken: 1,
             taken,
+            crowned: false,
         }
     }
 
@@ -49,6 +53,7 @@ impl Move {
             to,
             num_taken: via.len() as u8,
             taken,
+            crowned: false,
         }
     }
 
@@ -77,6 +82,15 @@ impl Move {
             to,
             num_taken: self.num_taken + 1,
             taken,
+            crowned: self.crowned,
+        }
+    }
+
+    // a man passing the last row while capturing, under the rules that crown it there
+    pub fn crown(&self) -> Self {
+        Move {
+            crowned: true,
+            ..*self
         }
     }
 
@@ -96,6 +110,10 @@ impl Move {
         self.num_taken
     }
 
+    pub fn is_crowned(&self) -> bool {
+        self.crowned
+    }
+
     pub fn taken(&self) -> &[Field] {
         &self.taken[0..self.num_taken as usize]
     }
@@ -0,0 +1,223 @@
+use std::ops::AddAssign;
+
+use super::generator::Generator;
+use super::mv::Move;
+use super::piece::{BLACK_MAN, WHITE_MAN};
+use super::position::{promote, Position};
+
+pub type Count = u64;
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct PerftCount {
+    pub nodes: Count,
+    pub captures: Count,
+    pub promotions: Count,
+}
+
+impl PerftCount {
+    fn leaf(position: &Position, mv: &Move) -> PerftCount {
+        let piece = position.piece_at(mv.from());
+        let promotes = (piece == WHITE_MAN || piece == BLACK_MAN)
+            && (mv.is_crowned() || promote(position.size(), mv.to(), piece) != piece);
+        PerftCount {
+            nodes: 1,
+            captures: if mv.num_taken() > 0 { 1 } else { 0 },
+            promotions: if promotes { 1 } else { 0 },
+        }
+    }
+}
+
+impl AddAssign for PerftCount {
+    fn add_assign(&mut self, other: PerftCount) {
+        self.nodes += other.nodes;
+        self.captures += other.captures;
+        self.promotions += other.promotions;
+    }
+}
+
+fn count(
+    generator: &Generator,
+    position: &Position,
+    depth: usize,
+    lists: &mut [Vec<Move>],
+) -> PerftCount {
+    let mut total = PerftCount::default();
+    if depth == 0 {
+        total.nodes = 1;
+        return total;
+    }
+
+    let (list, rest) = lists.split_first_mut().unwrap();
+    generator.legal_moves2(position, list);
+    for mv in list.iter() {
+        if depth == 1 {
+            total += PerftCount::leaf(position, mv);
+        } else {
+            total += count(generator, &position.go(mv), depth - 1, rest);
+        }
+    }
+    total
+}
+
+pub fn perft_count(generator: &Generator, position: &Position, depth: usize) -> PerftCount {
+    let mut lists = vec![Vec::with_capacity(63); depth];
+    count(generator, position, depth, &mut lists)
+}
+
+pub fn perft(generator: &Generator, position: &Position, depth: usize) -> Count {
+    perft_count(generator, position, depth).nodes
+}
+
+pub fn divide(generator: &Generator, position: &Position, depth: usize) -> Vec<(Move, PerftCount)> {
+    let mut lists = vec![Vec::with_capacity(63); depth];
+    generator
+        .legal_moves(position)
+        .into_iter()
+        .map(|mv| {
+            let sub = if depth <= 1 {
+                PerftCount::leaf(position, &mv)
+            } else {
+                count(generator, &position.go(&mv), depth - 1, &mut lists)
+            };
+            (mv, sub)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+fn slow_perft(generator: &Generator, position: &Position, depth: usize) -> Count {
+    if depth == 0 {
+        return 1;
+    }
+    generator
+        .legal_moves(position)
+        .iter()
+        .map(|mv| slow_perft(generator, &position.go(mv), depth - 1))
+        .sum()
+}
+
+#[test]
+fn initial() {
+    let generator = Generator::create();
+    let position = Position::initial();
+    let expected = [1, 9, 81, 658, 4_265, 27_117, 167_140, 1_049_442];
+    for (depth, &nodes) in expected.iter().enumerate() {
+        assert_eq!(perft(&generator, &position, depth), nodes);
+    }
+}
+
+// the published counts of the woldouby position and of a position where kings take long series
+#[test]
+fn published() {
+    let generator = Generator::create();
+    for (fen, expected) in [
+        (
+            "W:W25,27,28,30,32,33,34,35,37,38:B12,13,14,16,18,19,21,23,24,26",
+            &[1, 6, 12, 30, 73, 215, 590, 1_944][..],
+        ),
+        (
+            "B:BK17,K24:W6,9,10,11,20,21,22,23,30,K31,33,37,41,42,43,44,46",
+            &[1, 14, 55, 1_168, 5_432, 87_195, 629_010][..],
+        ),
+    ] {
+        let position = Position::parse_pdn(fen).unwrap();
+        for (depth, &nodes) in expected.iter().enumerate() {
+            assert_eq!(perft(&generator, &position, depth), nodes);
+        }
+    }
+}
+
+#[test]
+fn variants() {
+    use super::rules::Rules;
+    for (rules, expected) in [
+        (
+            Rules::brazilian(),
+            [1, 7, 49, 302, 1_469, 7_473, 37_628, 187_302],
+        ),
+        (
+            Rules::russian(),
+            [1, 7, 49, 302, 1_469, 7_482, 37_986, 190_146],
+        ),
+        (
+            Rules::english(),
+            [1, 7, 49, 302, 1_469, 7_361, 36_768, 179_740],
+        ),
+    ] {
+        let generator = Generator::with_rules(rules);
+        let position = rules.initial();
+        for (depth, &nodes) in expected.iter().enumerate() {
+            assert_eq!(perft(&generator, &position, depth), nodes);
+        }
+    }
+}
+
+#[test]
+fn matches_legal_moves() {
+    let generator = Generator::create();
+    for fen in &[
+        "w 5/3be/5/3be/web2/wewbe/ew3/3bb/5/3ww",
+        "b 5/el2/5/Bebew/2w2/5/eh2/3we/ew3/5",
+        "w 3We/5/5/5/l3/5/l3/ew3/b4/5",
+        "b 2b2/b4/3bb/5/wewww/3we/4B/ww2w/eww2/5",
+    ] {
+        let position = Position::parse(fen).unwrap();
+        for depth in 0..4 {
+            assert_eq!(
+                perft(&generator, &position, depth),
+                slow_perft(&generator, &position, depth)
+            );
+        }
+    }
+}
+
+#[test]
+fn divide_sums_to_perft() {
+    let generator = Generator::create();
+    let position = Position::parse("w 5/3be/5/3be/web2/wewbe/ew3/3bb/5/3ww").unwrap();
+    let mut total = PerftCount::default();
+    for (_, sub) in divide(&generator, &position, 3) {
+        total += sub;
+    }
+    assert_eq!(total, perft_count(&generator, &position, 3));
+}
+
+#[test]
+fn captures_and_promotions() {
+    let generator = Generator::create();
+    let position = Position::parse("w 5/bw3/5/5/5/5/5/5/5/5").unwrap();
+    let count = perft_count(&generator, &position, 1);
+    assert_eq!(count.nodes, 2);
+    assert_eq!(count.captures, 0);
+    assert_eq!(count.promotions, 2);
+
+    let position = Position::parse("b 5/5/5/5/5/5/5/5/ew3/eb3").unwrap();
+    let count = perft_count(&generator, &position, 1);
+    assert_eq!(count.nodes, 1);
+    assert_eq!(count.captures, 1);
+    assert_eq!(count.promotions, 0);
+}
+
+#[test]
+fn killer() {
+    use super::rules::Rules;
+    let generator = Generator::with_rules(Rules::killer());
+    // 46x28 has to land right behind the king, and the black king on 3 is out of reach
+    let position = Position::parse_pdn("W:WK46:BK32,K3").unwrap();
+    for (depth, &nodes) in [1, 1, 9, 121].iter().enumerate() {
+        assert_eq!(perft(&generator, &position, depth), nodes);
+    }
+    // black steps onto a diagonal of the king on 28 at 17 or 14 and is taken by the one landing behind it,
+    // elsewhere the king has 17 moves
+    let position = Position::parse_pdn("B:WK28:BK3").unwrap();
+    let moves = divide(&generator, &position, 2);
+    assert_eq!(moves.len(), 9);
+    for (mv, count) in moves {
+        let (nodes, captures) = match mv.to() + 1 {
+            17 | 14 => (1, 1),
+            _ => (17, 0),
+        };
+        assert_eq!(count.nodes, nodes, "{}", mv.as_string());
+        assert_eq!(count.captures, captures, "{}", mv.as_string());
+    }
+}
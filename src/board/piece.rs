@@ -5,7 +5,7 @@ pub const WHITE_KING: Piece = 2;
 pub const BLACK_MAN: Piece = 3;
 pub const BLACK_KING: Piece = 4;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Color {
     White,
     Black,
@@ -1,31 +1,69 @@
 use super::mv::Move;
 use super::piece::{Color, Piece, BLACK_KING, BLACK_MAN, EMPTY, WHITE_KING, WHITE_MAN};
+use super::zobrist::{piece_key, Key, SIDE_KEY};
 
 pub type Field = usize;
-pub fn promote(field: Field, piece: Piece) -> Piece {
-    if piece == WHITE_MAN && field < 5 {
+
+// the board, numbered like the international one from the top left
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Size {
+    Ten,
+    Eight,
+}
+
+impl Size {
+    pub fn rows(&self) -> usize {
+        match self {
+            Size::Ten => 10,
+            Size::Eight => 8,
+        }
+    }
+
+    pub fn per_row(&self) -> usize {
+        self.rows() / 2
+    }
+
+    pub fn fields(&self) -> usize {
+        self.rows() * self.per_row()
+    }
+}
+
+pub fn promote(size: Size, field: Field, piece: Piece) -> Piece {
+    if piece == WHITE_MAN && field < size.per_row() {
         WHITE_KING
-    } else if piece == BLACK_MAN && field >= 45 {
+    } else if piece == BLACK_MAN && field >= size.fields() - size.per_row() {
         BLACK_KING
     } else {
         piece
     }
 }
 
+// a man crowned during its capture, wherever it ends
+pub fn promote_now(piece: Piece) -> Piece {
+    match piece {
+        WHITE_MAN => WHITE_KING,
+        BLACK_MAN => BLACK_KING,
+        _ => piece,
+    }
+}
+
 const FEN_CHARS: [char; 5] = ['e', 'w', 'W', 'b', 'B'];
 const ASCII_CHARS: [char; 5] = ['.', 'w', 'W', 'b', 'B'];
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(PartialEq, Eq, Clone, Copy)]
 pub struct Position {
     empty: u64,
     white_man: u64,
     black_man: u64,
     white_king: u64,
+    key: Key,
 }
 
 type Index = usize;
 const SIDE_BIT: u64 = 1 << 50;
 const ALL_BITS: u64 = SIDE_BIT - 1;
+// the fields beyond 32 stay empty on the small board
+const EIGHT_BIT: u64 = 1 << 51;
 const BITS: [u64; 50] = [
     1,
     1 << 1,
@@ -166,13 +204,48 @@ impl Position {
         }
     }
 
+    pub fn size(&self) -> Size {
+        if self.empty & EIGHT_BIT == 0 {
+            Size::Ten
+        } else {
+            Size::Eight
+        }
+    }
+
+    pub fn count_pieces(&self) -> usize {
+        50 - (self.empty & ALL_BITS).count_ones() as usize
+    }
+
+    pub fn key(&self) -> Key {
+        self.key
+    }
+
     pub fn create() -> Position {
         Position {
             empty: ALL_BITS,
             white_man: 0,
             black_man: 0,
             white_king: 0,
+            key: 0,
+        }
+    }
+
+    pub fn with_size(size: Size) -> Position {
+        let mut position = Position::create();
+        if size == Size::Eight {
+            position.empty |= EIGHT_BIT;
         }
+        position
+    }
+
+    // men on all but the two middle rows
+    pub fn initial_with_size(size: Size) -> Position {
+        let men = size.per_row() * (size.rows() - 2) / 2;
+        let black = (0..men).fold(Self::with_size(size), |pos, field| {
+            pos.put_piece(field, BLACK_MAN)
+        });
+        (size.fields() - men..size.fields())
+            .fold(black, |pos, field| pos.put_piece(field, WHITE_MAN))
     }
 
     pub fn toggle_side(&self) -> Position {
@@ -181,11 +254,13 @@ impl Position {
             white_man: self.white_man,
             black_man: self.black_man,
             white_king: self.white_king,
+            key: self.key ^ SIDE_KEY,
         }
     }
 
     pub fn put_piece(&self, field: Field, piece: Piece) -> Position {
         Position {
+            key: self.key ^ piece_key(self.piece_at(field), field) ^ piece_key(piece, field),
             empty: if piece == EMPTY {
                 set(self.empty, field)
             } else {
@@ -209,6 +284,34 @@ impl Position {
         }
     }
 
+    // in place, touching only the bitboard of the piece, for the mutable board
+    pub(crate) fn remove_piece(&mut self, field: Field, piece: Piece) {
+        match piece {
+            WHITE_MAN => self.white_man = clear(self.white_man, field),
+            BLACK_MAN => self.black_man = clear(self.black_man, field),
+            WHITE_KING => self.white_king = clear(self.white_king, field),
+            _ => (),
+        }
+        self.empty = set(self.empty, field);
+        self.key ^= piece_key(piece, field);
+    }
+
+    pub(crate) fn place_piece(&mut self, field: Field, piece: Piece) {
+        match piece {
+            WHITE_MAN => self.white_man = set(self.white_man, field),
+            BLACK_MAN => self.black_man = set(self.black_man, field),
+            WHITE_KING => self.white_king = set(self.white_king, field),
+            _ => (),
+        }
+        self.empty = clear(self.empty, field);
+        self.key ^= piece_key(piece, field);
+    }
+
+    pub(crate) fn switch_side(&mut self) {
+        self.empty ^= SIDE_BIT;
+        self.key ^= SIDE_KEY;
+    }
+
     pub fn go(&self, mv: &Move) -> Self {
         let from = mv.from();
         let to = mv.to();
@@ -219,24 +322,33 @@ impl Position {
         let mut white_man = self.white_man;
         let mut black_man = self.black_man;
         let mut white_king = self.white_king;
+        let mut key = self.key ^ SIDE_KEY;
         for &taken in mv.taken() {
-            match self.piece_at(taken) {
+            let taken_piece = self.piece_at(taken);
+            match taken_piece {
                 WHITE_MAN => white_man = clear(white_man, taken),
                 BLACK_MAN => black_man = clear(black_man, taken),
                 WHITE_KING => white_king = clear(white_king, taken),
                 _ => (),
             }
             empty = set(empty, taken);
+            key ^= piece_key(taken_piece, taken);
         }
 
         let from_piece = self.piece_at(from);
+        let to_piece = if mv.is_crowned() {
+            promote_now(from_piece)
+        } else {
+            promote(self.size(), to, from_piece)
+        };
+        key ^= piece_key(from_piece, from) ^ piece_key(to_piece, to);
         match from_piece {
             WHITE_MAN => white_man = clear(white_man, from),
             BLACK_MAN => black_man = clear(black_man, from),
             WHITE_KING => white_king = clear(white_king, from),
             _ => (),
         }
-        match promote(to, from_piece) {
+        match to_piece {
             WHITE_MAN => white_man = set(white_man, to),
             BLACK_MAN => black_man = set(black_man, to),
             WHITE_KING => white_king = set(white_king, to),
@@ -248,6 +360,7 @@ impl Position {
             white_man,
             black_man,
             white_king,
+            key,
         }
     }
 
@@ -257,7 +370,7 @@ impl Position {
         } else {
             "b"
         });
-        for c in (0..50).map(|i| FEN_CHARS[self.piece_at(i) as Field]) {
+        for c in (0..self.size().fields()).map(|i| FEN_CHARS[self.piece_at(i) as Field]) {
             fen.push(c);
         }
         fen
@@ -269,7 +382,7 @@ impl Position {
         } else {
             "B"
         });
-        for c in (0..50).map(|i| FEN_CHARS[self.piece_at(i) as Field]) {
+        for c in (0..self.size().fields()).map(|i| FEN_CHARS[self.piece_at(i) as Field]) {
             fen.push(c);
         }
         fen
@@ -290,7 +403,8 @@ impl Position {
             }
             0
         }
-        for i in 1..51 {
+        let size = self.size();
+        for i in 1..=size.fields() {
             match self.piece_at(i - 1) as Field {
                 0 => num_empty += 1,
                 n => {
@@ -298,15 +412,53 @@ impl Position {
                     fen.push(FEN_CHARS[n]);
                 }
             }
-            if i % 5 == 0 {
+            if i % size.per_row() == 0 {
                 num_empty = flush(&mut fen, num_empty);
             }
         }
         fen
     }
 
+    pub fn pdn_fen(&self) -> String {
+        let mut fen = String::from(if self.side_to_move() == Color::White {
+            "W"
+        } else {
+            "B"
+        });
+        for &(side, man, king) in &[('W', WHITE_MAN, WHITE_KING), ('B', BLACK_MAN, BLACK_KING)] {
+            fen.push(':');
+            fen.push(side);
+            let mut squares = vec![];
+            let mut field = 0;
+            let fields = self.size().fields();
+            while field < fields {
+                let piece = self.piece_at(field);
+                if piece == king {
+                    squares.push(format!("K{}", field + 1));
+                    field += 1;
+                } else if piece == man {
+                    let start = field;
+                    while field < fields && self.piece_at(field) == man {
+                        field += 1;
+                    }
+                    squares.push(match field - start {
+                        1 => format!("{}", field),
+                        2 => format!("{},{}", start + 1, field),
+                        _ => format!("{}-{}", start + 1, field),
+                    });
+                } else {
+                    field += 1;
+                }
+            }
+            fen.push_str(&squares.join(","));
+        }
+        fen
+    }
+
+    // field counts all squares, light and dark, row by row from the top left
     pub fn ascii_char(&self, field: usize) -> char {
-        if (field + (field / 10)) % 2 == 0 {
+        let rows = self.size().rows();
+        if (field + (field / rows)).is_multiple_of(2) {
             ' '
         } else {
             ASCII_CHARS[self.piece_at(field / 2) as usize]
@@ -314,17 +466,18 @@ impl Position {
     }
 
     pub fn ascii(&self) -> String {
+        let rows = self.size().rows();
         let mut ascii = String::new();
-        for field in 0..100 {
+        for field in 0..rows * rows {
             let c = self.ascii_char(field);
             ascii.push(c);
             ascii.push(c);
-            if (field == 9 && self.side_to_move() == Color::Black)
-                || (field == 99 && self.side_to_move() == Color::White)
+            if (field == rows - 1 && self.side_to_move() == Color::Black)
+                || (field == rows * rows - 1 && self.side_to_move() == Color::White)
             {
                 ascii.push_str("  *")
             }
-            if field % 10 == 9 {
+            if field % rows == rows - 1 {
                 ascii.push('\r');
                 ascii.push('\n');
             } else {
@@ -335,22 +488,22 @@ impl Position {
     }
 
     pub fn initial() -> Self {
-        let black = (0..20).fold(Self::create(), |pos, field| pos.put_piece(field, BLACK_MAN));
-        (30..50).fold(black, |pos, field| pos.put_piece(field, WHITE_MAN))
+        Self::initial_with_size(Size::Ten)
     }
 
+    // the number of fields tells the size of the board
     pub fn parse(fen: &str) -> Result<Self, String> {
-        if fen.len() < 11 {
+        if fen.len() < 9 {
             return Err("Invalid length".into());
         }
-        let mut position = Self::create();
-        let mut field = 0;
+        let mut black = false;
+        let mut fields = Vec::with_capacity(50);
 
         for (i, c) in fen.chars().enumerate() {
             if i == 0 {
                 match c {
                     'w' => (),
-                    'b' => position = position.toggle_side(),
+                    'b' => black = true,
                     _ => return Err("Invalid side to move".into()),
                 }
             } else {
@@ -386,25 +539,106 @@ impl Position {
                 match pieces {
                     Some((piece, count)) => {
                         for _ in 0..count {
-                            if field == 50 {
+                            if fields.len() == 50 {
                                 return Err(String::from("Too many fields"));
                             }
-                            position = position.put_piece(field, piece);
-                            field += 1;
+                            fields.push(piece);
                         }
                     }
                     None => return Err(format!("Invalid piece at {}", i)),
                 }
             }
         }
-        if field != 50 {
-            return Err(format!("Insufficient number of fields: {}", field));
+        let size = match fields.len() {
+            50 => Size::Ten,
+            32 => Size::Eight,
+            count => return Err(format!("Insufficient number of fields: {}", count)),
+        };
+        let position = fields
+            .into_iter()
+            .enumerate()
+            .fold(Self::with_size(size), |position, (field, piece)| {
+                position.put_piece(field, piece)
+            });
+        Ok(if black {
+            position.toggle_side()
+        } else {
+            position
+        })
+    }
+
+    pub fn parse_pdn(fen: &str) -> Result<Self, String> {
+        Self::parse_pdn_with_size(fen, Size::Ten)
+    }
+
+    // the squares do not tell the size of the board
+    pub fn parse_pdn_with_size(fen: &str, size: Size) -> Result<Self, String> {
+        let fen = fen.trim().trim_matches('"').trim_end_matches('.');
+        let mut sections = fen.split(':');
+        let mut position = match sections.next().map(str::trim) {
+            Some("W") | Some("w") => Self::with_size(size),
+            Some("B") | Some("b") => Self::with_size(size).toggle_side(),
+            _ => return Err("Invalid side to move".into()),
+        };
+
+        for section in sections.map(str::trim) {
+            let mut chars = section.chars();
+            let (man, king) = match chars.next() {
+                Some('W') | Some('w') => (WHITE_MAN, WHITE_KING),
+                Some('B') | Some('b') => (BLACK_MAN, BLACK_KING),
+                // half move clock and move number, as written by some tools
+                Some('H') | Some('F') => continue,
+                _ => return Err(format!("Invalid section {}", section)),
+            };
+            for square in chars.as_str().split(',').map(str::trim) {
+                if square.is_empty() {
+                    continue;
+                }
+                let (piece, range) = match square.strip_prefix(|c| c == 'K' || c == 'k') {
+                    Some(range) => (king, range),
+                    None => (man, square),
+                };
+                let (first, last) = match range.split_once('-') {
+                    Some((first, last)) => (parse_square(first, size)?, parse_square(last, size)?),
+                    None => {
+                        let field = parse_square(range, size)?;
+                        (field, field)
+                    }
+                };
+                if first > last {
+                    return Err(format!("Invalid range {}", square));
+                }
+                for field in first..=last {
+                    if !position.is_empty(field) {
+                        return Err(format!("Square {} occupied twice", field + 1));
+                    }
+                    // a man there would have been crowned
+                    if promote(size, field, piece) != piece {
+                        return Err(format!("Man on promotion square {}", field + 1));
+                    }
+                    position = position.put_piece(field, piece);
+                }
+            }
         }
         Ok(position)
     }
 }
 
+fn parse_square(square: &str, size: Size) -> Result<Field, String> {
+    match square.trim().parse::<Field>() {
+        Ok(number) if (1..=size.fields()).contains(&number) => Ok(number - 1),
+        _ => Err(format!("Invalid square {}", square)),
+    }
+}
+
 use std::fmt;
+use std::hash::{Hash, Hasher};
+
+impl Hash for Position {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.key);
+    }
+}
 
 impl fmt::Display for Position {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -514,8 +748,7 @@ fn from_fen() {
         .toggle_side();
     match Position::parse("bebeeeweeeeeBeeeWeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee") {
         Err(msg) => {
-            println!("{}", msg);
-            assert!(false);
+            panic!("{}", msg);
         }
         Ok(parsed) => assert!(constructed == parsed),
     }
@@ -545,8 +778,7 @@ fn from_sfen() {
         .toggle_side();
     match Position::parse("beb3w41B3W4555555") {
         Err(msg) => {
-            println!("{}", msg);
-            assert!(false);
+            panic!("{}", msg);
         }
         Ok(parsed) => assert!(constructed == parsed),
     }
@@ -564,8 +796,7 @@ fn from_ufen() {
         .toggle_side();
     match Position::parse("beb3i21B3W4555555") {
         Err(msg) => {
-            println!("{}", msg);
-            assert!(false);
+            panic!("{}", msg);
         }
         Ok(parsed) => assert!(constructed == parsed),
     }
@@ -574,20 +805,12 @@ fn from_ufen() {
 #[test]
 fn goerres_bayar() {
     let spaced = match Position::parse("w ce/bea/k/a2/2b2/5/r/r/et/eie") {
-        Err(msg) => {
-            println!("{}", msg);
-            assert!(false);
-            None
-        }
-        Ok(parsed) => Some(parsed),
+        Err(msg) => panic!("{}", msg),
+        Ok(parsed) => parsed,
     };
     let small = match Position::parse("wcebeaka22b25rreteie") {
-        Err(msg) => {
-            println!("{}", msg);
-            assert!(false);
-            None
-        }
-        Ok(parsed) => Some(parsed),
+        Err(msg) => panic!("{}", msg),
+        Ok(parsed) => parsed,
     };
     assert!(spaced == small);
 }
@@ -612,8 +835,148 @@ fn as_ascii() {
             assert_eq!(ascii.len(), 313);
         }
         Err(msg) => {
-            println!("{}", msg);
-            assert!(false);
+            panic!("{}", msg);
         }
     }
 }
+
+#[test]
+fn as_pdn_fen() {
+    assert_eq!("W:W31-50:B1-20", Position::initial().pdn_fen());
+    let constructed = Position::create()
+        .put_piece(1, BLACK_MAN)
+        .put_piece(5, WHITE_MAN)
+        .put_piece(6, WHITE_MAN)
+        .put_piece(11, BLACK_KING)
+        .put_piece(15, WHITE_KING)
+        .toggle_side();
+    assert_eq!("B:W6,7,K16:B2,K12", constructed.pdn_fen());
+}
+
+#[test]
+fn from_pdn_fen() {
+    let constructed = Position::create()
+        .put_piece(1, BLACK_MAN)
+        .put_piece(4, WHITE_KING)
+        .put_piece(30, WHITE_MAN)
+        .put_piece(0, BLACK_MAN)
+        .toggle_side();
+    match Position::parse_pdn("B:WK5,31:B1,2") {
+        Err(msg) => panic!("{}", msg),
+        Ok(parsed) => assert!(constructed == parsed),
+    }
+    match Position::parse_pdn("W:W31-50:B1-20") {
+        Err(msg) => panic!("{}", msg),
+        Ok(parsed) => assert!(Position::initial() == parsed),
+    }
+    match Position::parse_pdn("\"W:B1-20:W31-50:H0:F1.\"") {
+        Err(msg) => panic!("{}", msg),
+        Ok(parsed) => assert!(Position::initial() == parsed),
+    }
+}
+
+#[test]
+fn invalid_pdn_fen() {
+    assert!(Position::parse_pdn("X:W31-50:B1-20").is_err());
+    assert!(Position::parse_pdn("W:W31-51:B1-20").is_err());
+    assert!(Position::parse_pdn("W:W20-50:B1-20").is_err());
+    assert!(Position::parse_pdn("W:W35-31").is_err());
+    assert!(Position::parse_pdn("W:Z1").is_err());
+    assert!(Position::parse_pdn("W:W1:B20").is_err());
+    assert!(Position::parse_pdn("W:W31:B46").is_err());
+    assert!(Position::parse_pdn("W:WK1:BK46").is_ok());
+    assert!(Position::parse_pdn_with_size("W:W4:B20", Size::Eight).is_err());
+    assert!(Position::parse_pdn_with_size("W:W5:B29", Size::Eight).is_err());
+}
+
+#[test]
+fn pdn_fen_roundtrip() {
+    for fen in &[
+        "wkkkk55rrrr",
+        "w 5/3be/5/3be/web2/wewbe/ew3/3bb/5/3ww",
+        "b 5/el2/5/Bebew/2w2/5/eh2/3we/ew3/5",
+        "w 3We/5/5/5/l3/5/l3/ew3/b4/5",
+        "wcebeaka22b25rreteie",
+    ] {
+        let position = Position::parse(fen).unwrap();
+        let parsed = Position::parse_pdn(&position.pdn_fen()).unwrap();
+        assert!(position == parsed);
+        assert_eq!(position.sfen(), parsed.sfen());
+    }
+}
+
+#[test]
+fn eight_by_eight() {
+    let initial = Position::initial_with_size(Size::Eight);
+    assert_eq!(initial.size(), Size::Eight);
+    assert_eq!(initial.count_pieces(), 24);
+    assert_eq!(initial.sfen(), "wbbbbbbbbbbbb44wwwwwwwwwwww");
+    assert_eq!(initial.pdn_fen(), "W:W21-32:B1-12");
+    assert!(Position::parse("wccc44ttt").unwrap() == initial);
+    assert!(Position::parse(&initial.fen()).unwrap() == initial);
+    assert!(Position::parse_pdn_with_size("W:W21-32:B1-12", Size::Eight).unwrap() == initial);
+    assert!(Position::parse_pdn_with_size("W:W33:B1", Size::Eight).is_err());
+    assert!(Position::parse_pdn("W:W21-32:B1-12").unwrap() != initial);
+    assert_eq!(initial.ascii().len(), 203);
+    assert_eq!(promote(Size::Eight, 3, WHITE_MAN), WHITE_KING);
+    assert_eq!(promote(Size::Eight, 4, WHITE_MAN), WHITE_MAN);
+    assert_eq!(promote(Size::Eight, 28, BLACK_MAN), BLACK_KING);
+}
+
+#[cfg(test)]
+fn full_key(position: &Position) -> Key {
+    let side = if position.side_to_move() == Color::White {
+        0
+    } else {
+        SIDE_KEY
+    };
+    (0..50).fold(side, |key, field| {
+        key ^ piece_key(position.piece_at(field), field)
+    })
+}
+
+#[test]
+fn incremental_key() {
+    use super::generator::Generator;
+
+    let generator = Generator::create();
+    for fen in &[
+        "wkkkk55rrrr",
+        "w 5/3be/5/3be/web2/wewbe/ew3/3bb/5/3ww",
+        "b 2b2/b4/3bb/5/wewww/3we/4B/ww2w/eww2/5",
+        "w 5/bw3/5/5/5/5/5/5/5/5",
+    ] {
+        let mut position = Position::parse(fen).unwrap();
+        assert_eq!(position.key(), full_key(&position));
+        for ply in 0..60 {
+            let moves = generator.legal_moves(&position);
+            if moves.is_empty() {
+                break;
+            }
+            position = position.go(&moves[ply % moves.len()]);
+            assert_eq!(position.key(), full_key(&position));
+        }
+    }
+}
+
+#[test]
+fn key_identifies_position() {
+    assert_eq!(Position::create().key(), 0);
+    assert_ne!(
+        Position::initial().key(),
+        Position::initial().toggle_side().key()
+    );
+    assert_eq!(
+        Position::initial().key(),
+        Position::parse_pdn("W:W31-50:B1-20").unwrap().key()
+    );
+    let promoted = Position::create()
+        .put_piece(1, BLACK_MAN)
+        .put_piece(5, WHITE_MAN)
+        .go(&Move::shift(5, 0));
+    let constructed = Position::create()
+        .put_piece(1, BLACK_MAN)
+        .put_piece(0, WHITE_KING)
+        .toggle_side();
+    assert_eq!(promoted.key(), constructed.key());
+}
@@ -0,0 +1,141 @@
+use super::position::{Position, Size};
+
+// which capture must be played when there is a choice
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Majority {
+    // the one taking the most pieces
+    Most,
+    // any capture, but it has to be finished
+    Free,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rules {
+    pub size: Size,
+    pub men_capture_backwards: bool,
+    pub flying_kings: bool,
+    pub majority: Majority,
+    // a man reaching the last row while capturing goes on as a king
+    pub promote_during_capture: bool,
+    // antidraughts: the side without pieces or moves wins
+    pub losing: bool,
+    // killer draughts: a king taking a king last lands right behind it
+    pub killer: bool,
+}
+
+impl Rules {
+    pub fn international() -> Rules {
+        Rules {
+            size: Size::Ten,
+            men_capture_backwards: true,
+            flying_kings: true,
+            majority: Majority::Most,
+            promote_during_capture: false,
+            losing: false,
+            killer: false,
+        }
+    }
+
+    // international rules on the small board
+    pub fn brazilian() -> Rules {
+        Rules {
+            size: Size::Eight,
+            ..Rules::international()
+        }
+    }
+
+    pub fn russian() -> Rules {
+        Rules {
+            size: Size::Eight,
+            majority: Majority::Free,
+            promote_during_capture: true,
+            ..Rules::international()
+        }
+    }
+
+    pub fn english() -> Rules {
+        Rules {
+            size: Size::Eight,
+            men_capture_backwards: false,
+            flying_kings: false,
+            majority: Majority::Free,
+            promote_during_capture: false,
+            losing: false,
+            killer: false,
+        }
+    }
+
+    // international with the killer landing, which leaves fewer drawn endgames
+    pub fn killer() -> Rules {
+        Rules {
+            killer: true,
+            ..Rules::international()
+        }
+    }
+
+    // the same moves as international, played to lose everything
+    pub fn antidraughts() -> Rules {
+        Rules {
+            losing: true,
+            ..Rules::international()
+        }
+    }
+
+    // frisian draughts needs orthogonal captures and a majority by value, which are not here
+    pub fn parse(name: &str) -> Result<Rules, String> {
+        match name.to_lowercase().as_str() {
+            "international" => Ok(Rules::international()),
+            "brazilian" => Ok(Rules::brazilian()),
+            "russian" => Ok(Rules::russian()),
+            "english" | "checkers" => Ok(Rules::english()),
+            "killer" => Ok(Rules::killer()),
+            "antidraughts" | "giveaway" | "losing" => Ok(Rules::antidraughts()),
+            "frisian" => Err("Frisian rules are not supported".into()),
+            _ => Err(format!("Unknown rules {}", name)),
+        }
+    }
+
+    // by the first number of the pdn game type
+    pub fn from_game_type(game_type: &str) -> Result<Rules, String> {
+        match game_type.split(',').next().map(str::trim) {
+            Some("20") => Ok(Rules::international()),
+            Some("21") => Ok(Rules::english()),
+            Some("25") => Ok(Rules::russian()),
+            Some("26") => Ok(Rules::brazilian()),
+            _ => Err(format!("Unsupported game type {}", game_type)),
+        }
+    }
+
+    pub fn game_type(&self) -> Option<&'static str> {
+        [
+            (Rules::international(), "20"),
+            (Rules::english(), "21"),
+            (Rules::russian(), "25"),
+            (Rules::brazilian(), "26"),
+        ]
+        .iter()
+        .find(|(rules, _)| rules == self)
+        .map(|&(_, game_type)| game_type)
+    }
+
+    pub fn initial(&self) -> Position {
+        Position::initial_with_size(self.size)
+    }
+}
+
+#[test]
+fn parse() {
+    assert_eq!(Rules::parse("Russian"), Ok(Rules::russian()));
+    assert_eq!(Rules::parse("international"), Ok(Rules::international()));
+    assert!(Rules::parse("giveaway").unwrap().losing);
+    assert!(Rules::parse("Killer").unwrap().killer);
+    assert!(Rules::parse("frisian").is_err());
+    assert!(Rules::parse("chess").is_err());
+}
+
+#[test]
+fn game_type() {
+    assert_eq!(Rules::from_game_type("25,W,8,8,A1,0"), Ok(Rules::russian()));
+    assert_eq!(Rules::brazilian().game_type(), Some("26"));
+    assert!(Rules::from_game_type("30").is_err());
+}
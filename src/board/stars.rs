@@ -1,30 +1,37 @@
 use super::coords::{Coords, MinXy};
-use super::position::Field;
+use super::position::{Field, Size};
 
-fn star(mid: Field) -> Option<[Field; 5]> {
-    let mid = Coords::from(mid);
-    if mid.min_x() == mid.x || mid.max_x() == mid.x || mid.min_y() == mid.y || mid.max_y() == mid.y
+fn star(size: Size, mid: Field) -> Option<[Field; 5]> {
+    let mid = Coords::on(size, mid);
+    if mid.min_x(size) == mid.x
+        || mid.max_x(size) == mid.x
+        || mid.min_y(size) == mid.y
+        || mid.max_y(size) == mid.y
     {
         None
     } else {
         Some([
-            Field::from(Coords {
+            Coords {
                 x: mid.x,
                 y: mid.y + 1,
-            }),
-            Field::from(Coords {
+            }
+            .field_on(size),
+            Coords {
                 x: mid.x + 1,
                 y: mid.y,
-            }),
-            Field::from(Coords { x: mid.x, y: mid.y }),
-            Field::from(Coords {
+            }
+            .field_on(size),
+            Coords { x: mid.x, y: mid.y }.field_on(size),
+            Coords {
                 x: mid.x - 1,
                 y: mid.y,
-            }),
-            Field::from(Coords {
+            }
+            .field_on(size),
+            Coords {
                 x: mid.x,
                 y: mid.y - 1,
-            }),
+            }
+            .field_on(size),
         ])
     }
 }
@@ -36,11 +43,13 @@ pub struct Stars {
 }
 
 impl Stars {
-    pub fn create() -> Stars {
-        let stars: Vec<_> = (0..50).filter_map(star).collect();
-        let positions = (0..50)
+    pub fn create(size: Size) -> Stars {
+        let stars: Vec<_> = (0..size.fields())
+            .filter_map(|mid| star(size, mid))
+            .collect();
+        let positions = (0..size.fields())
             .map(|field| {
-                (0..32)
+                (0..stars.len())
                     .filter_map(|star| {
                         stars[star]
                             .iter()
@@ -56,30 +65,31 @@ impl Stars {
 
 #[test]
 fn corner() {
-    assert_eq!(star(0), None)
+    assert_eq!(star(Size::Ten, 0), None)
 }
 
 #[test]
 fn top() {
-    assert_eq!(star(2), None)
+    assert_eq!(star(Size::Ten, 2), None)
 }
 
 #[test]
 fn bottom() {
-    assert_eq!(star(47), None)
+    assert_eq!(star(Size::Ten, 47), None)
 }
 
 #[test]
 fn odd() {
-    assert_eq!(star(7), Some([1, 2, 7, 11, 12]))
+    assert_eq!(star(Size::Ten, 7), Some([1, 2, 7, 11, 12]))
 }
 
 #[test]
 fn even() {
-    assert_eq!(star(12), Some([7, 8, 12, 17, 18]))
+    assert_eq!(star(Size::Ten, 12), Some([7, 8, 12, 17, 18]))
 }
 
 #[test]
 fn star_count() {
-    assert_eq!((0..50).filter_map(star).count(), 32)
+    assert_eq!((0..50).filter_map(|mid| star(Size::Ten, mid)).count(), 32);
+    assert_eq!((0..32).filter_map(|mid| star(Size::Eight, mid)).count(), 18);
 }
@@ -21,27 +21,31 @@ impl PositionStats {
         let mut voffset_black = [0; 10];
         let mut hoffset_white = [0; 10];
         let mut hoffset_black = [0; 10];
-        let mut vmin_white = 9;
+        let size = position.size();
+        let (rows, per_row) = (size.rows(), size.per_row());
+        // the columns of a small board are centered, so the tables stay symmetric
+        let margin = (10 - rows) / 2;
+        let mut vmin_white = rows - 1;
         let mut vmax_white = 0;
-        let mut vmin_black = 9;
+        let mut vmin_black = rows - 1;
         let mut vmax_black = 0;
 
-        for field in 0..50 {
+        for field in 0..size.fields() {
             let piece = position.piece_at(field);
             piece_count[piece as usize] += 1;
             match piece {
                 WHITE_MAN => {
-                    let x = 1 + 2 * (field % 5) - field / 5 % 2;
+                    let x = margin + 1 + 2 * (field % per_row) - field / per_row % 2;
                     hoffset_white[x] += 1;
-                    let y = 9 - field / 5;
+                    let y = rows - 1 - field / per_row;
                     voffset_white[y] += 1;
                     vmin_white = min(vmin_white, y);
                     vmax_white = max(vmax_white, y);
                 }
                 BLACK_MAN => {
-                    let x = 8 - 2 * (field % 5) + field / 5 % 2;
+                    let x = margin + rows - 2 - 2 * (field % per_row) + field / per_row % 2;
                     hoffset_black[x] += 1;
-                    let y = field / 5;
+                    let y = field / per_row;
                     voffset_black[y] += 1;
                     vmin_black = min(vmin_black, y);
                     vmax_black = max(vmax_black, y);
@@ -56,16 +60,8 @@ impl PositionStats {
             voffset_black,
             hoffset_white,
             hoffset_black,
-            height_white: if vmax_white > vmin_white {
-                vmax_white - vmin_white
-            } else {
-                0
-            },
-            height_black: if vmax_black > vmin_black {
-                vmax_black - vmin_black
-            } else {
-                0
-            },
+            height_white: vmax_white.saturating_sub(vmin_white),
+            height_black: vmax_black.saturating_sub(vmin_black),
         }
     }
 }
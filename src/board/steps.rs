@@ -1,49 +1,49 @@
 use std::ops::Range;
 
 use super::coords::{Coords, MinXy};
-use super::position::Field;
+use super::position::{Field, Size};
 
-fn all<F, G>(generator: F) -> Vec<G>
+fn all<F, G>(size: Size, generator: F) -> Vec<G>
 where
-    F: Fn(Field) -> G,
+    F: Fn(Size, Field) -> G,
 {
-    let mut result = Vec::with_capacity(50);
-    for field in 0..50 {
-        result.push(generator(field));
+    let mut result = Vec::with_capacity(size.fields());
+    for field in 0..size.fields() {
+        result.push(generator(size, field));
     }
     result
 }
 
-fn path<F>(len: i8, generator: F) -> Vec<Field>
+fn path<F>(size: Size, len: i8, generator: F) -> Vec<Field>
 where
     F: Fn(i8) -> Coords,
 {
     let mut result = vec![];
     for d in 1..=len {
-        result.push(Field::from(generator(d)));
+        result.push(generator(d).field_on(size));
     }
     result
 }
 
-fn paths(field: Field) -> [Box<[Field]>; 4] {
-    let coords = Coords::from(field);
+fn paths(size: Size, field: Field) -> [Box<[Field]>; 4] {
+    let coords = Coords::on(size, field);
     [
-        path(coords.max_x() - coords.x, |d| Coords {
+        path(size, coords.max_x(size) - coords.x, |d| Coords {
             x: coords.x + d,
             y: coords.y,
         })
         .into_boxed_slice(),
-        path(coords.max_y() - coords.y, |d| Coords {
+        path(size, coords.max_y(size) - coords.y, |d| Coords {
             x: coords.x,
             y: coords.y + d,
         })
         .into_boxed_slice(),
-        path(coords.x - coords.min_x(), |d| Coords {
+        path(size, coords.x - coords.min_x(size), |d| Coords {
             x: coords.x - d,
             y: coords.y,
         })
         .into_boxed_slice(),
-        path(coords.y - coords.min_y(), |d| Coords {
+        path(size, coords.y - coords.min_y(size), |d| Coords {
             x: coords.x,
             y: coords.y - d,
         })
@@ -51,9 +51,9 @@ fn paths(field: Field) -> [Box<[Field]>; 4] {
     ]
 }
 
-fn steps(field: Field, range: Range<Field>) -> Vec<Field> {
+fn steps(size: Size, field: Field, range: Range<Field>) -> Vec<Field> {
     let mut result = vec![];
-    let paths = paths(field);
+    let paths = paths(size, field);
     for i in range {
         if !paths[i].is_empty() {
             result.push(paths[i][0]);
@@ -62,66 +62,54 @@ fn steps(field: Field, range: Range<Field>) -> Vec<Field> {
     result
 }
 
-fn white_steps(field: Field) -> Vec<Field> {
-    steps(field, 0..2)
+fn white_steps(size: Size, field: Field) -> Vec<Field> {
+    steps(size, field, 0..2)
 }
 
 #[test]
 fn white_steps_side() {
-    let steps = white_steps(35);
+    let steps = white_steps(Size::Ten, 35);
     assert_eq!(steps.len(), 1);
     for step in steps.into_iter() {
-        assert!(match step {
-            30 => true,
-            _ => false,
-        });
+        assert!(matches!(step, 30));
     }
 }
 
 #[test]
 fn white_steps_center() {
-    let steps = white_steps(36);
+    let steps = white_steps(Size::Ten, 36);
     assert_eq!(steps.len(), 2);
     for step in steps.into_iter() {
-        assert!(match step {
-            30 | 31 => true,
-            _ => false,
-        });
+        assert!(matches!(step, 30 | 31));
     }
 }
 
-fn black_steps(field: Field) -> Vec<Field> {
-    steps(field, 2..4)
+fn black_steps(size: Size, field: Field) -> Vec<Field> {
+    steps(size, field, 2..4)
 }
 
 #[test]
 fn black_steps_side() {
-    let steps = black_steps(35);
+    let steps = black_steps(Size::Ten, 35);
     assert_eq!(steps.len(), 1);
     for step in steps.into_iter() {
-        assert!(match step {
-            40 => true,
-            _ => false,
-        });
+        assert!(matches!(step, 40));
     }
 }
 
 #[test]
 fn black_steps_center() {
-    let steps = black_steps(36);
+    let steps = black_steps(Size::Ten, 36);
     assert_eq!(steps.len(), 2);
     for step in steps.into_iter() {
-        assert!(match step {
-            40 | 41 => true,
-            _ => false,
-        });
+        assert!(matches!(step, 40 | 41));
     }
 }
 
-fn short_jumps(field: Field) -> Vec<(Field, Field)> {
+fn jumps(size: Size, field: Field, range: Range<Field>) -> Vec<(Field, Field)> {
     let mut result = vec![];
-    let paths = paths(field);
-    for path in &paths {
+    let paths = paths(size, field);
+    for path in &paths[range] {
         if path.len() > 1 {
             result.push((path[0], path[1]));
         }
@@ -129,38 +117,42 @@ fn short_jumps(field: Field) -> Vec<(Field, Field)> {
     result
 }
 
+fn short_jumps(size: Size, field: Field) -> Vec<(Field, Field)> {
+    jumps(size, field, 0..4)
+}
+
+fn white_jumps(size: Size, field: Field) -> Vec<(Field, Field)> {
+    jumps(size, field, 0..2)
+}
+
+fn black_jumps(size: Size, field: Field) -> Vec<(Field, Field)> {
+    jumps(size, field, 2..4)
+}
+
 #[test]
 fn short_jumps_side() {
-    let steps = short_jumps(30);
+    let steps = short_jumps(Size::Ten, 30);
     assert_eq!(steps.len(), 2);
     for step in steps.into_iter() {
-        assert!(match step {
-            (26, 21) | (36, 41) => true,
-            _ => false,
-        });
+        assert!(matches!(step, (26, 21) | (36, 41)));
     }
 }
 
 #[test]
 fn short_jumps_center() {
-    let steps = short_jumps(31);
+    let steps = short_jumps(Size::Ten, 31);
     assert_eq!(steps.len(), 4);
     for step in steps.into_iter() {
-        assert!(match step {
-            (26, 20) | (27, 22) | (36, 40) | (37, 42) => true,
-            _ => false,
-        });
+        assert!(matches!(step, (26, 20) | (27, 22) | (36, 40) | (37, 42)));
     }
 }
 
 #[cfg(test)]
 fn long_steps(field: Field) -> Vec<Field> {
     let mut v = vec![];
-    let paths = paths(field);
-    for i in 0..4 {
-        for j in 0..paths[i].len() {
-            v.push(paths[i][j]);
-        }
+    let paths = paths(Size::Ten, field);
+    for path in &paths {
+        v.extend_from_slice(path);
     }
     v
 }
@@ -170,10 +162,10 @@ fn long_steps_side() {
     let steps = long_steps(30);
     assert_eq!(steps.len(), 11);
     for step in steps.into_iter() {
-        assert!(match step {
-            25 | 26 | 21 | 17 | 12 | 8 | 3 | 35 | 36 | 41 | 47 => true,
-            _ => false,
-        });
+        assert!(matches!(
+            step,
+            25 | 26 | 21 | 17 | 12 | 8 | 3 | 35 | 36 | 41 | 47
+        ));
     }
 }
 
@@ -182,28 +174,40 @@ fn long_steps_center() {
     let steps = long_steps(31);
     assert_eq!(steps.len(), 15);
     for step in steps.into_iter() {
-        assert!(match step {
-            26 | 20 | 15 | 27 | 22 | 18 | 13 | 9 | 4 | 36 | 40 | 45 | 37 | 42 | 48 => true,
-            _ => false,
-        });
+        assert!(matches!(
+            step,
+            26 | 20 | 15 | 27 | 22 | 18 | 13 | 9 | 4 | 36 | 40 | 45 | 37 | 42 | 48
+        ));
     }
 }
 
+#[test]
+fn forward_jumps_eight() {
+    assert_eq!(short_jumps(Size::Eight, 13).len(), 4);
+    assert_eq!(white_jumps(Size::Eight, 13), vec![(9, 6), (8, 4)]);
+    assert_eq!(black_jumps(Size::Eight, 13), vec![(16, 20), (17, 22)]);
+    assert_eq!(white_steps(Size::Eight, 28), vec![24]);
+}
+
 #[derive(Clone)]
 pub struct Steps {
     all_white_steps: Vec<Vec<Field>>,
     all_black_steps: Vec<Vec<Field>>,
     all_short_jumps: Vec<Vec<(Field, Field)>>,
+    all_white_jumps: Vec<Vec<(Field, Field)>>,
+    all_black_jumps: Vec<Vec<(Field, Field)>>,
     all_paths: Vec<[Box<[Field]>; 4]>,
 }
 
 impl Steps {
-    pub fn create() -> Steps {
+    pub fn create(size: Size) -> Steps {
         Steps {
-            all_white_steps: all(white_steps),
-            all_black_steps: all(black_steps),
-            all_short_jumps: all(short_jumps),
-            all_paths: all(paths),
+            all_white_steps: all(size, white_steps),
+            all_black_steps: all(size, black_steps),
+            all_short_jumps: all(size, short_jumps),
+            all_white_jumps: all(size, white_jumps),
+            all_black_jumps: all(size, black_jumps),
+            all_paths: all(size, paths),
         }
     }
 
@@ -216,6 +220,13 @@ impl Steps {
     pub fn short_jumps(&self, field: Field) -> &[(Field, Field)] {
         &self.all_short_jumps[field]
     }
+    // the jumps of a man that cannot capture backwards
+    pub fn white_jumps(&self, field: Field) -> &[(Field, Field)] {
+        &self.all_white_jumps[field]
+    }
+    pub fn black_jumps(&self, field: Field) -> &[(Field, Field)] {
+        &self.all_black_jumps[field]
+    }
     pub fn paths(&self, field: Field) -> &[Box<[Field]>; 4] {
         &self.all_paths[field]
     }
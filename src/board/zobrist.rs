@@ -0,0 +1,75 @@
+use std::hash::{BuildHasherDefault, Hasher};
+
+use super::piece::{Piece, EMPTY};
+use super::position::Field;
+
+pub type Key = u64;
+
+const fn split_mix(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+const fn piece_keys() -> [[Key; 50]; 4] {
+    let mut keys = [[0; 50]; 4];
+    let mut state = 0x2016_0d1a_0a0c_0a11;
+    let mut piece = 0;
+    while piece < 4 {
+        let mut field = 0;
+        while field < 50 {
+            let (next, key) = split_mix(state);
+            keys[piece][field] = key;
+            state = next;
+            field += 1;
+        }
+        piece += 1;
+    }
+    keys
+}
+
+const PIECE_KEYS: [[Key; 50]; 4] = piece_keys();
+pub const SIDE_KEY: Key = split_mix(0x5eed).1;
+
+pub fn piece_key(piece: Piece, field: Field) -> Key {
+    if piece == EMPTY {
+        0
+    } else {
+        PIECE_KEYS[piece as usize - 1][field]
+    }
+}
+
+// Keys are already uniformly distributed, so hash maps can use them as is
+#[derive(Default)]
+pub struct KeyHasher {
+    key: Key,
+}
+
+impl Hasher for KeyHasher {
+    fn finish(&self) -> u64 {
+        self.key
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.key = self.key.rotate_left(8) ^ Key::from(byte);
+        }
+    }
+
+    fn write_u64(&mut self, key: u64) {
+        self.key ^= key;
+    }
+}
+
+pub type KeyBuildHasher = BuildHasherDefault<KeyHasher>;
+
+#[test]
+fn distinct_keys() {
+    let mut keys: Vec<Key> = PIECE_KEYS.iter().flatten().cloned().collect();
+    keys.push(SIDE_KEY);
+    keys.sort_unstable();
+    keys.dedup();
+    assert_eq!(keys.len(), 201);
+}
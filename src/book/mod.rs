@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::board::game::Outcome;
+use crate::board::generator::Generator;
+use crate::board::mv::Move;
+use crate::board::piece::Color;
+use crate::board::position::Position;
+use crate::board::zobrist::Key;
+use crate::pdn::game::PdnGame;
+
+const MAGIC: &[u8; 4] = b"DBK1";
+const RECORD: usize = 28;
+
+// the results after one move, for the side that played it
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BookMove {
+    pub next: Key,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl BookMove {
+    pub fn create(next: Key) -> BookMove {
+        BookMove {
+            next,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    pub fn points(&self) -> u32 {
+        2 * self.wins + self.draws
+    }
+
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            0.5
+        } else {
+            self.points() as f64 / (2 * self.games()) as f64
+        }
+    }
+
+    fn add(&mut self, other: &BookMove) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Selection {
+    // the highest score, the most games on a tie
+    Best,
+    // random, in proportion to the points scored
+    Weighted,
+    // random among the moves scoring within the margin of the best
+    Margin(f64),
+}
+
+// xorshift64*, good enough to vary the openings
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn create(seed: u64) -> Random {
+        Random { state: seed | 1 }
+    }
+
+    pub fn from_time() -> Random {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        Random::create(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound.max(1)
+    }
+}
+
+#[derive(Default)]
+pub struct Book {
+    entries: HashMap<Key, Vec<BookMove>>,
+}
+
+impl Book {
+    pub fn create() -> Book {
+        Book::default()
+    }
+
+    // the number of positions
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn add(&mut self, key: Key, book_move: &BookMove) {
+        let moves = self.entries.entry(key).or_default();
+        match moves.iter_mut().find(|found| found.next == book_move.next) {
+            Some(found) => found.add(book_move),
+            None => moves.push(*book_move),
+        }
+    }
+
+    // counts the first plies of a game, skipping games without a result
+    pub fn add_game(&mut self, game: &PdnGame, plies: usize) -> Result<(), String> {
+        let outcome = match Outcome::parse_pdn(game.result()) {
+            Some(outcome) => outcome,
+            None => return Ok(()),
+        };
+        let positions = game.positions()?;
+        for pair in positions.windows(2).take(plies) {
+            let mut book_move = BookMove::create(pair[1].key());
+            match (outcome, pair[0].side_to_move()) {
+                (Outcome::Draw, _) => book_move.draws = 1,
+                (Outcome::WhiteWins, Color::White) | (Outcome::BlackWins, Color::Black) => {
+                    book_move.wins = 1
+                }
+                _ => book_move.losses = 1,
+            }
+            self.add(pair[0].key(), &book_move);
+        }
+        Ok(())
+    }
+
+    pub fn build<I>(games: I, plies: usize) -> Result<Book, String>
+    where
+        I: Iterator<Item = Result<PdnGame, String>>,
+    {
+        let mut book = Book::create();
+        for game in games {
+            book.add_game(&game?, plies)?;
+        }
+        Ok(book)
+    }
+
+    // drops the moves played less often than the minimum
+    pub fn prune(&mut self, min_games: u32) {
+        for moves in self.entries.values_mut() {
+            moves.retain(|book_move| book_move.games() >= min_games);
+        }
+        self.entries.retain(|_, moves| !moves.is_empty());
+    }
+
+    // the legal moves in the book, most played first
+    pub fn moves(&self, generator: &Generator, position: &Position) -> Vec<(Move, BookMove)> {
+        let entry = match self.entries.get(&position.key()) {
+            Some(entry) => entry,
+            None => return vec![],
+        };
+        let mut moves: Vec<(Move, BookMove)> = generator
+            .legal_moves(position)
+            .into_iter()
+            .filter_map(|mv| {
+                let next = position.go(&mv).key();
+                entry
+                    .iter()
+                    .find(|book_move| book_move.next == next)
+                    .map(|book_move| (mv, *book_move))
+            })
+            .collect();
+        moves.sort_by_key(|(_, book_move)| u32::MAX - book_move.games());
+        moves
+    }
+
+    pub fn choose(
+        &self,
+        generator: &Generator,
+        position: &Position,
+        selection: Selection,
+        random: &mut Random,
+    ) -> Option<(Move, BookMove)> {
+        let moves = self.moves(generator, position);
+        let best = moves.iter().copied().max_by(|(_, a), (_, b)| {
+            a.score()
+                .total_cmp(&b.score())
+                .then(a.games().cmp(&b.games()))
+        })?;
+        match selection {
+            Selection::Best => Some(best),
+            Selection::Weighted => {
+                // moves that only lost never come up
+                let total: u64 = moves.iter().map(|(_, m)| m.points() as u64).sum();
+                if total == 0 {
+                    return None;
+                }
+                let mut pick = random.below(total);
+                moves.into_iter().find(|(_, book_move)| {
+                    let points = book_move.points() as u64;
+                    if pick < points {
+                        true
+                    } else {
+                        pick -= points;
+                        false
+                    }
+                })
+            }
+            Selection::Margin(margin) => {
+                let close: Vec<(Move, BookMove)> = moves
+                    .into_iter()
+                    .filter(|(_, book_move)| book_move.score() + margin >= best.1.score())
+                    .collect();
+                Some(close[random.below(close.len() as u64) as usize])
+            }
+        }
+    }
+
+    // sorted records of position key, next key and the three counts
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let mut records: Vec<(Key, &BookMove)> = self
+            .entries
+            .iter()
+            .flat_map(|(&key, moves)| moves.iter().map(move |book_move| (key, book_move)))
+            .collect();
+        records.sort_by_key(|&(key, book_move)| (key, book_move.next));
+        let mut bytes = MAGIC.to_vec();
+        bytes.reserve(records.len() * RECORD);
+        for (key, book_move) in records {
+            bytes.extend_from_slice(&key.to_le_bytes());
+            bytes.extend_from_slice(&book_move.next.to_le_bytes());
+            bytes.extend_from_slice(&book_move.wins.to_le_bytes());
+            bytes.extend_from_slice(&book_move.draws.to_le_bytes());
+            bytes.extend_from_slice(&book_move.losses.to_le_bytes());
+        }
+        fs::write(path, bytes).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn read(path: &Path) -> Result<Book, String> {
+        let bytes = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(format!("{}: not a book", path.display()));
+        }
+        let records = &bytes[MAGIC.len()..];
+        if records.len() % RECORD != 0 {
+            return Err(format!("{}: truncated", path.display()));
+        }
+        let mut book = Book::create();
+        for record in records.chunks(RECORD) {
+            let u64_at = |at: usize| u64::from_le_bytes(record[at..at + 8].try_into().unwrap());
+            let u32_at = |at: usize| u32::from_le_bytes(record[at..at + 4].try_into().unwrap());
+            let book_move = BookMove {
+                next: u64_at(8),
+                wins: u32_at(16),
+                draws: u32_at(20),
+                losses: u32_at(24),
+            };
+            book.add(u64_at(0), &book_move);
+        }
+        Ok(book)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pdn::reader::PdnReader;
+    use std::env;
+
+    const GAMES: &str = "[Result \"2-0\"]\n1. 32-28 19-23 2. 28x19 14x23 2-0\n\n\
+                         [Result \"1-1\"]\n1. 32-28 18-23 1-1\n\n\
+                         [Result \"0-2\"]\n1. 33-28 19-23 0-2\n\n\
+                         [Result \"*\"]\n1. 31-27 *\n";
+
+    fn book() -> Book {
+        Book::build(PdnReader::create(GAMES.as_bytes()), 2).unwrap()
+    }
+
+    #[test]
+    fn statistics() {
+        let generator = Generator::create();
+        let book = book();
+        let moves = book.moves(&generator, &Position::initial());
+        assert_eq!(moves.len(), 2);
+        assert_eq!(
+            generator.to_short_string(&Position::initial(), &moves[0].0),
+            "32-28"
+        );
+        assert_eq!(
+            (moves[0].1.wins, moves[0].1.draws, moves[0].1.losses),
+            (1, 1, 0)
+        );
+        assert_eq!((moves[1].1.wins, moves[1].1.losses), (0, 1));
+
+        // only the first two plies are in the book
+        let after = Position::initial().go(&moves[0].0);
+        assert_eq!(book.moves(&generator, &after).len(), 2);
+        assert_eq!(book.len(), 3);
+    }
+
+    #[test]
+    fn selection() {
+        let generator = Generator::create();
+        let book = book();
+        let initial = Position::initial();
+        let mut random = Random::create(7);
+        let (best, _) = book
+            .choose(&generator, &initial, Selection::Best, &mut random)
+            .unwrap();
+        assert_eq!(generator.to_short_string(&initial, &best), "32-28");
+        for _ in 0..20 {
+            let (weighted, _) = book
+                .choose(&generator, &initial, Selection::Weighted, &mut random)
+                .unwrap();
+            assert!(weighted == best);
+        }
+        let picks: Vec<Move> = (0..50)
+            .map(|_| {
+                book.choose(&generator, &initial, Selection::Margin(1.0), &mut random)
+                    .unwrap()
+                    .0
+            })
+            .collect();
+        assert!(picks.iter().any(|&mv| mv != best));
+    }
+
+    #[test]
+    fn file() {
+        let path = env::temp_dir().join(format!("book-{}.dbk", std::process::id()));
+        let book = book();
+        book.write(&path).unwrap();
+        let read = Book::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(read.len(), book.len());
+        let generator = Generator::create();
+        assert!(
+            read.moves(&generator, &Position::initial())
+                == book.moves(&generator, &Position::initial())
+        );
+    }
+}
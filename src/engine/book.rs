@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use super::time::TimeControl;
+use super::{Engine, EngineResult};
+use crate::algorithm::judge::ZERO_EVAL;
+use crate::algorithm::meta::Meta;
+use crate::algorithm::stop::Stop;
+use crate::board::generator::Generator;
+use crate::board::position::Position;
+use crate::board::rules::Rules;
+use crate::book::{Book, Random, Selection};
+
+// answers from the book while the position is in it, and leaves the rest to the engine
+pub struct BookEngine<E: Engine> {
+    engine: E,
+    book: Arc<Book>,
+    selection: Selection,
+    random: Random,
+    generator: Generator,
+    answer: Option<EngineResult>,
+    in_book: bool,
+}
+
+impl<E: Engine> BookEngine<E> {
+    pub fn create(engine: E, book: Arc<Book>, selection: Selection) -> BookEngine<E> {
+        BookEngine {
+            engine,
+            book,
+            selection,
+            random: Random::from_time(),
+            generator: Generator::create(),
+            answer: None,
+            in_book: false,
+        }
+    }
+
+    pub fn set_random(&mut self, random: Random) {
+        self.random = random;
+    }
+
+    pub fn engine(&mut self) -> &mut E {
+        &mut self.engine
+    }
+}
+
+impl<E: Engine> Iterator for BookEngine<E> {
+    type Item = EngineResult;
+    fn next(&mut self) -> Option<EngineResult> {
+        if self.in_book {
+            self.answer.take()
+        } else {
+            self.engine.next()
+        }
+    }
+}
+
+impl<E: Engine> Engine for BookEngine<E> {
+    fn display_name(&self) -> &str {
+        self.engine.display_name()
+    }
+
+    fn set_position(&mut self, position: &Position) {
+        let chosen = self
+            .book
+            .choose(&self.generator, position, self.selection, &mut self.random);
+        self.in_book = chosen.is_some();
+        self.answer = chosen.map(|(mv, _)| EngineResult::create(mv, ZERO_EVAL, Meta::create()));
+        if !self.in_book {
+            self.engine.set_position(position);
+        }
+    }
+
+    fn set_time_control(&mut self, time_control: &TimeControl) {
+        self.engine.set_time_control(time_control);
+    }
+
+    fn stop_handle(&self) -> Option<Stop> {
+        self.engine.stop_handle()
+    }
+
+    fn set_rules(&mut self, rules: Rules) {
+        self.engine.set_rules(rules);
+        self.generator = Generator::with_rules(rules);
+    }
+}
+
+#[test]
+fn book_moves() {
+    use super::randaap::RandAap;
+    use crate::board::position::Size;
+    use crate::pdn::reader::PdnReader;
+
+    let games = "[Result \"2-0\"]\n1. 31-27 16-21 2-0\n";
+    let book = Arc::new(Book::build(PdnReader::create(games.as_bytes()), 2).unwrap());
+    let generator = Generator::create();
+    let mut engine = BookEngine::create(RandAap::create(1_000), book, Selection::Best);
+
+    let initial = Position::initial();
+    engine.set_position(&initial);
+    let result = engine.by_ref().last().unwrap();
+    assert_eq!(generator.to_short_string(&initial, &result.mv), "31-27");
+
+    let out_of_book = initial.go(&generator.parse_move(&initial, "32-28").unwrap());
+    engine.set_position(&out_of_book);
+    let result = engine.by_ref().last().unwrap();
+    assert!(result.meta.get_nodes() > 0);
+
+    // the engine behind the book plays by the same rules, here crowning during the capture
+    engine.set_rules(Rules::russian());
+    let position = Position::parse_pdn_with_size("W:W9:B6,16", Size::Eight).unwrap();
+    engine.set_position(&position);
+    let result = engine.last().unwrap();
+    assert_eq!(result.mv.to(), 19);
+}
@@ -0,0 +1,216 @@
+use super::time::{TimeControl, TimeManager};
+use super::{Engine, EngineResult};
+use crate::algorithm::adaptive::AdaptiveScope;
+use crate::algorithm::bns::best_node_search;
+use crate::algorithm::judge::{Eval, Judge, PositionMemory, ZERO_EVAL};
+use crate::algorithm::meta::{Meta, Nodes};
+use crate::algorithm::pv::principal_variation;
+use crate::algorithm::scope::Depth;
+use crate::algorithm::search::SearchResult;
+use crate::algorithm::stop::Stop;
+use crate::algorithm::tt::{TranspositionTable, DEFAULT_MEGABYTES};
+use crate::board::generator::Generator;
+use crate::board::mv::Move;
+use crate::board::piece::Color::White;
+use crate::board::position::Position;
+use crate::board::rules::Rules;
+
+// every piece on the board is one more to give away, a king most of all
+const PIECES: [Eval; 5] = [ZERO_EVAL, -100, -250, 100, 250];
+// fewer moves is closer to having none
+const MOBILITY: Eval = 4;
+
+pub struct GiveawayJudge {
+    generator: Generator,
+    table: TranspositionTable,
+}
+
+impl GiveawayJudge {
+    pub fn create() -> GiveawayJudge {
+        GiveawayJudge::with_hash_size(DEFAULT_MEGABYTES)
+    }
+
+    pub fn with_hash_size(megabytes: usize) -> GiveawayJudge {
+        GiveawayJudge {
+            generator: Generator::with_rules(Rules::antidraughts()),
+            table: TranspositionTable::create(megabytes),
+        }
+    }
+
+    // the moves of the rules, always played to lose
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.generator = Generator::with_rules(Rules {
+            losing: true,
+            ..rules
+        });
+    }
+
+    pub fn reset(&mut self) {
+        self.table.clear()
+    }
+}
+
+impl Judge for GiveawayJudge {
+    fn recall(&self, position: &Position, _: Depth) -> PositionMemory {
+        self.table.recall(position.key())
+    }
+
+    fn remember(
+        &mut self,
+        position: &Position,
+        depth: Depth,
+        evaluation: Eval,
+        mv: Option<Move>,
+        low: bool,
+    ) {
+        self.table
+            .remember(position.key(), depth, evaluation, mv, low);
+    }
+
+    fn evaluate(&self, position: &Position) -> Eval {
+        let material = (0..position.size().fields())
+            .fold(0, |score, i| score + PIECES[position.piece_at(i) as usize]);
+        let mobility = self.generator.legal_moves(position).len() as Eval;
+        let material = if position.side_to_move() == White {
+            material
+        } else {
+            -material
+        };
+        material - MOBILITY * mobility
+    }
+
+    fn moves(&self, position: &Position, _depth: Depth) -> Vec<Move> {
+        self.generator.legal_moves(position)
+    }
+
+    fn display_name(&self) -> &str {
+        "Giveaway"
+    }
+
+    fn losing(&self) -> bool {
+        self.generator.rules().losing
+    }
+
+    fn quiet_move(&self, _: &Position, mv: &Move) -> bool {
+        mv.num_taken() == 0
+    }
+}
+
+pub struct Giveaway {
+    max_nodes: Nodes,
+    giveaway: GiveawayJudge,
+    previous: EngineResult,
+    position: Position,
+    generator: Generator,
+    time_control: TimeControl,
+    time: TimeManager,
+    stop: Stop,
+}
+
+impl Giveaway {
+    pub fn create(max_nodes: Nodes) -> Giveaway {
+        Giveaway::with_hash_size(max_nodes, DEFAULT_MEGABYTES)
+    }
+
+    pub fn with_hash_size(max_nodes: Nodes, megabytes: usize) -> Giveaway {
+        Giveaway {
+            max_nodes,
+            giveaway: GiveawayJudge::with_hash_size(megabytes),
+            previous: EngineResult::create(Move::null(), ZERO_EVAL, Meta::create()),
+            position: Position::initial(),
+            generator: Generator::with_rules(Rules::antidraughts()),
+            time_control: TimeControl::Unlimited,
+            time: TimeManager::start(&TimeControl::Unlimited),
+            stop: Stop::create(),
+        }
+    }
+}
+
+impl Iterator for Giveaway {
+    type Item = EngineResult;
+    fn next(&mut self) -> Option<EngineResult> {
+        let searched = self.previous.meta.get_nodes() > 0;
+        if self.previous.meta.get_nodes() >= self.max_nodes
+            || self.previous.meta.get_depth() > 63
+            || self.previous.is_proven()
+            || (searched && (self.stop.stopped() || !self.time.start_iteration()))
+        {
+            return None;
+        }
+
+        let mut meta = self.previous.meta.clone();
+        let search_result = if meta.get_depth() == 0 {
+            SearchResult::evaluation(self.previous.evaluation)
+        } else {
+            SearchResult::with_move(self.previous.mv, self.previous.evaluation)
+        };
+        let depth = if meta.get_nodes() == 0 {
+            0
+        } else {
+            meta.get_depth() + 1
+        };
+        meta.put_depth(depth);
+        let bns = best_node_search::<AdaptiveScope>(
+            &mut self.giveaway,
+            &self.position,
+            depth,
+            &search_result,
+            &self.stop,
+        );
+        if !bns.complete && searched {
+            return None;
+        }
+        meta.add_nodes(bns.meta.get_nodes());
+        self.previous = EngineResult::create(bns.mv, bns.lower, meta);
+        self.previous.pv =
+            principal_variation(&self.giveaway, &self.generator, &self.position, bns.mv);
+        Some(self.previous.clone())
+    }
+}
+
+impl Engine for Giveaway {
+    fn display_name(&self) -> &str {
+        self.giveaway.display_name()
+    }
+
+    fn set_position(&mut self, position: &Position) {
+        self.giveaway.reset();
+        self.position = *position;
+        self.previous = EngineResult::empty();
+        self.time = TimeManager::start(&self.time_control);
+        self.stop.restart(self.time.deadline());
+    }
+
+    fn set_time_control(&mut self, time_control: &TimeControl) {
+        self.time_control = *time_control;
+        self.time = TimeManager::start(time_control);
+        self.stop.restart(self.time.deadline());
+    }
+
+    fn stop_handle(&self) -> Option<Stop> {
+        Some(self.stop.clone())
+    }
+
+    fn set_rules(&mut self, rules: Rules) {
+        self.giveaway.set_rules(rules);
+        self.generator = Generator::with_rules(rules);
+    }
+}
+
+#[test]
+fn gives_pieces_away() {
+    use crate::algorithm::judge::win_eval;
+
+    // 28-23 is taken by 19x28 and leaves white without pieces
+    let position = Position::parse_pdn("W:W28:B19").unwrap();
+    let mut engine = Giveaway::with_hash_size(100_000, 1);
+    engine.set_position(&position);
+    let result = engine.last().unwrap();
+    assert_eq!(result.mv.to_string(), "28-23");
+    assert_eq!(result.evaluation, win_eval(2));
+
+    // fewer pieces than the opponent is the better side
+    let judge = GiveawayJudge::create();
+    assert!(judge.losing());
+    assert!(judge.evaluate(&Position::parse_pdn("W:W28:B22,23").unwrap()) > 0);
+}
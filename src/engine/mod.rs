@@ -1,19 +1,33 @@
+pub mod book;
+pub mod giveaway;
 pub mod randaap;
 pub mod sherlock;
 pub mod slonenok;
+pub mod time;
+pub mod weights;
 
 use std::iter::Iterator;
 
-use crate::algorithm::judge::{Eval, ZERO_EVAL};
+use crate::algorithm::judge::{plies_to_end, Eval, ZERO_EVAL};
 use crate::algorithm::meta::Meta;
+use crate::algorithm::multipv::Ranking;
+use crate::algorithm::stop::Stop;
+use crate::board::generator::Generator;
 use crate::board::mv::Move;
 use crate::board::position::Position;
+use crate::board::rules::Rules;
+use crate::tablebase::Tablebase;
+use time::TimeControl;
+
+const MAX_PV: usize = 32;
 
 #[derive(Clone)]
 pub struct EngineResult {
     pub mv: Move,
     pub evaluation: Eval,
     pub meta: Meta,
+    pub pv: Vec<Move>,
+    pub ranking: Ranking,
 }
 
 impl EngineResult {
@@ -22,14 +36,227 @@ impl EngineResult {
             mv,
             evaluation,
             meta,
+            pv: if mv == Move::null() { vec![] } else { vec![mv] },
+            ranking: vec![],
         }
     }
     pub fn empty() -> EngineResult {
         EngineResult::create(Move::null(), ZERO_EVAL, Meta::create())
     }
+
+    // "win in 4" or "loss in 3" in moves of the side to move, or the evaluation itself
+    pub fn score_string(&self) -> String {
+        match plies_to_end(self.evaluation) {
+            Some(plies) if self.evaluation > 0 => format!("win in {}", (plies + 1) / 2),
+            Some(plies) => format!("loss in {}", (plies + 1) / 2),
+            None => self.evaluation.to_string(),
+        }
+    }
+
+    // a forced result no further away than the search looked
+    pub fn is_proven(&self) -> bool {
+        plies_to_end(self.evaluation).is_some_and(|plies| plies <= self.meta.get_depth() as Eval)
+    }
+
+    pub fn pv_string(&self, generator: &Generator, position: &Position) -> String {
+        let mut position = *position;
+        let mut moves = vec![];
+        for mv in &self.pv {
+            moves.push(generator.to_short_string(&position, mv));
+            position = position.go(mv);
+        }
+        moves.join(" ")
+    }
+}
+
+// the best tablebase move at the root, which keeps the win or the draw without searching
+pub fn probe_root(
+    tablebase: &Tablebase,
+    generator: &Generator,
+    position: &Position,
+) -> Option<EngineResult> {
+    let ranking = tablebase.rank_moves(generator, position)?;
+    let &(mv, evaluation) = ranking.first()?;
+    let mut meta = Meta::create();
+    meta.add_nodes(ranking.len());
+    meta.put_depth(1);
+    let mut result = EngineResult::create(mv, evaluation, meta);
+    let mut next = position.go(&mv);
+    while result.pv.len() < MAX_PV {
+        match tablebase
+            .rank_moves(generator, &next)
+            .and_then(|ranking| ranking.first().copied())
+        {
+            Some((mv, _)) => {
+                result.pv.push(mv);
+                next = next.go(&mv);
+            }
+            None => break,
+        }
+    }
+    result.ranking = ranking;
+    Some(result)
 }
 
 pub trait Engine: Iterator<Item = EngineResult> {
     fn display_name(&self) -> &str;
     fn set_position(&mut self, position: &Position);
+    fn set_time_control(&mut self, _time_control: &TimeControl) {}
+    fn stop_handle(&self) -> Option<Stop> {
+        None
+    }
+    // plays on the board of the rules from the next position on
+    fn set_rules(&mut self, _rules: Rules) {}
+}
+
+impl<E: Engine + ?Sized> Engine for Box<E> {
+    fn display_name(&self) -> &str {
+        (**self).display_name()
+    }
+    fn set_position(&mut self, position: &Position) {
+        (**self).set_position(position)
+    }
+    fn set_time_control(&mut self, time_control: &TimeControl) {
+        (**self).set_time_control(time_control)
+    }
+    fn stop_handle(&self) -> Option<Stop> {
+        (**self).stop_handle()
+    }
+    fn set_rules(&mut self, rules: Rules) {
+        (**self).set_rules(rules)
+    }
+}
+
+#[test]
+fn stop_from_other_thread() {
+    use crate::board::generator::Generator;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    let position = Position::initial();
+    let mut engine = sherlock::Sherlock::with_hash_size(usize::MAX, 1);
+    engine.set_position(&position);
+    let stop = engine.stop_handle().unwrap();
+    let start = Instant::now();
+    let stopper = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(200));
+        stop.stop();
+    });
+    let result = engine.last().unwrap();
+    stopper.join().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert!(result.meta.get_depth() > 0);
+    assert!(Generator::create()
+        .legal_moves(&position)
+        .contains(&result.mv));
+}
+
+#[test]
+fn tablebase_probing() {
+    use crate::algorithm::alphabeta::makes_cut_parallel;
+    use crate::algorithm::depth::DepthScope;
+    use crate::algorithm::judge::{is_exact, Judge};
+    use crate::algorithm::scope::Scope;
+    use crate::tablebase::generate::{materials, solve};
+    use std::sync::Arc;
+
+    let mut tablebase = Tablebase::create();
+    for material in materials(2) {
+        if !tablebase.contains(&material) {
+            for table in solve(&tablebase, material).unwrap() {
+                tablebase.insert(table);
+            }
+        }
+    }
+    let tablebase = Arc::new(tablebase);
+    let generator = Generator::create();
+
+    // the root is in the tablebase: the move keeps the win
+    let position = Position::parse_pdn("W:WK50:B3").unwrap();
+    let mut engine = sherlock::Sherlock::with_hash_size(10_000, 1);
+    engine.set_tablebase(tablebase.clone());
+    engine.set_position(&position);
+    let result = engine.last().unwrap();
+    assert!(is_exact(result.evaluation) && result.evaluation > 0);
+    let after = tablebase.probe(&position.go(&result.mv)).unwrap();
+    assert!(matches!(after, crate::tablebase::Value::Loss(_)));
+    assert_eq!(result.ranking.len(), generator.legal_moves(&position).len());
+
+    // the search reaches the tablebase after a capture
+    let position = Position::parse_pdn("B:W28,K1:B22").unwrap();
+    let mut engine = slonenok::Slonenok::with_hash_size(10_000, 1);
+    engine.set_tablebase(tablebase.clone());
+    engine.set_position(&position);
+    let result = engine.last().unwrap();
+    assert!(is_exact(result.evaluation));
+
+    // the parallel search stops at the tablebase as well
+    let position = Position::parse_pdn("W:WK50:B3").unwrap();
+    let mut judge = sherlock::SherlockJudge::create(generator, Default::default());
+    judge.set_tablebase(tablebase);
+    let exact = judge.probe(&position).unwrap();
+    let result = makes_cut_parallel::<_, DepthScope>(
+        &mut vec![judge],
+        &mut Meta::create(),
+        &position,
+        &DepthScope::from_depth(1),
+        exact,
+        &Stop::create(),
+    );
+    assert_eq!(result.evaluation, exact);
+}
+
+#[test]
+fn win_distance() {
+    use crate::algorithm::judge::win_eval;
+
+    for (fen, plies, score) in [("W:W28:B22", 1, "win in 1"), ("W:WK46:B5", 3, "win in 2")] {
+        let mut engine = slonenok::Slonenok::with_hash_size(100_000, 1);
+        engine.set_position(&Position::parse_pdn(fen).unwrap());
+        let result = engine.last().unwrap();
+        assert_eq!(result.evaluation, win_eval(plies));
+        assert_eq!(result.score_string(), score);
+        assert!(result.is_proven());
+    }
+    let mut result = EngineResult::empty();
+    result.evaluation = crate::algorithm::judge::loss_eval(4);
+    assert_eq!(result.score_string(), "loss in 2");
+}
+
+#[test]
+fn small_board() {
+    use crate::board::rules::Rules;
+
+    fn play<E: Engine>(engine: &mut E, rules: Rules, start: Position) {
+        let generator = Generator::with_rules(rules);
+        let mut position = start;
+        for _ in 0..6 {
+            let legal = generator.legal_moves(&position);
+            if legal.is_empty() {
+                break;
+            }
+            engine.set_position(&position);
+            let result = engine.by_ref().last().unwrap();
+            assert!(legal.contains(&result.mv));
+            position = position.go(&result.mv);
+        }
+    }
+
+    let rules = Rules::russian();
+    let mut sherlock = sherlock::Sherlock::with_hash_size(1_000, 1);
+    sherlock.set_rules(rules);
+    play(&mut sherlock, rules, rules.initial());
+    let mut slonenok = slonenok::Slonenok::with_hash_size(1_000, 1);
+    slonenok.set_rules(rules);
+    play(&mut slonenok, rules, rules.initial());
+    let mut randaap = randaap::RandAap::create(1_000);
+    randaap.set_rules(rules);
+    play(&mut randaap, rules, rules.initial());
+
+    // kings on the board, where the killer landing matters
+    let rules = Rules::killer();
+    let start = Position::parse_pdn("W:WK47,K11,31,36:BK5,K25,18,19,20").unwrap();
+    let mut sherlock = sherlock::Sherlock::with_hash_size(1_000, 1);
+    sherlock.set_rules(rules);
+    play(&mut sherlock, rules, start);
 }
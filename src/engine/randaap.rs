@@ -1,17 +1,20 @@
 use std::iter;
 use std::sync::Arc;
 
+use super::time::{TimeControl, TimeManager};
 use super::{Engine, EngineResult};
 use crate::algorithm::depth::DepthScope;
-use crate::algorithm::judge::{Eval, Judge, MAX_EVAL, MIN_EVAL, ZERO_EVAL};
+use crate::algorithm::judge::{Eval, Judge, ZERO_EVAL};
 use crate::algorithm::meta::{Meta, Nodes};
 use crate::algorithm::mtdf::mtd_f_parallel;
 use crate::algorithm::scope::Depth;
+use crate::algorithm::stop::Stop;
 use crate::board::generator::Generator;
 use crate::board::mv::Move;
 use crate::board::piece::Color::White;
 use crate::board::piece::{Piece, BLACK_KING, BLACK_MAN, WHITE_KING, WHITE_MAN};
-use crate::board::position::{Field, Position};
+use crate::board::position::{Field, Position, Size};
+use crate::board::rules::Rules;
 
 #[derive(Clone)]
 struct RandAapJudge {
@@ -26,16 +29,21 @@ const FIELDS: [Eval; 50] = [
 
 impl RandAapJudge {
     pub fn create() -> RandAapJudge {
+        RandAapJudge::with_rules(Rules::international())
+    }
+
+    pub fn with_rules(rules: Rules) -> RandAapJudge {
         RandAapJudge {
-            generator: Arc::new(Generator::create()),
+            generator: Arc::new(Generator::with_rules(rules)),
         }
     }
 
-    fn evaluate(&self, piece: Piece, field: Field) -> Eval {
+    // the field bonuses are for the big board
+    fn evaluate(&self, size: Size, piece: Piece, field: Field) -> Eval {
         PIECES[piece as usize]
             + match piece {
-                WHITE_MAN => FIELDS[field],
-                BLACK_MAN => -FIELDS[49 - field],
+                WHITE_MAN if size == Size::Ten => FIELDS[field],
+                BLACK_MAN if size == Size::Ten => -FIELDS[49 - field],
                 _ => ZERO_EVAL,
             }
     }
@@ -43,7 +51,8 @@ impl RandAapJudge {
 
 impl Judge for RandAapJudge {
     fn evaluate(&self, position: &Position) -> Eval {
-        let eval = (0..50).fold((0, 0, 0), |(white, black, score), i| {
+        let size = position.size();
+        let eval = (0..size.fields()).fold((0, 0, 0), |(white, black, score), i| {
             let piece = position.piece_at(i);
             (
                 match piece {
@@ -54,7 +63,7 @@ impl Judge for RandAapJudge {
                     BLACK_MAN | BLACK_KING => black + 1,
                     _ => black,
                 },
-                score + self.evaluate(piece, i),
+                score + self.evaluate(size, piece, i),
             )
         });
         let score = if eval.0 <= 3 && eval.1 <= 3 {
@@ -87,15 +96,21 @@ pub struct RandAap {
     judges: Vec<RandAapJudge>,
     previous: EngineResult,
     position: Position,
+    time_control: TimeControl,
+    time: TimeManager,
+    stop: Stop,
 }
 
 impl RandAap {
     pub fn create(max_nodes: Nodes) -> RandAap {
         RandAap {
             max_nodes,
-            judges: iter::repeat(RandAapJudge::create()).take(8).collect(),
+            judges: iter::repeat_n(RandAapJudge::create(), 8).collect(),
             previous: EngineResult::create(Move::null(), ZERO_EVAL, Meta::create()),
             position: Position::initial(),
+            time_control: TimeControl::Unlimited,
+            time: TimeManager::start(&TimeControl::Unlimited),
+            stop: Stop::create(),
         }
     }
 }
@@ -103,10 +118,11 @@ impl RandAap {
 impl Iterator for RandAap {
     type Item = EngineResult;
     fn next(&mut self) -> Option<EngineResult> {
+        let searched = self.previous.meta.get_nodes() > 0;
         if self.previous.meta.get_nodes() >= self.max_nodes
             || self.previous.meta.get_depth() > 63
-            || self.previous.evaluation == MIN_EVAL
-            || self.previous.evaluation == MAX_EVAL
+            || self.previous.is_proven()
+            || (searched && (self.stop.stopped() || !self.time.start_iteration()))
         {
             return None;
         }
@@ -123,7 +139,11 @@ impl Iterator for RandAap {
             &self.position,
             depth,
             self.previous.evaluation,
+            &self.stop,
         );
+        if !mtd.complete && searched {
+            return None;
+        }
         meta.add_nodes(mtd.meta.get_nodes());
         self.previous = EngineResult::create(mtd.mv, mtd.evaluation, meta);
         Some(self.previous.clone())
@@ -134,9 +154,25 @@ impl Engine for RandAap {
     fn set_position(&mut self, position: &Position) {
         self.position = *position;
         self.previous = EngineResult::empty();
+        self.time = TimeManager::start(&self.time_control);
+        self.stop.restart(self.time.deadline());
+    }
+
+    fn set_time_control(&mut self, time_control: &TimeControl) {
+        self.time_control = *time_control;
+        self.time = TimeManager::start(time_control);
+        self.stop.restart(self.time.deadline());
+    }
+
+    fn stop_handle(&self) -> Option<Stop> {
+        Some(self.stop.clone())
     }
 
     fn display_name(&self) -> &str {
         self.judges[0].display_name()
     }
+
+    fn set_rules(&mut self, rules: Rules) {
+        self.judges = iter::repeat_n(RandAapJudge::with_rules(rules), 8).collect();
+    }
 }
@@ -1,22 +1,29 @@
-use std::cmp::Ordering;
-use std::collections::HashMap;
 use std::iter;
-use std::sync::{Arc, RwLock};
+use std::path::Path;
+use std::sync::Arc;
 
-use super::{Engine, EngineResult};
+use super::time::{TimeControl, TimeManager};
+use super::weights::{self, Weights};
+use super::{probe_root, Engine, EngineResult};
 use crate::algorithm::bns::best_node_search_parallel;
-use crate::algorithm::judge::{Eval, Judge, PositionMemory, MAX_EVAL, MIN_EVAL, ZERO_EVAL};
+use crate::algorithm::judge::{Eval, Judge, PositionMemory, ZERO_EVAL};
 use crate::algorithm::logarithmic::LogarithmicScope;
 use crate::algorithm::meta::{Meta, Nodes};
+use crate::algorithm::multipv::multi_pv_parallel;
+use crate::algorithm::pv::principal_variation;
 use crate::algorithm::scope::Depth;
 use crate::algorithm::search::SearchResult;
+use crate::algorithm::stop::Stop;
+use crate::algorithm::tt::{TranspositionTable, DEFAULT_MEGABYTES};
 use crate::board::generator::Generator;
 use crate::board::mv::Move;
 use crate::board::piece::Color::White;
 use crate::board::piece::{BLACK_KING, BLACK_MAN, WHITE_KING, WHITE_MAN};
-use crate::board::position::{Field, Position};
+use crate::board::position::Position;
+use crate::board::rules::Rules;
 use crate::board::stars::Stars;
 use crate::board::stats::PositionStats;
+use crate::tablebase::Tablebase;
 
 const PIECES: [Eval; 5] = [ZERO_EVAL, 500, 1475, -500, -1475];
 const BALANCE: [Eval; 10] = [-54, -52, -48, -42, -10, 10, 42, 48, 52, 54];
@@ -36,132 +43,194 @@ const MM: usize = 2;
 const BL: usize = 3;
 const BR: usize = 4;
 
-type SmallField = u8;
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SherlockWeights {
+    pub man: Eval,
+    pub king: Eval,
+    pub balance: [Eval; 10],
+    pub center: [Eval; 10],
+    pub locked: Eval,
+    pub semi_locked: Eval,
+    pub hanging: Eval,
+    pub isolated: Eval,
+    pub semi_hanging: Eval,
+    pub birdy: Eval,
+    pub tail: Eval,
+    pub extra: Eval,
+}
 
-struct HashEval {
-    lower: Eval,
-    upper: Eval,
-    depth: Depth,
-    from: SmallField,
-    to: SmallField,
-    generation: u8,
+impl Default for SherlockWeights {
+    fn default() -> SherlockWeights {
+        SherlockWeights {
+            man: PIECES[WHITE_MAN as usize],
+            king: PIECES[WHITE_KING as usize],
+            balance: BALANCE,
+            center: CENTER,
+            locked: LOCKED,
+            semi_locked: SEMI_LOCKED,
+            hanging: HANGING,
+            isolated: ISOLATED,
+            semi_hanging: SEMI_HANGING,
+            birdy: BIRDY,
+            tail: TAIL,
+            extra: EXTRA,
+        }
+    }
 }
 
-impl HashEval {
-    fn as_memory(&self) -> PositionMemory {
-        PositionMemory::create(
-            self.depth,
-            self.lower,
-            self.upper,
-            self.from as Field,
-            self.to as Field,
-        )
+impl Weights for SherlockWeights {
+    fn layout() -> &'static [(&'static str, usize)] {
+        &[
+            ("man", 1),
+            ("king", 1),
+            ("balance", 10),
+            ("center", 10),
+            ("locked", 1),
+            ("semi_locked", 1),
+            ("hanging", 1),
+            ("isolated", 1),
+            ("semi_hanging", 1),
+            ("birdy", 1),
+            ("tail", 1),
+            ("extra", 1),
+        ]
+    }
+
+    fn values(&self) -> Vec<Eval> {
+        let mut values = vec![self.man, self.king];
+        values.extend_from_slice(&self.balance);
+        values.extend_from_slice(&self.center);
+        values.extend_from_slice(&[
+            self.locked,
+            self.semi_locked,
+            self.hanging,
+            self.isolated,
+            self.semi_hanging,
+            self.birdy,
+            self.tail,
+            self.extra,
+        ]);
+        values
     }
 
-    fn update(&mut self, other: &HashEval) {
-        match self.depth.cmp(&other.depth) {
-            Ordering::Greater => (),
-            Ordering::Equal => {
-                self.lower = self.lower.max(other.lower);
-                self.upper = self.upper.min(other.upper);
-                self.generation = other.generation;
-                if self.from == 0 && self.to == 0 {
-                    self.from = other.from;
-                    self.to = other.to;
+    fn set_values(&mut self, values: &[Eval]) {
+        self.man = values[0];
+        self.king = values[1];
+        self.balance.copy_from_slice(&values[2..12]);
+        self.center.copy_from_slice(&values[12..22]);
+        self.locked = values[22];
+        self.semi_locked = values[23];
+        self.hanging = values[24];
+        self.isolated = values[25];
+        self.semi_hanging = values[26];
+        self.birdy = values[27];
+        self.tail = values[28];
+        self.extra = values[29];
+    }
+}
+
+// the score of every star, by the men on its five fields
+fn star_evals(weights: &SherlockWeights) -> [Eval; 243] {
+    let mut evals = [0; 243];
+    for tl in 0..3 {
+        let star = tl * THREES[TL];
+        for tr in 0..3 {
+            let star = star + tr * THREES[TR];
+            for mm in 1..3 {
+                // 0 is not interesting
+                let star = star + mm * THREES[MM];
+                let (sign, op) = if mm == 1 { (1, 2) } else { (-1, 1) };
+                for bl in 0..3 {
+                    let star = star + bl * THREES[BL];
+                    for br in 0..3 {
+                        let star = star + br * THREES[BR];
+                        let supporters = if mm == 1 {
+                            (if mm == bl { 1 } else { 0 }) + (if mm == br { 1 } else { 0 })
+                        } else {
+                            (if mm == tl { 1 } else { 0 }) + (if mm == tr { 1 } else { 0 })
+                        };
+                        let blockers = if mm == 1 {
+                            (if mm == tl { 1 } else { 0 }) + (if mm == tr { 1 } else { 0 })
+                        } else {
+                            (if mm == bl { 1 } else { 0 }) + (if mm == br { 1 } else { 0 })
+                        };
+                        let lockers = if mm == 1 {
+                            (if op == tl { 1 } else { 0 }) + (if op == tr { 1 } else { 0 })
+                        } else {
+                            (if op == bl { 1 } else { 0 }) + (if op == br { 1 } else { 0 })
+                        };
+                        evals[star] = sign
+                            * match (supporters, blockers, lockers) {
+                                (2, _, 2) => weights.locked,
+                                (_, _, 1) => weights.semi_locked,
+                                (0, 2, 0) => weights.hanging,
+                                (0, 0, _) => weights.isolated,
+                                (1, 2, 0) => weights.semi_hanging,
+                                (2, 0, 0) => weights.birdy,
+                                (2, b, 0) => weights.tail + weights.extra * b,
+                                (s, b, 0) => weights.extra * (s + b),
+                                _ => 0,
+                            };
+                    }
                 }
             }
-            Ordering::Less => {
-                self.depth = other.depth;
-                self.lower = other.lower;
-                self.upper = other.upper;
-                self.from = other.from;
-                self.to = other.to;
-                self.generation = other.generation;
-            }
         }
     }
+    evals
 }
 
+#[derive(Clone)]
 pub struct SherlockJudge {
     generator: Generator,
     stars: Stars,
+    weights: SherlockWeights,
     evals: [Eval; 243],
-    private_hash: HashMap<Position, HashEval>,
-    shared_hash: Arc<RwLock<HashMap<Position, HashEval>>>,
-    generation: u8,
-}
-
-impl Clone for SherlockJudge {
-    fn clone(&self) -> Self {
-        Self {
-            generator: self.generator.clone(),
-            stars: self.stars.clone(),
-            evals: self.evals,
-            private_hash: HashMap::new(),
-            shared_hash: Arc::clone(&self.shared_hash),
-            generation: self.generation,
-        }
-    }
+    table: Arc<TranspositionTable>,
+    tablebase: Option<Arc<Tablebase>>,
 }
 
 impl SherlockJudge {
-    pub fn create(generator: Generator) -> SherlockJudge {
-        let mut evals = [0; 243];
-        for tl in 0..3 {
-            let star = tl * THREES[TL];
-            for tr in 0..3 {
-                let star = star + tr * THREES[TR];
-                for mm in 1..3 {
-                    // 0 is not interesting
-                    let star = star + mm * THREES[MM];
-                    let (sign, op) = if mm == 1 { (1, 2) } else { (-1, 1) };
-                    for bl in 0..3 {
-                        let star = star + bl * THREES[BL];
-                        for br in 0..3 {
-                            let star = star + br * THREES[BR];
-                            let supporters = if mm == 1 {
-                                (if mm == bl { 1 } else { 0 }) + (if mm == br { 1 } else { 0 })
-                            } else {
-                                (if mm == tl { 1 } else { 0 }) + (if mm == tr { 1 } else { 0 })
-                            };
-                            let blockers = if mm == 1 {
-                                (if mm == tl { 1 } else { 0 }) + (if mm == tr { 1 } else { 0 })
-                            } else {
-                                (if mm == bl { 1 } else { 0 }) + (if mm == br { 1 } else { 0 })
-                            };
-                            let lockers = if mm == 1 {
-                                (if op == tl { 1 } else { 0 }) + (if op == tr { 1 } else { 0 })
-                            } else {
-                                (if op == bl { 1 } else { 0 }) + (if op == br { 1 } else { 0 })
-                            };
-                            evals[star] = sign
-                                * match (supporters, blockers, lockers) {
-                                    (2, _, 2) => LOCKED,
-                                    (_, _, 1) => SEMI_LOCKED,
-                                    (0, 2, 0) => HANGING,
-                                    (0, 0, _) => ISOLATED,
-                                    (1, 2, 0) => SEMI_HANGING,
-                                    (2, 0, 0) => BIRDY,
-                                    (2, b, 0) => TAIL + EXTRA * b,
-                                    (s, b, 0) => EXTRA * (s + b),
-                                    _ => 0,
-                                };
-                        }
-                    }
-                }
-            }
-        }
+    pub fn create(generator: Generator, weights: SherlockWeights) -> SherlockJudge {
+        SherlockJudge::with_table(
+            generator,
+            Arc::new(TranspositionTable::create(DEFAULT_MEGABYTES)),
+            weights,
+        )
+    }
 
+    pub fn with_table(
+        generator: Generator,
+        table: Arc<TranspositionTable>,
+        weights: SherlockWeights,
+    ) -> SherlockJudge {
         SherlockJudge {
+            stars: Stars::create(generator.rules().size),
             generator,
-            evals,
-            stars: Stars::create(),
-            private_hash: HashMap::new(),
-            shared_hash: Arc::new(RwLock::new(HashMap::new())),
-            generation: 0,
+            weights,
+            evals: star_evals(&weights),
+            table,
+            tablebase: None,
         }
     }
 
+    pub fn weights(&self) -> &SherlockWeights {
+        &self.weights
+    }
+
+    pub fn set_weights(&mut self, weights: SherlockWeights) {
+        self.weights = weights;
+        self.evals = star_evals(&weights);
+    }
+
+    pub fn set_tablebase(&mut self, tablebase: Arc<Tablebase>) {
+        self.tablebase = Some(tablebase);
+    }
+
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.generator = Generator::with_rules(rules);
+        self.stars = Stars::create(rules.size);
+    }
+
     // draw heuristic
     fn drawish(&self, stats: &PositionStats) -> bool {
         let whites = stats.piece_count[WHITE_MAN as usize] + stats.piece_count[WHITE_KING as usize];
@@ -173,19 +242,14 @@ impl SherlockJudge {
     }
 
     pub fn reset(&mut self) {
-        let generation = self.generation;
-        self.shared_hash
-            .write()
-            .unwrap()
-            .retain(|_, value| value.generation == generation);
-        self.generation += 1;
+        self.table.new_search();
     }
 
     fn balance(&self, hoffset: &[Eval]) -> Eval {
         -hoffset
             .iter()
             .enumerate()
-            .map(|(i, &offset)| BALANCE[i] * offset)
+            .map(|(i, &offset)| self.weights.balance[i] * offset)
             .sum::<Eval>()
             .abs()
     }
@@ -194,7 +258,7 @@ impl SherlockJudge {
         hoffset
             .iter()
             .enumerate()
-            .map(|(i, &offset)| CENTER[i] * offset)
+            .map(|(i, &offset)| self.weights.center[i] * offset)
             .sum()
     }
 }
@@ -206,14 +270,7 @@ impl Judge for SherlockJudge {
         if depth < HASH_DEPTH {
             return PositionMemory::empty();
         }
-        match self.private_hash.get(position) {
-            Some(found) => found.as_memory(),
-            None if depth < HASH_DEPTH => PositionMemory::empty(),
-            _ => match self.shared_hash.read().unwrap().get(position) {
-                Some(found) => found.as_memory(),
-                _ => PositionMemory::empty(),
-            },
-        }
+        self.table.recall(position.key())
     }
     fn remember(
         &mut self,
@@ -223,36 +280,21 @@ impl Judge for SherlockJudge {
         mv: Option<Move>,
         low: bool,
     ) {
-        // if depth < HASH_DEPTH {
-        //     return;
-        // }
-        let mv = mv.unwrap_or_else(Move::null);
-        let hash_eval = HashEval {
-            depth,
-            lower: if low { MIN_EVAL } else { evaluation },
-            upper: if low { evaluation } else { MAX_EVAL },
-            from: mv.from() as SmallField,
-            to: mv.to() as SmallField,
-            generation: self.generation,
-        };
-
-        self.private_hash
-            .entry(*position)
-            .and_modify(|found| found.update(&hash_eval))
-            .or_insert(hash_eval);
-    }
-    fn consolidate(&mut self) {
-        let mut hash = self.shared_hash.write().unwrap();
-        for (position, hash_eval) in self.private_hash.drain() {
-            hash.entry(position)
-                .and_modify(|found| found.update(&hash_eval))
-                .or_insert(hash_eval);
-        }
+        self.table
+            .remember(position.key(), depth, evaluation, mv, low);
     }
     fn evaluate(&self, position: &Position) -> Eval {
         let stats = PositionStats::for_position(position);
 
-        let beans = (0..5).fold(0, |b, i| b + PIECES[i] * stats.piece_count[i]);
+        let weights = &self.weights;
+        let pieces = [
+            ZERO_EVAL,
+            weights.man,
+            weights.king,
+            -weights.man,
+            -weights.king,
+        ];
+        let beans = (0..5).fold(0, |b, i| b + pieces[i] * stats.piece_count[i]);
 
         let men = stats.piece_count[WHITE_MAN as usize] + stats.piece_count[BLACK_MAN as usize];
 
@@ -270,7 +312,7 @@ impl Judge for SherlockJudge {
             0
         } else {
             let mut stars = [0; 32];
-            for field in 0..50 {
+            for field in 0..position.size().fields() {
                 let value = match position.piece_at(field) {
                     WHITE_MAN => 1,
                     BLACK_MAN => 2,
@@ -307,6 +349,16 @@ impl Judge for SherlockJudge {
         }
     }
 
+    fn probe(&self, position: &Position) -> Option<Eval> {
+        if !Tablebase::solves(self.generator.rules()) {
+            return None;
+        }
+        self.tablebase
+            .as_ref()?
+            .probe(position)
+            .map(|value| value.eval())
+    }
+
     fn moves(&self, position: &Position, depth: Depth) -> Vec<Move> {
         let mut moves = self.generator.legal_moves(position);
         let memory = self.recall(position, depth);
@@ -324,11 +376,13 @@ impl Judge for SherlockJudge {
     }
 
     fn quiet_move(&self, position: &Position, mv: &Move) -> bool {
+        let size = position.size();
+        let near = 2 * size.per_row();
         mv.num_taken() == 0
             && if position.side_to_move() == White {
-                mv.to() >= 10 || position.piece_at(mv.from()) != WHITE_MAN
+                mv.to() >= near || position.piece_at(mv.from()) != WHITE_MAN
             } else {
-                mv.to() <= 39 || position.piece_at(mv.from()) != BLACK_MAN
+                mv.to() < size.fields() - near || position.piece_at(mv.from()) != BLACK_MAN
             }
     }
 
@@ -338,32 +392,124 @@ impl Judge for SherlockJudge {
 }
 
 pub struct Sherlock {
+    name: String,
     max_nodes: Nodes,
+    multi_pv: usize,
     sherlocks: Vec<SherlockJudge>,
     previous: EngineResult,
     position: Position,
+    generator: Generator,
+    time_control: TimeControl,
+    time: TimeManager,
+    stop: Stop,
+    tablebase: Option<Arc<Tablebase>>,
 }
 
 impl Sherlock {
     pub fn create(max_nodes: Nodes) -> Sherlock {
+        Sherlock::with_hash_size(max_nodes, DEFAULT_MEGABYTES)
+    }
+
+    pub fn with_hash_size(max_nodes: Nodes, megabytes: usize) -> Sherlock {
+        let table = Arc::new(TranspositionTable::create(megabytes));
         Sherlock {
+            name: String::from("Sherlock"),
             max_nodes,
-            sherlocks: iter::repeat(SherlockJudge::create(Generator::create()))
-                .take(8)
-                .collect(),
+            multi_pv: 1,
+            sherlocks: iter::repeat_n(
+                SherlockJudge::with_table(Generator::create(), table, SherlockWeights::default()),
+                8,
+            )
+            .collect(),
             previous: EngineResult::create(Move::null(), ZERO_EVAL, Meta::create()),
             position: Position::initial(),
+            generator: Generator::create(),
+            time_control: TimeControl::Unlimited,
+            time: TimeManager::start(&TimeControl::Unlimited),
+            stop: Stop::create(),
+            tablebase: None,
+        }
+    }
+
+    pub fn set_tablebase(&mut self, tablebase: Arc<Tablebase>) {
+        for sherlock in &mut self.sherlocks {
+            sherlock.set_tablebase(tablebase.clone());
+        }
+        self.tablebase = Some(tablebase);
+    }
+
+    pub fn load_tablebase(&mut self, dir: &Path) -> Result<(), String> {
+        self.set_tablebase(Arc::new(Tablebase::load(dir)?));
+        Ok(())
+    }
+
+    pub fn set_weights(&mut self, weights: SherlockWeights) {
+        for sherlock in &mut self.sherlocks {
+            sherlock.set_weights(weights);
         }
     }
+
+    pub fn load_weights(&mut self, path: &Path) -> Result<(), String> {
+        self.set_weights(weights::load(path)?);
+        Ok(())
+    }
+
+    // tells apart engines playing with different weights
+    pub fn set_display_name(&mut self, name: &str) {
+        self.name = name.to_owned();
+    }
+
+    // ranks the best count moves in every iteration instead of searching for the best one
+    pub fn set_multi_pv(&mut self, count: usize) {
+        self.multi_pv = count.max(1);
+    }
+
+    fn next_ranking(
+        &mut self,
+        mut meta: Meta,
+        depth: Depth,
+        searched: bool,
+    ) -> Option<EngineResult> {
+        let multi = multi_pv_parallel::<SherlockJudge, LogarithmicScope>(
+            &mut self.sherlocks,
+            &self.position,
+            depth,
+            self.multi_pv,
+            &self.previous.ranking,
+            &self.stop,
+        );
+        if !multi.complete && searched {
+            return None;
+        }
+        let &(mv, evaluation) = multi.ranking.first()?;
+        meta.add_nodes(multi.meta.get_nodes());
+        self.previous = EngineResult::create(mv, evaluation, meta);
+        self.previous.pv =
+            principal_variation(&self.sherlocks[0], &self.generator, &self.position, mv);
+        self.previous.ranking = multi.ranking;
+        Some(self.previous.clone())
+    }
 }
 
 impl Iterator for Sherlock {
     type Item = EngineResult;
     fn next(&mut self) -> Option<EngineResult> {
+        let searched = self.previous.meta.get_nodes() > 0;
+        if let Some(result) = self
+            .tablebase
+            .as_ref()
+            .and_then(|tablebase| probe_root(tablebase, &self.generator, &self.position))
+        {
+            if searched {
+                return None;
+            }
+            self.previous = result;
+            return Some(self.previous.clone());
+        }
         if self.previous.meta.get_nodes() >= self.max_nodes
             || self.previous.meta.get_depth() > 27
-            || self.previous.evaluation == MIN_EVAL
-            || self.previous.evaluation == MAX_EVAL
+            || self.previous.is_proven()
+            || (searched && (self.stop.stopped() || !self.time.start_iteration()))
         {
             return None;
         }
@@ -376,30 +522,53 @@ impl Iterator for Sherlock {
         };
         let depth = meta.get_depth() + 1;
         meta.put_depth(depth);
+        if self.multi_pv > 1 {
+            return self.next_ranking(meta, depth, searched);
+        }
         let bns = best_node_search_parallel::<SherlockJudge, LogarithmicScope>(
             &mut self.sherlocks,
             &self.position,
             depth,
             &search_result,
+            &self.stop,
         );
+        if !bns.complete && searched {
+            return None;
+        }
         meta.add_nodes(bns.meta.get_nodes());
         self.previous = EngineResult::create(bns.mv, bns.lower, meta);
+        self.previous.pv =
+            principal_variation(&self.sherlocks[0], &self.generator, &self.position, bns.mv);
         Some(self.previous.clone())
     }
 }
 
 impl Engine for Sherlock {
     fn display_name(&self) -> &str {
-        "Sherlock"
+        &self.name
     }
     fn set_position(&mut self, position: &Position) {
         self.sherlocks[0].reset();
-        self.sherlocks[0].consolidate();
-        let generation = self.sherlocks[0].generation;
-        for sherlock in &mut self.sherlocks {
-            sherlock.generation = generation;
-        }
         self.position = *position;
         self.previous = EngineResult::empty();
+        self.time = TimeManager::start(&self.time_control);
+        self.stop.restart(self.time.deadline());
+    }
+
+    fn set_time_control(&mut self, time_control: &TimeControl) {
+        self.time_control = *time_control;
+        self.time = TimeManager::start(time_control);
+        self.stop.restart(self.time.deadline());
+    }
+
+    fn stop_handle(&self) -> Option<Stop> {
+        Some(self.stop.clone())
+    }
+
+    fn set_rules(&mut self, rules: Rules) {
+        for sherlock in &mut self.sherlocks {
+            sherlock.set_rules(rules);
+        }
+        self.generator = Generator::with_rules(rules);
     }
 }
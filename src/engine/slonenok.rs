@@ -1,69 +1,161 @@
 use std::cmp::Ordering::{Greater, Less};
-use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
 
-use super::{Engine, EngineResult};
+use super::time::{TimeControl, TimeManager};
+use super::weights::{self, Weights};
+use super::{probe_root, Engine, EngineResult};
 use crate::algorithm::adaptive::AdaptiveScope;
 use crate::algorithm::bns::best_node_search;
-use crate::algorithm::judge::{Eval, Judge, PositionMemory, MAX_EVAL, MIN_EVAL, ZERO_EVAL};
+use crate::algorithm::judge::{Eval, Judge, PositionMemory, ZERO_EVAL};
 use crate::algorithm::meta::{Meta, Nodes};
+use crate::algorithm::multipv::multi_pv;
+use crate::algorithm::pv::principal_variation;
 use crate::algorithm::scope::Depth;
 use crate::algorithm::search::SearchResult;
+use crate::algorithm::stop::Stop;
+use crate::algorithm::tt::{TranspositionTable, DEFAULT_MEGABYTES};
 use crate::board::generator::Generator;
 use crate::board::mv::Move;
 use crate::board::piece::Color::White;
 use crate::board::piece::{BLACK_KING, BLACK_MAN, EMPTY, WHITE_KING, WHITE_MAN};
-use crate::board::position::{Field, Position};
+use crate::board::position::{Position, Size};
+use crate::board::rules::Rules;
 use crate::board::stats::PositionStats;
+use crate::tablebase::Tablebase;
 
 const PIECES: [Eval; 5] = [ZERO_EVAL, 500, 1500, -500, -1500];
 const HOFFSET: [Eval; 10] = [0, 1, 3, 7, 15, 15, 7, 3, 1, 0];
 const VOFFSET_FULL: [Eval; 10] = [8, 7, 5, 1, -7, -23, -7, 1, 5, 7];
 const VOFFSET_EMPTY: [Eval; 10] = [-15, -23, -7, 1, 5, 7, 8, 9, 10, 11];
 const BALANCE: [Eval; 10] = [-6, -5, -4, -3, -2, 2, 3, 4, 5, 6];
+// the formations that evaluate_structure looks for
+const FORMATION: Eval = 100;
+const HALF_FORMATION: Eval = 20;
+const CORNER: Eval = 15;
+const CLASH: Eval = 100;
 const KILLERS: usize = 20;
 
-type SmallField = u8;
-struct HashEval {
-    lower: Eval,
-    upper: Eval,
-    depth: Depth,
-    from: SmallField,
-    to: SmallField,
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SlonenokWeights {
+    pub man: Eval,
+    pub king: Eval,
+    pub hoffset: [Eval; 10],
+    pub voffset_full: [Eval; 10],
+    pub voffset_empty: [Eval; 10],
+    pub balance: [Eval; 10],
+    pub formation: Eval,
+    pub half_formation: Eval,
+    pub corner: Eval,
+    pub clash: Eval,
 }
 
-impl HashEval {
-    fn as_memory(&self) -> PositionMemory {
-        PositionMemory::create(
-            self.depth,
-            self.lower,
-            self.upper,
-            self.from as Field,
-            self.to as Field,
-        )
+impl Default for SlonenokWeights {
+    fn default() -> SlonenokWeights {
+        SlonenokWeights {
+            man: PIECES[WHITE_MAN as usize],
+            king: PIECES[WHITE_KING as usize],
+            hoffset: HOFFSET,
+            voffset_full: VOFFSET_FULL,
+            voffset_empty: VOFFSET_EMPTY,
+            balance: BALANCE,
+            formation: FORMATION,
+            half_formation: HALF_FORMATION,
+            corner: CORNER,
+            clash: CLASH,
+        }
+    }
+}
+
+impl Weights for SlonenokWeights {
+    fn layout() -> &'static [(&'static str, usize)] {
+        &[
+            ("man", 1),
+            ("king", 1),
+            ("hoffset", 10),
+            ("voffset_full", 10),
+            ("voffset_empty", 10),
+            ("balance", 10),
+            ("formation", 1),
+            ("half_formation", 1),
+            ("corner", 1),
+            ("clash", 1),
+        ]
+    }
+
+    fn values(&self) -> Vec<Eval> {
+        let mut values = vec![self.man, self.king];
+        values.extend_from_slice(&self.hoffset);
+        values.extend_from_slice(&self.voffset_full);
+        values.extend_from_slice(&self.voffset_empty);
+        values.extend_from_slice(&self.balance);
+        values.extend_from_slice(&[self.formation, self.half_formation, self.corner, self.clash]);
+        values
+    }
+
+    fn set_values(&mut self, values: &[Eval]) {
+        self.man = values[0];
+        self.king = values[1];
+        self.hoffset.copy_from_slice(&values[2..12]);
+        self.voffset_full.copy_from_slice(&values[12..22]);
+        self.voffset_empty.copy_from_slice(&values[22..32]);
+        self.balance.copy_from_slice(&values[32..42]);
+        self.formation = values[42];
+        self.half_formation = values[43];
+        self.corner = values[44];
+        self.clash = values[45];
     }
 }
 
 pub struct SlonenokJudge {
     generator: Generator,
-    hash: HashMap<Position, HashEval>,
+    weights: SlonenokWeights,
+    table: TranspositionTable,
     white_killer_moves: [Move; KILLERS],
     white_killer_cursor: usize,
     black_killer_moves: [Move; KILLERS],
     black_killer_cursor: usize,
+    tablebase: Option<Arc<Tablebase>>,
 }
 
 impl SlonenokJudge {
-    pub fn create(generator: Generator) -> SlonenokJudge {
+    pub fn create(generator: Generator, weights: SlonenokWeights) -> SlonenokJudge {
+        SlonenokJudge::with_hash_size(generator, DEFAULT_MEGABYTES, weights)
+    }
+
+    pub fn with_hash_size(
+        generator: Generator,
+        megabytes: usize,
+        weights: SlonenokWeights,
+    ) -> SlonenokJudge {
         SlonenokJudge {
             generator,
-            hash: HashMap::new(),
+            weights,
+            table: TranspositionTable::create(megabytes),
             white_killer_moves: [Move::null(); KILLERS],
             white_killer_cursor: 0,
             black_killer_moves: [Move::null(); KILLERS],
             black_killer_cursor: 0,
+            tablebase: None,
         }
     }
 
+    pub fn set_tablebase(&mut self, tablebase: Arc<Tablebase>) {
+        self.tablebase = Some(tablebase);
+    }
+
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.generator = Generator::with_rules(rules);
+    }
+
+    pub fn weights(&self) -> &SlonenokWeights {
+        &self.weights
+    }
+
+    pub fn set_weights(&mut self, weights: SlonenokWeights) {
+        self.weights = weights;
+    }
+
     // draw heuristic
     fn drawish(&self, stats: &PositionStats) -> bool {
         let whites = stats.piece_count[WHITE_MAN as usize] + stats.piece_count[WHITE_KING as usize];
@@ -75,10 +167,15 @@ impl SlonenokJudge {
     }
 
     pub fn reset(&mut self) {
-        self.hash.clear()
+        self.table.clear()
     }
 
     fn evaluate_structure(&self, position: &Position) -> Eval {
+        // the patterns are drawn on the big board
+        if position.size() != Size::Ten {
+            return 0;
+        }
+        let weights = &self.weights;
         let mut structure = 0;
 
         // hanging piece penalty
@@ -89,12 +186,12 @@ impl SlonenokJudge {
                 && position.piece_at(start - 10) == EMPTY
             {
                 if position.piece_at(start - 9) == EMPTY {
-                    structure += 100;
+                    structure += weights.formation;
                 } else if start == 13 {
                     structure += if position.piece_at(24) == WHITE_MAN {
-                        100
+                        weights.formation
                     } else {
-                        20
+                        weights.half_formation
                     };
                 }
             }
@@ -106,7 +203,7 @@ impl SlonenokJudge {
                 && position.piece_at(start - 10) == EMPTY
                 && position.piece_at(start - 9) == EMPTY
             {
-                structure += 100;
+                structure += weights.formation;
             }
         }
         for start in 30..34 {
@@ -116,7 +213,7 @@ impl SlonenokJudge {
                 && position.piece_at(start + 10) == EMPTY
                 && position.piece_at(start + 11) == EMPTY
             {
-                structure -= 100;
+                structure -= weights.formation;
             }
         }
         for start in 35..39 {
@@ -126,12 +223,12 @@ impl SlonenokJudge {
                 && position.piece_at(start + 10) == EMPTY
             {
                 if position.piece_at(start + 11) == EMPTY {
-                    structure -= 100;
+                    structure -= weights.formation;
                 } else if start == 35 {
                     structure -= if position.piece_at(25) == BLACK_MAN {
-                        100
+                        weights.formation
                     } else {
-                        20
+                        weights.half_formation
                     };
                 }
             }
@@ -139,10 +236,10 @@ impl SlonenokJudge {
 
         // corner penalty
         if position.piece_at(4) == BLACK_MAN {
-            structure += 15;
+            structure += weights.corner;
         }
         if position.piece_at(45) == WHITE_MAN {
-            structure -= 15;
+            structure -= weights.corner;
         }
 
         // fork locks
@@ -154,8 +251,8 @@ impl SlonenokJudge {
                     && position.piece_at(start + 11) == WHITE_MAN
                 {
                     match position.piece_at(start + 5) {
-                        WHITE_MAN => structure -= 100,
-                        BLACK_MAN => structure += 100,
+                        WHITE_MAN => structure -= weights.clash,
+                        BLACK_MAN => structure += weights.clash,
                         _ => (),
                     };
                 }
@@ -167,8 +264,8 @@ impl SlonenokJudge {
                     && position.piece_at(start + 11) == WHITE_MAN
                 {
                     match position.piece_at(start + 6) {
-                        WHITE_MAN => structure -= 100,
-                        BLACK_MAN => structure += 100,
+                        WHITE_MAN => structure -= weights.clash,
+                        BLACK_MAN => structure += weights.clash,
                         _ => (),
                     };
                 }
@@ -181,10 +278,7 @@ impl SlonenokJudge {
 
 impl Judge for SlonenokJudge {
     fn recall(&self, position: &Position, _: Depth) -> PositionMemory {
-        match self.hash.get(position) {
-            Some(found) => found.as_memory(),
-            _ => PositionMemory::empty(),
-        }
+        self.table.recall(position.key())
     }
     fn remember(
         &mut self,
@@ -194,7 +288,7 @@ impl Judge for SlonenokJudge {
         mv: Option<Move>,
         low: bool,
     ) {
-        let (has_move, from, to) = if let Some(mv) = mv {
+        if let Some(mv) = mv {
             if position.side_to_move() == White {
                 if !self.white_killer_moves.contains(&mv) {
                     self.white_killer_moves[self.white_killer_cursor] = mv;
@@ -204,62 +298,48 @@ impl Judge for SlonenokJudge {
                 self.black_killer_moves[self.black_killer_cursor] = mv;
                 self.black_killer_cursor = (self.black_killer_cursor + 1) % KILLERS;
             }
-            (true, mv.from() as SmallField, mv.to() as SmallField)
-        } else {
-            (false, 0, 0)
-        };
+        }
+        self.table
+            .remember(position.key(), depth, evaluation, mv, low);
+    }
 
-        let hash_eval = if let Some(found) = self.hash.get(position) {
-            if found.depth > depth {
-                return;
-            }
-            if found.depth == depth {
-                if !low && evaluation <= found.lower || low && found.upper >= evaluation {
-                    return;
-                }
-                HashEval {
-                    depth,
-                    lower: if low { found.lower } else { evaluation },
-                    upper: if low { evaluation } else { found.upper },
-                    from: if has_move { from } else { found.from },
-                    to: if has_move { to } else { found.to },
-                }
-            } else {
-                HashEval {
-                    depth,
-                    lower: if low { MIN_EVAL } else { evaluation },
-                    upper: if low { evaluation } else { MAX_EVAL },
-                    from,
-                    to,
-                }
-            }
-        } else {
-            HashEval {
-                depth,
-                lower: if low { MIN_EVAL } else { evaluation },
-                upper: if low { evaluation } else { MAX_EVAL },
-                from,
-                to,
-            }
-        };
-        self.hash.insert(*position, hash_eval);
+    fn probe(&self, position: &Position) -> Option<Eval> {
+        if !Tablebase::solves(self.generator.rules()) {
+            return None;
+        }
+        self.tablebase
+            .as_ref()?
+            .probe(position)
+            .map(|value| value.eval())
     }
 
     fn evaluate(&self, position: &Position) -> Eval {
         let stats = PositionStats::for_position(position);
 
-        let beans = (0..5).fold(0, |b, i| b + PIECES[i] * stats.piece_count[i]);
+        let weights = &self.weights;
+        let pieces = [
+            ZERO_EVAL,
+            weights.man,
+            weights.king,
+            -weights.man,
+            -weights.king,
+        ];
+        let beans = (0..5).fold(0, |b, i| b + pieces[i] * stats.piece_count[i]);
         let men = stats.piece_count[WHITE_MAN as usize] + stats.piece_count[BLACK_MAN as usize];
-        let hoffset_white = (0..10).fold(0, |b, i| b + HOFFSET[i] * stats.hoffset_white[i]);
-        let hoffset_black = (0..10).fold(0, |b, i| b + HOFFSET[i] * stats.hoffset_black[i]);
-        let voffset_white_full =
-            (0..10).fold(0, |b, i| b + VOFFSET_FULL[i] * stats.voffset_white[i]);
-        let voffset_white_empty =
-            (0..10).fold(0, |b, i| b + VOFFSET_EMPTY[i] * stats.voffset_white[i]);
-        let voffset_black_full =
-            (0..10).fold(0, |b, i| b + VOFFSET_FULL[i] * stats.voffset_black[i]);
-        let voffset_black_empty =
-            (0..10).fold(0, |b, i| b + VOFFSET_EMPTY[i] * stats.voffset_black[i]);
+        let hoffset_white = (0..10).fold(0, |b, i| b + weights.hoffset[i] * stats.hoffset_white[i]);
+        let hoffset_black = (0..10).fold(0, |b, i| b + weights.hoffset[i] * stats.hoffset_black[i]);
+        let voffset_white_full = (0..10).fold(0, |b, i| {
+            b + weights.voffset_full[i] * stats.voffset_white[i]
+        });
+        let voffset_white_empty = (0..10).fold(0, |b, i| {
+            b + weights.voffset_empty[i] * stats.voffset_white[i]
+        });
+        let voffset_black_full = (0..10).fold(0, |b, i| {
+            b + weights.voffset_full[i] * stats.voffset_black[i]
+        });
+        let voffset_black_empty = (0..10).fold(0, |b, i| {
+            b + weights.voffset_empty[i] * stats.voffset_black[i]
+        });
         let voffset_white = if men >= 30 {
             voffset_white_full
         } else if men <= 10 {
@@ -274,8 +354,8 @@ impl Judge for SlonenokJudge {
         } else {
             ((men - 30) * voffset_black_full + (30 - men) * voffset_black_empty) / 20
         };
-        let balance_white = (0..10).fold(0, |b, i| b + BALANCE[i] * stats.hoffset_white[i]);
-        let balance_black = (0..10).fold(0, |b, i| b + BALANCE[i] * stats.hoffset_black[i]);
+        let balance_white = (0..10).fold(0, |b, i| b + weights.balance[i] * stats.hoffset_white[i]);
+        let balance_black = (0..10).fold(0, |b, i| b + weights.balance[i] * stats.hoffset_black[i]);
 
         let structure = self.evaluate_structure(position);
 
@@ -330,11 +410,13 @@ impl Judge for SlonenokJudge {
     }
 
     fn quiet_move(&self, position: &Position, mv: &Move) -> bool {
+        let size = position.size();
+        let near = 2 * size.per_row();
         mv.num_taken() == 0
             && if position.side_to_move() == White {
-                mv.to() >= 10 || position.piece_at(mv.from()) != WHITE_MAN
+                mv.to() >= near || position.piece_at(mv.from()) != WHITE_MAN
             } else {
-                mv.to() <= 39 || position.piece_at(mv.from()) != BLACK_MAN
+                mv.to() < size.fields() - near || position.piece_at(mv.from()) != BLACK_MAN
             }
     }
 
@@ -344,30 +426,118 @@ impl Judge for SlonenokJudge {
 }
 
 pub struct Slonenok {
+    name: String,
     max_nodes: Nodes,
+    multi_pv: usize,
     slonenok: SlonenokJudge,
     previous: EngineResult,
     position: Position,
+    generator: Generator,
+    time_control: TimeControl,
+    time: TimeManager,
+    stop: Stop,
+    tablebase: Option<Arc<Tablebase>>,
 }
 
 impl Slonenok {
     pub fn create(max_nodes: Nodes) -> Slonenok {
+        Slonenok::with_hash_size(max_nodes, DEFAULT_MEGABYTES)
+    }
+
+    pub fn with_hash_size(max_nodes: Nodes, megabytes: usize) -> Slonenok {
         Slonenok {
+            name: String::from("Slonënok"),
             max_nodes,
-            slonenok: SlonenokJudge::create(Generator::create()),
+            multi_pv: 1,
+            slonenok: SlonenokJudge::with_hash_size(
+                Generator::create(),
+                megabytes,
+                SlonenokWeights::default(),
+            ),
             previous: EngineResult::create(Move::null(), ZERO_EVAL, Meta::create()),
             position: Position::initial(),
+            generator: Generator::create(),
+            time_control: TimeControl::Unlimited,
+            time: TimeManager::start(&TimeControl::Unlimited),
+            stop: Stop::create(),
+            tablebase: None,
         }
     }
+
+    pub fn set_tablebase(&mut self, tablebase: Arc<Tablebase>) {
+        self.slonenok.set_tablebase(tablebase.clone());
+        self.tablebase = Some(tablebase);
+    }
+
+    pub fn load_tablebase(&mut self, dir: &Path) -> Result<(), String> {
+        self.set_tablebase(Arc::new(Tablebase::load(dir)?));
+        Ok(())
+    }
+
+    pub fn set_weights(&mut self, weights: SlonenokWeights) {
+        self.slonenok.set_weights(weights);
+    }
+
+    pub fn load_weights(&mut self, path: &Path) -> Result<(), String> {
+        self.set_weights(weights::load(path)?);
+        Ok(())
+    }
+
+    // tells apart engines playing with different weights
+    pub fn set_display_name(&mut self, name: &str) {
+        self.name = name.to_owned();
+    }
+
+    // ranks the best count moves in every iteration instead of searching for the best one
+    pub fn set_multi_pv(&mut self, count: usize) {
+        self.multi_pv = count.max(1);
+    }
+
+    fn next_ranking(
+        &mut self,
+        mut meta: Meta,
+        depth: Depth,
+        searched: bool,
+    ) -> Option<EngineResult> {
+        let multi = multi_pv::<AdaptiveScope>(
+            &mut self.slonenok,
+            &self.position,
+            depth,
+            self.multi_pv,
+            &self.previous.ranking,
+            &self.stop,
+        );
+        if !multi.complete && searched {
+            return None;
+        }
+        let &(mv, evaluation) = multi.ranking.first()?;
+        meta.add_nodes(multi.meta.get_nodes());
+        self.previous = EngineResult::create(mv, evaluation, meta);
+        self.previous.pv = principal_variation(&self.slonenok, &self.generator, &self.position, mv);
+        self.previous.ranking = multi.ranking;
+        Some(self.previous.clone())
+    }
 }
 
 impl Iterator for Slonenok {
     type Item = EngineResult;
     fn next(&mut self) -> Option<EngineResult> {
+        let searched = self.previous.meta.get_nodes() > 0;
+        if let Some(result) = self
+            .tablebase
+            .as_ref()
+            .and_then(|tablebase| probe_root(tablebase, &self.generator, &self.position))
+        {
+            if searched {
+                return None;
+            }
+            self.previous = result;
+            return Some(self.previous.clone());
+        }
         if self.previous.meta.get_nodes() >= self.max_nodes
             || self.previous.meta.get_depth() > 63
-            || self.previous.evaluation == MIN_EVAL
-            || self.previous.evaluation == MAX_EVAL
+            || self.previous.is_proven()
+            || (searched && (self.stop.stopped() || !self.time.start_iteration()))
         {
             return None;
         }
@@ -385,25 +555,51 @@ impl Iterator for Slonenok {
         };
         meta.put_depth(depth);
         meta.put_depth(depth);
+        if self.multi_pv > 1 {
+            return self.next_ranking(meta, depth, searched);
+        }
         let bns = best_node_search::<AdaptiveScope>(
             &mut self.slonenok,
             &self.position,
             depth,
             &search_result,
+            &self.stop,
         );
+        if !bns.complete && searched {
+            return None;
+        }
         meta.add_nodes(bns.meta.get_nodes());
         self.previous = EngineResult::create(bns.mv, bns.lower, meta);
+        self.previous.pv =
+            principal_variation(&self.slonenok, &self.generator, &self.position, bns.mv);
         Some(self.previous.clone())
     }
 }
 
 impl Engine for Slonenok {
     fn display_name(&self) -> &str {
-        "Slonënok"
+        &self.name
     }
     fn set_position(&mut self, position: &Position) {
         self.slonenok.reset();
         self.position = *position;
         self.previous = EngineResult::empty();
+        self.time = TimeManager::start(&self.time_control);
+        self.stop.restart(self.time.deadline());
+    }
+
+    fn set_time_control(&mut self, time_control: &TimeControl) {
+        self.time_control = *time_control;
+        self.time = TimeManager::start(time_control);
+        self.stop.restart(self.time.deadline());
+    }
+
+    fn stop_handle(&self) -> Option<Stop> {
+        Some(self.stop.clone())
+    }
+
+    fn set_rules(&mut self, rules: Rules) {
+        self.slonenok.set_rules(rules);
+        self.generator = Generator::with_rules(rules);
     }
 }
@@ -0,0 +1,133 @@
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeControl {
+    Unlimited,
+    PerMove(Duration),
+    Clock {
+        remaining: Duration,
+        increment: Duration,
+        moves_to_go: Option<u32>,
+    },
+}
+
+const SAFETY: Duration = Duration::from_millis(30);
+const MOVES_TO_GO: u32 = 30;
+
+pub struct TimeManager {
+    start: Instant,
+    soft: Option<Duration>,
+    hard: Option<Duration>,
+}
+
+impl TimeManager {
+    pub fn start(time_control: &TimeControl) -> TimeManager {
+        let (soft, hard) = match *time_control {
+            TimeControl::Unlimited => (None, None),
+            TimeControl::PerMove(time) => {
+                let hard = time.saturating_sub(SAFETY);
+                (Some(hard / 2), Some(hard))
+            }
+            TimeControl::Clock {
+                remaining,
+                increment,
+                moves_to_go,
+            } => {
+                let available = remaining.saturating_sub(SAFETY);
+                let moves = moves_to_go.unwrap_or(MOVES_TO_GO).max(1);
+                let target = (available / moves + increment).min(available);
+                let reserve = if moves == 1 { available } else { available / 2 };
+                let hard = (3 * target).min(reserve).max(target);
+                (Some(target / 2), Some(hard))
+            }
+        };
+        TimeManager {
+            start: Instant::now(),
+            soft,
+            hard,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    // the next iteration usually takes longer than all previous ones together
+    pub fn start_iteration(&self) -> bool {
+        self.soft.is_none_or(|soft| self.elapsed() < soft)
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.hard.map(|hard| self.start + hard)
+    }
+
+    pub fn expired(&self) -> bool {
+        self.hard.is_some_and(|hard| self.elapsed() >= hard)
+    }
+}
+
+#[test]
+fn unlimited() {
+    let manager = TimeManager::start(&TimeControl::Unlimited);
+    assert!(manager.start_iteration());
+    assert!(!manager.expired());
+    assert!(manager.deadline().is_none());
+}
+
+#[test]
+fn per_move() {
+    let manager = TimeManager::start(&TimeControl::PerMove(Duration::from_millis(1030)));
+    assert_eq!(manager.soft, Some(Duration::from_millis(500)));
+    assert_eq!(manager.hard, Some(Duration::from_millis(1000)));
+    assert!(manager.start_iteration());
+    assert!(!manager.expired());
+
+    let manager = TimeManager::start(&TimeControl::PerMove(Duration::from_millis(10)));
+    assert!(!manager.start_iteration());
+    assert!(manager.expired());
+}
+
+#[test]
+fn clock() {
+    let manager = TimeManager::start(&TimeControl::Clock {
+        remaining: Duration::from_millis(60_030),
+        increment: Duration::from_millis(1_000),
+        moves_to_go: None,
+    });
+    assert_eq!(manager.soft, Some(Duration::from_millis(1_500)));
+    assert_eq!(manager.hard, Some(Duration::from_millis(9_000)));
+
+    let manager = TimeManager::start(&TimeControl::Clock {
+        remaining: Duration::from_millis(10_030),
+        increment: Duration::ZERO,
+        moves_to_go: Some(1),
+    });
+    assert_eq!(manager.soft, Some(Duration::from_millis(5_000)));
+    assert_eq!(manager.hard, Some(Duration::from_millis(10_000)));
+
+    let manager = TimeManager::start(&TimeControl::Clock {
+        remaining: Duration::from_millis(1_030),
+        increment: Duration::from_millis(5_000),
+        moves_to_go: Some(10),
+    });
+    assert_eq!(manager.hard, Some(Duration::from_millis(1_000)));
+}
+
+#[test]
+fn engine_keeps_time() {
+    use super::slonenok::Slonenok;
+    use super::Engine;
+    use crate::board::generator::Generator;
+    use crate::board::position::Position;
+
+    let position = Position::initial();
+    let mut engine = Slonenok::with_hash_size(usize::MAX, 1);
+    engine.set_time_control(&TimeControl::PerMove(Duration::from_millis(200)));
+    let start = Instant::now();
+    engine.set_position(&position);
+    let result = engine.last().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert!(Generator::create()
+        .legal_moves(&position)
+        .contains(&result.mv));
+}
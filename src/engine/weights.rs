@@ -0,0 +1,260 @@
+use std::fs;
+use std::path::Path;
+
+use crate::algorithm::judge::Eval;
+
+// the evaluation parameters of a judge, as named groups of values
+pub trait Weights: Clone + Default {
+    // the names and lengths of the groups, in the order of values
+    fn layout() -> &'static [(&'static str, usize)];
+    fn values(&self) -> Vec<Eval>;
+    fn set_values(&mut self, values: &[Eval]);
+
+    fn names() -> Vec<String> {
+        Self::layout()
+            .iter()
+            .flat_map(|&(name, len)| {
+                (0..len).map(move |i| {
+                    if len == 1 {
+                        name.to_owned()
+                    } else {
+                        format!("{}[{}]", name, i)
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+// one line per group: the name and its values
+pub fn to_text<W: Weights>(weights: &W) -> String {
+    let values = weights.values();
+    let mut text = String::new();
+    let mut start = 0;
+    for &(name, len) in W::layout() {
+        let group: Vec<String> = values[start..start + len]
+            .iter()
+            .map(|value| value.to_string())
+            .collect();
+        text.push_str(&format!("{} {}\n", name, group.join(" ")));
+        start += len;
+    }
+    text
+}
+
+// one member per group, with an array for groups of more than one value
+pub fn to_json<W: Weights>(weights: &W) -> String {
+    let values = weights.values();
+    let mut members = vec![];
+    let mut start = 0;
+    for &(name, len) in W::layout() {
+        let group: Vec<String> = values[start..start + len]
+            .iter()
+            .map(|value| value.to_string())
+            .collect();
+        if len == 1 {
+            members.push(format!("  \"{}\": {}", name, group[0]));
+        } else {
+            members.push(format!("  \"{}\": [{}]", name, group.join(", ")));
+        }
+        start += len;
+    }
+    format!("{{\n{}\n}}\n", members.join(",\n"))
+}
+
+fn parse_value(word: &str, name: &str) -> Result<Eval, String> {
+    word.trim()
+        .parse::<Eval>()
+        .map_err(|_| format!("Invalid value {} for {}", word.trim(), name))
+}
+
+// the lines of the text format
+fn text_groups(text: &str) -> Result<Vec<(String, Vec<Eval>)>, String> {
+    let mut groups = vec![];
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut words = line.split_whitespace();
+        if let Some(name) = words.next() {
+            let group = words
+                .map(|word| parse_value(word, name))
+                .collect::<Result<Vec<Eval>, String>>()?;
+            groups.push((name.to_owned(), group));
+        }
+    }
+    Ok(groups)
+}
+
+// a flat json object of numbers and arrays of numbers, which is all we write
+fn json_groups(text: &str) -> Result<Vec<(String, Vec<Eval>)>, String> {
+    let body = text
+        .trim()
+        .strip_prefix('{')
+        .and_then(|body| body.strip_suffix('}'))
+        .ok_or("Expected a JSON object")?;
+    let mut members = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in body.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            ',' if depth == 0 => {
+                members.push(&body[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    members.push(&body[start..]);
+
+    let mut groups = vec![];
+    for member in members.iter().filter(|member| !member.trim().is_empty()) {
+        let (name, value) = member
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid member {}", member.trim()))?;
+        let name = name
+            .trim()
+            .strip_prefix('"')
+            .and_then(|name| name.strip_suffix('"'))
+            .ok_or_else(|| format!("Invalid name {}", name.trim()))?;
+        let value = value.trim();
+        let group = match value
+            .strip_prefix('[')
+            .and_then(|array| array.strip_suffix(']'))
+        {
+            Some(array) => array
+                .split(',')
+                .map(|word| parse_value(word, name))
+                .collect::<Result<Vec<Eval>, String>>()?,
+            None => vec![parse_value(value, name)?],
+        };
+        groups.push((name.to_owned(), group));
+    }
+    Ok(groups)
+}
+
+// json or the text format, groups that are left out keep their defaults and '#' starts a comment
+pub fn parse<W: Weights>(text: &str) -> Result<W, String> {
+    let groups = if text.trim_start().starts_with('{') {
+        json_groups(text)?
+    } else {
+        text_groups(text)?
+    };
+    let mut weights = W::default();
+    let mut values = weights.values();
+    for (name, group) in groups {
+        let mut start = 0;
+        let mut found = None;
+        for &(layout_name, len) in W::layout() {
+            if layout_name == name {
+                found = Some(len);
+                break;
+            }
+            start += len;
+        }
+        let len = found.ok_or_else(|| format!("Unknown weight {}", name))?;
+        if group.len() != len {
+            return Err(format!(
+                "Expected {} values for {}, found {}",
+                len,
+                name,
+                group.len()
+            ));
+        }
+        values[start..start + len].copy_from_slice(&group);
+    }
+    weights.set_values(&values);
+    Ok(weights)
+}
+
+pub fn load<W: Weights>(path: &Path) -> Result<W, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse(&text)
+}
+
+// as json when the file name asks for it
+pub fn save<W: Weights>(weights: &W, path: &Path) -> Result<(), String> {
+    let text = if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        to_json(weights)
+    } else {
+        to_text(weights)
+    };
+    fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[test]
+fn text_roundtrip() {
+    use super::sherlock::SherlockWeights;
+    use super::slonenok::SlonenokWeights;
+
+    let mut weights = SherlockWeights {
+        king: 1400,
+        ..SherlockWeights::default()
+    };
+    weights.balance[3] = -40;
+    assert_eq!(parse::<SherlockWeights>(&to_text(&weights)), Ok(weights));
+    let weights = SlonenokWeights::default();
+    assert_eq!(parse::<SlonenokWeights>(&to_text(&weights)), Ok(weights));
+    assert_eq!(
+        SherlockWeights::names().len(),
+        SherlockWeights::default().values().len()
+    );
+
+    let parsed: SherlockWeights = parse("# tuned\nman 510\n").unwrap();
+    assert_eq!(parsed.man, 510);
+    assert_eq!(parsed.king, SherlockWeights::default().king);
+    assert!(parse::<SherlockWeights>("queen 900").is_err());
+    assert!(parse::<SherlockWeights>("balance 1 2").is_err());
+}
+
+#[test]
+fn json() {
+    use super::sherlock::SherlockWeights;
+    use super::slonenok::SlonenokWeights;
+
+    let mut weights = SlonenokWeights::default();
+    weights.voffset_empty[9] = 12;
+    assert_eq!(parse::<SlonenokWeights>(&to_json(&weights)), Ok(weights));
+    let parsed: SherlockWeights =
+        parse("{ \"king\": 1450, \"center\": [0, 0, 0, 0, 1, 1, 0, 0, 0, 0] }").unwrap();
+    assert_eq!(parsed.king, 1450);
+    assert_eq!(parsed.center[4], 1);
+    assert_eq!(parsed.man, SherlockWeights::default().man);
+    assert_eq!(
+        parse::<SherlockWeights>("{}"),
+        Ok(SherlockWeights::default())
+    );
+    assert!(parse::<SherlockWeights>("{ \"man\": 1.5 }").is_err());
+    assert!(parse::<SherlockWeights>("{ man: 500 }").is_err());
+}
+
+#[test]
+fn file() {
+    use super::sherlock::{Sherlock, SherlockJudge, SherlockWeights};
+    use crate::algorithm::judge::Judge;
+    use crate::board::generator::Generator;
+    use crate::board::position::Position;
+    use std::env;
+
+    let weights = SherlockWeights {
+        man: 400,
+        ..SherlockWeights::default()
+    };
+    for extension in &["txt", "json"] {
+        let path = env::temp_dir().join(format!("weights-{}.{}", std::process::id(), extension));
+        save(&weights, &path).unwrap();
+        let loaded = load::<SherlockWeights>(&path);
+        let mut engine = Sherlock::create(1_000);
+        assert!(engine.load_weights(&path).is_ok());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, Ok(weights));
+    }
+
+    let position = Position::parse_pdn("W:W31,32:B19").unwrap();
+    let tuned = SherlockJudge::create(Generator::create(), weights);
+    let default = SherlockJudge::create(Generator::create(), SherlockWeights::default());
+    assert!(tuned.evaluate(&position) < default.evaluate(&position));
+}
@@ -1,4 +1,9 @@
 pub mod algorithm;
 pub mod board;
+pub mod book;
 pub mod engine;
+pub mod pdn;
+pub mod tablebase;
+pub mod tournament;
+pub mod tuning;
 pub mod uci;
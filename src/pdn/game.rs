@@ -0,0 +1,154 @@
+use crate::board::mv::Move;
+use crate::board::position::Position;
+use crate::board::rules::Rules;
+
+pub type Nag = u8;
+
+#[derive(Clone)]
+pub struct PdnMove {
+    pub mv: Move,
+    pub nags: Vec<Nag>,
+    pub comment_before: Option<String>,
+    pub comment: Option<String>,
+    pub variations: Vec<Vec<PdnMove>>,
+}
+
+impl PdnMove {
+    pub fn create(mv: Move) -> PdnMove {
+        PdnMove {
+            mv,
+            nags: vec![],
+            comment_before: None,
+            comment: None,
+            variations: vec![],
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PdnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<PdnMove>,
+    pub comment: Option<String>,
+}
+
+pub const EVENT: &str = "Event";
+pub const WHITE: &str = "White";
+pub const BLACK: &str = "Black";
+pub const RESULT: &str = "Result";
+pub const FEN: &str = "FEN";
+pub const GAME_TYPE: &str = "GameType";
+pub const UNKNOWN_RESULT: &str = "*";
+
+impl PdnGame {
+    pub fn create() -> PdnGame {
+        PdnGame {
+            tags: vec![],
+            moves: vec![],
+            comment: None,
+        }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some(found) => found.1 = value.to_owned(),
+            None => self.tags.push((name.to_owned(), value.to_owned())),
+        }
+    }
+
+    pub fn event(&self) -> Option<&str> {
+        self.tag(EVENT)
+    }
+
+    pub fn white(&self) -> Option<&str> {
+        self.tag(WHITE)
+    }
+
+    pub fn black(&self) -> Option<&str> {
+        self.tag(BLACK)
+    }
+
+    pub fn result(&self) -> &str {
+        self.tag(RESULT).unwrap_or(UNKNOWN_RESULT)
+    }
+
+    pub fn game_type(&self) -> Option<&str> {
+        self.tag(GAME_TYPE)
+    }
+
+    // international without a game type
+    pub fn rules(&self) -> Result<Rules, String> {
+        match self.game_type() {
+            Some(game_type) => Rules::from_game_type(game_type),
+            None => Ok(Rules::international()),
+        }
+    }
+
+    pub fn set_rules(&mut self, rules: &Rules) -> Result<(), String> {
+        let game_type = rules
+            .game_type()
+            .ok_or_else(|| "No game type for these rules".to_owned())?;
+        self.set_tag(GAME_TYPE, game_type);
+        Ok(())
+    }
+
+    pub fn initial_position(&self) -> Result<Position, String> {
+        let rules = self.rules()?;
+        match self.tag(FEN) {
+            Some(fen) => Position::parse_pdn_with_size(fen, rules.size),
+            None => Ok(rules.initial()),
+        }
+    }
+
+    pub fn set_initial_position(&mut self, position: &Position) {
+        if *position == Position::initial_with_size(position.size()) {
+            self.tags.retain(|(tag, _)| tag != FEN);
+        } else {
+            self.set_tag(FEN, &position.pdn_fen());
+        }
+    }
+
+    pub fn push(&mut self, mv: Move) {
+        self.moves.push(PdnMove::create(mv));
+    }
+
+    pub fn positions(&self) -> Result<Vec<Position>, String> {
+        let mut positions = vec![self.initial_position()?];
+        for pdn_move in &self.moves {
+            let next = positions[positions.len() - 1].go(&pdn_move.mv);
+            positions.push(next);
+        }
+        Ok(positions)
+    }
+}
+
+#[test]
+fn tags() {
+    let mut game = PdnGame::create();
+    assert!(game.event().is_none());
+    assert_eq!(game.result(), "*");
+    game.set_tag(EVENT, "Test");
+    game.set_tag(RESULT, "1-1");
+    game.set_tag(EVENT, "Match");
+    assert_eq!(game.tags.len(), 2);
+    assert_eq!(game.event(), Some("Match"));
+    assert_eq!(game.result(), "1-1");
+}
+
+#[test]
+fn initial_position() {
+    let mut game = PdnGame::create();
+    assert!(game.initial_position().unwrap() == Position::initial());
+    let position = Position::parse("b 3B1/5/5/5/5/ewebe/5/eh2/w4/5").unwrap();
+    game.set_initial_position(&position);
+    assert!(game.initial_position().unwrap() == position);
+    game.set_initial_position(&Position::initial());
+    assert!(game.tag(FEN).is_none());
+}
@@ -0,0 +1,3 @@
+pub mod game;
+pub mod reader;
+pub mod writer;
@@ -0,0 +1,455 @@
+use std::io::{BufRead, Lines};
+use std::iter::Peekable;
+use std::str::Chars;
+
+use super::game::{Nag, PdnGame, PdnMove, RESULT};
+use crate::board::generator::Generator;
+use crate::board::position::Position;
+
+const RESULTS: [&str; 8] = ["2-0", "0-2", "1-1", "0-0", "1-0", "0-1", "1/2-1/2", "*"];
+
+fn is_result(word: &str) -> bool {
+    RESULTS.contains(&word)
+}
+
+fn suffix_nag(suffix: &str) -> Option<Nag> {
+    match suffix {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Open,
+    Close,
+    Nag(Nag),
+    Move(String),
+    Result(String),
+}
+
+fn read_while<F>(chars: &mut Peekable<Chars>, accept: F) -> String
+where
+    F: Fn(char) -> bool,
+{
+    let mut word = String::new();
+    while let Some(&c) = chars.peek() {
+        if !accept(c) {
+            break;
+        }
+        word.push(c);
+        chars.next();
+    }
+    word
+}
+
+fn read_tag(chars: &mut Peekable<Chars>) -> Result<Token, String> {
+    read_while(chars, char::is_whitespace);
+    let name = read_while(chars, |c| c.is_alphanumeric() || c == '_');
+    read_while(chars, char::is_whitespace);
+    if chars.next() != Some('"') {
+        return Err(format!("Missing value for tag {}", name));
+    }
+    let mut value = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.next() {
+                Some(c) => value.push(c),
+                None => return Err(format!("Unterminated tag {}", name)),
+            },
+            Some(c) => value.push(c),
+            None => return Err(format!("Unterminated tag {}", name)),
+        }
+    }
+    read_while(chars, char::is_whitespace);
+    if chars.next() != Some(']') {
+        return Err(format!("Unterminated tag {}", name));
+    }
+    Ok(Token::Tag(name, value))
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            '[' => {
+                chars.next();
+                tokens.push(read_tag(&mut chars)?);
+            }
+            '{' => {
+                chars.next();
+                let comment = read_while(&mut chars, |c| c != '}');
+                if chars.next().is_none() {
+                    return Err("Unterminated comment".into());
+                }
+                tokens.push(Token::Comment(comment.trim().to_owned()));
+            }
+            ';' => {
+                chars.next();
+                let comment = read_while(&mut chars, |c| c != '\n');
+                tokens.push(Token::Comment(comment.trim().to_owned()));
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '$' => {
+                chars.next();
+                let number = read_while(&mut chars, |c| c.is_ascii_digit());
+                match number.parse() {
+                    Ok(nag) => tokens.push(Token::Nag(nag)),
+                    Err(_) => return Err(format!("Invalid NAG ${}", number)),
+                }
+            }
+            '!' | '?' => {
+                let suffix = read_while(&mut chars, |c| c == '!' || c == '?');
+                match suffix_nag(&suffix) {
+                    Some(nag) => tokens.push(Token::Nag(nag)),
+                    None => return Err(format!("Invalid suffix {}", suffix)),
+                }
+            }
+            _ => {
+                let word = read_while(&mut chars, |c| {
+                    !c.is_whitespace() && !"[]{}();$!?".contains(c)
+                });
+                if word.is_empty() {
+                    return Err(format!("Unexpected character {}", c));
+                }
+                if is_result(&word) {
+                    tokens.push(Token::Result(word));
+                    continue;
+                }
+                // strip move numbers such as "12." or "12..."
+                let notation = match word.rfind('.') {
+                    Some(dot) => &word[dot + 1..],
+                    None => &word[..],
+                };
+                if !notation.is_empty() {
+                    tokens.push(Token::Move(notation.to_owned()));
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn append(comment: &mut Option<String>, text: String) {
+    *comment = match comment.take() {
+        Some(previous) => Some(format!("{} {}", previous, text)),
+        None => Some(text),
+    };
+}
+
+struct Parser<'a> {
+    generator: &'a Generator,
+    tokens: Vec<Token>,
+    index: usize,
+    result: Option<String>,
+    comment: Option<String>,
+}
+
+impl<'a> Parser<'a> {
+    fn parse_line(&mut self, initial: Position, depth: usize) -> Result<Vec<PdnMove>, String> {
+        let mut moves: Vec<PdnMove> = vec![];
+        let mut position = initial;
+        let mut previous = initial;
+        let mut pending = None;
+        while self.index < self.tokens.len() {
+            let index = self.index;
+            self.index += 1;
+            match &self.tokens[index] {
+                Token::Move(notation) => {
+                    let mv = self
+                        .generator
+                        .parse_move(&position, notation)
+                        .map_err(|msg| format!("{} in {}", msg, position.pdn_fen()))?;
+                    let mut pdn_move = PdnMove::create(mv);
+                    pdn_move.comment_before = pending.take();
+                    moves.push(pdn_move);
+                    previous = position;
+                    position = position.go(&mv);
+                }
+                Token::Nag(nag) => {
+                    if let Some(last) = moves.last_mut() {
+                        last.nags.push(*nag);
+                    }
+                }
+                Token::Comment(comment) => match moves.last_mut() {
+                    Some(last) => append(&mut last.comment, comment.clone()),
+                    None => append(&mut pending, comment.clone()),
+                },
+                Token::Open => {
+                    if moves.is_empty() {
+                        return Err("Variation without move".into());
+                    }
+                    let variation = self.parse_line(previous, depth + 1)?;
+                    if let Some(last) = moves.last_mut() {
+                        last.variations.push(variation);
+                    }
+                }
+                Token::Close if depth > 0 => return Ok(moves),
+                Token::Close => return Err("Unbalanced variation".into()),
+                Token::Result(_) if depth > 0 => return Err("Result inside variation".into()),
+                Token::Result(result) => {
+                    self.result = Some(result.clone());
+                    break;
+                }
+                Token::Tag(name, _) => return Err(format!("Unexpected tag {}", name)),
+            }
+        }
+        if depth > 0 {
+            return Err("Unterminated variation".into());
+        }
+        if let Some(comment) = pending {
+            append(&mut self.comment, comment);
+        }
+        Ok(moves)
+    }
+}
+
+pub fn parse_game(generator: &Generator, text: &str) -> Result<PdnGame, String> {
+    let mut tokens = tokenize(text)?;
+    let mut game = PdnGame::create();
+    let tags = tokens
+        .iter()
+        .take_while(|token| matches!(token, Token::Tag(_, _)))
+        .count();
+    for token in tokens.drain(..tags) {
+        if let Token::Tag(name, value) = token {
+            game.tags.push((name, value));
+        }
+    }
+
+    // the game type may ask for other rules than the reader's
+    let rules = game.rules()?;
+    let own;
+    let generator = if rules == *generator.rules() {
+        generator
+    } else {
+        own = Generator::with_rules(rules);
+        &own
+    };
+    let mut parser = Parser {
+        generator,
+        tokens,
+        index: 0,
+        result: None,
+        comment: None,
+    };
+    game.moves = parser.parse_line(game.initial_position()?, 0)?;
+    game.comment = parser.comment;
+    if let Some(result) = parser.result {
+        if game.tag(RESULT).is_none() {
+            game.set_tag(RESULT, &result);
+        }
+    }
+    Ok(game)
+}
+
+#[derive(Default)]
+struct Scanner {
+    comment: bool,
+    depth: isize,
+    moves: bool,
+}
+
+impl Scanner {
+    fn at_rest(&self) -> bool {
+        !self.comment && self.depth == 0
+    }
+
+    // returns true when the line completes a game
+    fn feed(&mut self, line: &str) -> bool {
+        if !self.comment && line.trim_start().starts_with('[') {
+            return false;
+        }
+        let mut finished = false;
+        let mut word = String::new();
+        for c in line.chars().chain(Some(' ')) {
+            if self.comment {
+                self.comment = c != '}';
+                continue;
+            }
+            match c {
+                '{' => self.comment = true,
+                ';' => break,
+                '(' => self.depth += 1,
+                ')' => self.depth -= 1,
+                _ if !c.is_whitespace() => {
+                    word.push(c);
+                    continue;
+                }
+                _ => (),
+            }
+            if !word.is_empty() {
+                self.moves = true;
+                finished = self.depth == 0 && is_result(&word);
+                word.clear();
+            }
+        }
+        finished
+    }
+}
+
+pub struct PdnReader<R> {
+    lines: Lines<R>,
+    generator: Generator,
+    pending: String,
+}
+
+impl<R: BufRead> PdnReader<R> {
+    pub fn create(reader: R) -> PdnReader<R> {
+        PdnReader {
+            lines: reader.lines(),
+            generator: Generator::create(),
+            pending: String::new(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PdnReader<R> {
+    type Item = Result<PdnGame, String>;
+
+    fn next(&mut self) -> Option<Result<PdnGame, String>> {
+        let mut text = std::mem::take(&mut self.pending);
+        let mut scanner = Scanner::default();
+        loop {
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(err)) => return Some(Err(err.to_string())),
+                None => break,
+            };
+            if scanner.moves && scanner.at_rest() && line.trim_start().starts_with('[') {
+                self.pending = line + "\n";
+                break;
+            }
+            let finished = scanner.feed(&line);
+            text.push_str(&line);
+            text.push('\n');
+            if finished {
+                break;
+            }
+        }
+
+        if text.trim().is_empty() {
+            None
+        } else {
+            Some(parse_game(&self.generator, &text))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::mv::Move;
+    use crate::pdn::game::FEN;
+    use std::io::Cursor;
+
+    const GAME: &str = r#"[Event "Test \"match\""]
+[White "Sherlock"]
+[Black "Slonenok"]
+[Result "1-1"]
+[GameType "20"]
+
+{Opening} 1. 32-28 19-23 2. 28x19 {forced} 14x23 $1 3. 33-28! (3. 37-32 10-14?) 3...
+23x32 4. 37x28 ; line comment
+1-1
+"#;
+
+    #[test]
+    fn tags_and_moves() {
+        let game = parse_game(&Generator::create(), GAME).unwrap();
+        assert_eq!(game.event(), Some("Test \"match\""));
+        assert_eq!(game.white(), Some("Sherlock"));
+        assert_eq!(game.black(), Some("Slonenok"));
+        assert_eq!(game.result(), "1-1");
+        assert_eq!(game.game_type(), Some("20"));
+        assert_eq!(game.moves.len(), 7);
+        assert!(game.moves[0].mv == Move::shift(31, 27));
+        assert_eq!(game.moves[0].comment_before.as_deref(), Some("Opening"));
+        assert!(game.moves[2].mv == Move::take_one(27, 18, 22));
+        assert_eq!(game.moves[2].comment.as_deref(), Some("forced"));
+        assert_eq!(game.moves[3].nags, vec![1]);
+        assert_eq!(game.moves[4].nags, vec![1]);
+        assert_eq!(game.moves[4].variations.len(), 1);
+        assert_eq!(game.moves[4].variations[0].len(), 2);
+        assert_eq!(game.moves[4].variations[0][1].nags, vec![2]);
+        assert_eq!(game.moves[6].comment.as_deref(), Some("line comment"));
+        assert_eq!(game.positions().unwrap().len(), 8);
+    }
+
+    #[test]
+    fn ambiguous_capture() {
+        let position = Position::parse("b 3B1/5/5/5/5/ewebe/5/eh2/w4/5").unwrap();
+        let text = format!("[FEN \"{}\"]\n1... 4x36x47x33 *", position.pdn_fen());
+        let game = parse_game(&Generator::create(), &text).unwrap();
+        assert!(game.tag(FEN).is_some());
+        assert!(game.moves[0].mv == Move::take(3, 32, &[26, 37, 40]));
+        assert_eq!(game.result(), "*");
+
+        let text = format!("[FEN \"{}\"]\n1... 4x33 *", position.pdn_fen());
+        assert!(parse_game(&Generator::create(), &text).is_err());
+    }
+
+    #[test]
+    fn game_type() {
+        let text = "[GameType \"25\"]\n1. 22-17 11-15 2. 17-14 10x17 *\n";
+        let game = parse_game(&Generator::create(), text).unwrap();
+        let rules = game.rules().unwrap();
+        assert_eq!(rules, crate::board::rules::Rules::russian());
+        assert_eq!(game.moves.len(), 4);
+        let generator = Generator::with_rules(rules);
+        let positions = game.positions().unwrap();
+        assert_eq!(positions[4].count_pieces(), 23);
+        assert_eq!(
+            generator.to_pdn_string(&positions[3], &game.moves[3].mv),
+            "10x17"
+        );
+    }
+
+    #[test]
+    fn invalid_games() {
+        let generator = Generator::create();
+        assert!(parse_game(&generator, "1. 32-27 19-23 (2. 37-32").is_err());
+        assert!(parse_game(&generator, "1. 32-28 19-23)").is_err());
+        assert!(parse_game(&generator, "1. 32-29").is_err());
+        assert!(parse_game(&generator, "{unterminated 1. 32-28").is_err());
+        assert!(parse_game(&generator, "[Event \"x\"").is_err());
+        assert!(parse_game(&generator, "1. 32-28 $x").is_err());
+    }
+
+    #[test]
+    fn multiple_games() {
+        let text = format!(
+            "{}\n[Event \"Second\"]\n1. 34-29 *\n\n1. 31-26 {{no tags}} 2-0\n[Event \"Open\"]\n1. 33-29",
+            GAME
+        );
+        let games = PdnReader::create(Cursor::new(text))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(games.len(), 4);
+        assert_eq!(games[0].moves.len(), 7);
+        assert_eq!(games[1].event(), Some("Second"));
+        assert_eq!(games[1].moves.len(), 1);
+        assert!(games[2].event().is_none());
+        assert_eq!(games[2].result(), "2-0");
+        assert_eq!(games[3].event(), Some("Open"));
+        assert_eq!(games[3].result(), "*");
+    }
+}
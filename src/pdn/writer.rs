@@ -0,0 +1,152 @@
+use super::game::{PdnGame, PdnMove};
+use crate::board::generator::Generator;
+use crate::board::piece::Color;
+use crate::board::position::Position;
+
+const LINE_LENGTH: usize = 79;
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn comment(text: &str) -> String {
+    format!("{{{}}}", text.replace('}', ")"))
+}
+
+fn write_line(
+    generator: &Generator,
+    initial: &Position,
+    first_number: usize,
+    moves: &[PdnMove],
+    words: &mut Vec<String>,
+) {
+    let mut position = *initial;
+    let mut number = first_number;
+    let mut numbered = false;
+    for pdn_move in moves {
+        if let Some(text) = &pdn_move.comment_before {
+            words.push(comment(text));
+            numbered = false;
+        }
+        if position.side_to_move() == Color::White {
+            words.push(format!("{}.", number));
+        } else if !numbered {
+            words.push(format!("{}...", number));
+        }
+        words.push(generator.to_pdn_string(&position, &pdn_move.mv));
+        numbered = true;
+
+        for nag in &pdn_move.nags {
+            words.push(format!("${}", nag));
+        }
+        if let Some(text) = &pdn_move.comment {
+            words.push(comment(text));
+            numbered = false;
+        }
+        for variation in &pdn_move.variations {
+            let start = words.len();
+            write_line(generator, &position, number, variation, words);
+            if words.len() > start {
+                words[start].insert(0, '(');
+                words.last_mut().unwrap().push(')');
+            }
+            numbered = false;
+        }
+
+        if position.side_to_move() == Color::Black {
+            number += 1;
+        }
+        position = position.go(&pdn_move.mv);
+    }
+}
+
+pub fn write_game(generator: &Generator, game: &PdnGame) -> Result<String, String> {
+    let mut pdn = String::new();
+    for (name, value) in &game.tags {
+        pdn.push_str(&format!("[{} \"{}\"]\n", name, escape(value)));
+    }
+    if !game.tags.is_empty() {
+        pdn.push('\n');
+    }
+
+    let mut words = vec![];
+    write_line(
+        generator,
+        &game.initial_position()?,
+        1,
+        &game.moves,
+        &mut words,
+    );
+    if let Some(text) = &game.comment {
+        words.push(comment(text));
+    }
+    words.push(game.result().to_owned());
+
+    let mut line_length = 0;
+    for word in words {
+        if line_length > 0 && line_length + 1 + word.len() > LINE_LENGTH {
+            pdn.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            pdn.push(' ');
+            line_length += 1;
+        }
+        line_length += word.len();
+        pdn.push_str(&word);
+    }
+    pdn.push('\n');
+    Ok(pdn)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::mv::Move;
+    use crate::pdn::game::{EVENT, RESULT};
+    use crate::pdn::reader::parse_game;
+
+    #[test]
+    fn write_moves() {
+        let generator = Generator::create();
+        let mut game = PdnGame::create();
+        game.set_tag(EVENT, "Quote \"me\"");
+        game.set_tag(RESULT, "2-0");
+        game.push(Move::shift(31, 27));
+        game.push(Move::shift(18, 22));
+        game.push(Move::take_one(27, 18, 22));
+        game.moves[1].comment = Some("weak".into());
+        game.moves[2].nags.push(1);
+        assert_eq!(
+            write_game(&generator, &game).unwrap(),
+            "[Event \"Quote \\\"me\\\"\"]\n[Result \"2-0\"]\n\n1. 32-28 19-23 {weak} 2. 28x19 $1 2-0\n"
+        );
+    }
+
+    #[test]
+    fn black_to_move() {
+        let generator = Generator::create();
+        let position = Position::parse("b 3B1/5/5/5/5/ewebe/5/eh2/w4/5").unwrap();
+        let mut game = PdnGame::create();
+        game.set_initial_position(&position);
+        game.push(Move::take(3, 32, &[26, 37, 40]));
+        let pdn = write_game(&generator, &game).unwrap();
+        assert!(pdn.ends_with("\n\n1... 4x36x47x33 *\n"));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let generator = Generator::create();
+        let text =
+            "[Event \"Roundtrip\"]\n[Result \"1-1\"]\n\n{Start} 1. 32-28 19-23 2. 28x19 14x23 \
+                    $1 3. 33-28 {main} (3. 37-32 10-14 $2 {alternative} (3... 13-19) 4. 41-37) \
+                    3... 23x32 4. 37x28 {end} 1-1\n";
+        let game = parse_game(&generator, text).unwrap();
+        let written = write_game(&generator, &game).unwrap();
+        let reparsed = parse_game(&generator, &written).unwrap();
+        assert_eq!(written, write_game(&generator, &reparsed).unwrap());
+        let flat = written.replace('\n', " ");
+        assert!(flat.contains("(3. 37-32 10-14 $2 {alternative} (3... 13-19) 4. 41-37)"));
+        assert!(written.contains("3... 23x32"));
+        assert!(written.lines().all(|line| line.len() <= LINE_LENGTH));
+    }
+}
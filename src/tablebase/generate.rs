@@ -0,0 +1,454 @@
+use std::mem;
+use std::path::Path;
+use std::thread;
+
+use super::index::{flip, Material};
+use super::{decode, encode, Table, Tablebase, Value, MAX_WIN};
+use crate::board::coords::{Coord, Coords, MinXy};
+use crate::board::generator::Generator;
+use crate::board::piece::{EMPTY, WHITE_KING, WHITE_MAN};
+use crate::board::position::{Field, Position, Size};
+
+// no value encodes to it
+const UNKNOWN: u8 = u8::MAX;
+// every distance a table can store
+const MAX_PLIES: usize = MAX_WIN as usize + 1;
+
+// every material with at least one piece a side, smallest first and with promotions solved before
+pub fn materials(pieces: usize) -> Vec<Material> {
+    let mut materials = vec![];
+    for white_men in 0..=pieces {
+        for white_kings in 0..=pieces - white_men {
+            for black_men in 0..=pieces - white_men - white_kings {
+                for black_kings in 0..=pieces - white_men - white_kings - black_men {
+                    let material = Material {
+                        white_men,
+                        white_kings,
+                        black_men,
+                        black_kings,
+                    };
+                    if material.white() > 0 && material.black() > 0 {
+                        materials.push(material);
+                    }
+                }
+            }
+        }
+    }
+    materials.sort_by_key(|material| (material.pieces(), material.men(), *material));
+    materials
+}
+
+// none when the coordinates are off the board
+fn field_at(x: Coord, y: Coord) -> Option<Field> {
+    let coords = Coords { x, y };
+    if (coords.min_x(Size::Ten)..=coords.max_x(Size::Ten)).contains(&x) {
+        Some(coords.field_on(Size::Ten))
+    } else {
+        None
+    }
+}
+
+struct Pair {
+    materials: Vec<Material>,
+    values: Vec<Vec<u8>>,
+    // successors within the pair that are still unknown, none when a move out of the pair avoids the loss
+    counts: Vec<Vec<Option<u8>>>,
+    // the longest win among the known successors, plus one
+    longest: Vec<Vec<u8>>,
+    // positions to resolve by distance
+    buckets: Vec<Vec<(usize, usize, u8)>>,
+    // positions whose distance is too long for the buckets
+    beyond: Vec<(usize, usize)>,
+}
+
+impl Pair {
+    fn table(&self, material: &Material) -> Option<usize> {
+        self.materials.iter().position(|m| m == material)
+    }
+
+    fn schedule(&mut self, table: usize, index: usize, value: Value) {
+        let plies = match value {
+            Value::Win(plies) | Value::Loss(plies) => plies as usize,
+            Value::Draw => return,
+        };
+        if plies < self.buckets.len() {
+            self.buckets[plies].push((table, index, encode(value)));
+        } else {
+            self.beyond.push((table, index));
+        }
+    }
+}
+
+// what the moves out of the pair tell, and how many moves stay within it
+struct Start {
+    win: Option<usize>,
+    longest: usize,
+    count: usize,
+    no_loss: bool,
+}
+
+fn start(
+    solved: &Tablebase,
+    materials: &[Material],
+    generator: &Generator,
+    position: &Position,
+) -> Start {
+    let mut result = Start {
+        win: None,
+        longest: 0,
+        count: 0,
+        no_loss: false,
+    };
+    for mv in generator.legal_moves(position) {
+        // black is to move next, seen as white
+        let next = flip(&position.go(&mv));
+        let material = Material::of(&next);
+        let value = if material.white() == 0 {
+            Some(Value::Loss(0))
+        } else if materials.contains(&material) {
+            result.count += 1;
+            continue;
+        } else {
+            solved.probe(&next)
+        };
+        match value {
+            Some(Value::Loss(plies)) => {
+                let plies = plies as usize + 1;
+                result.win = Some(result.win.map_or(plies, |win| win.min(plies)));
+            }
+            Some(Value::Win(plies)) => result.longest = result.longest.max(plies as usize + 1),
+            _ => result.no_loss = true,
+        }
+    }
+    result
+}
+
+// the positions before a quiet white move into the position after it, seen as white to move
+fn predecessors(generator: &Generator, position: &Position) -> Vec<Position> {
+    let after = flip(position);
+    let mut result = vec![];
+    for to in 0..50 {
+        let piece = after.piece_at(to);
+        let Coords { x, y } = Coords::on(Size::Ten, to);
+        let mut froms = vec![];
+        // white men step up the board, to a higher x or y
+        if piece == WHITE_MAN {
+            froms.extend(field_at(x - 1, y));
+            froms.extend(field_at(x, y - 1));
+        } else if piece == WHITE_KING {
+            for (x_step, y_step) in [(1, 0), (0, 1), (-1, 0), (0, -1)] {
+                let mut distance = 1;
+                while let Some(from) = field_at(x + distance * x_step, y + distance * y_step) {
+                    if !after.is_empty(from) {
+                        break;
+                    }
+                    froms.push(from);
+                    distance += 1;
+                }
+            }
+        }
+        for from in froms {
+            if !after.is_empty(from) {
+                continue;
+            }
+            let before = after
+                .put_piece(to, EMPTY)
+                .put_piece(from, piece)
+                .toggle_side();
+            // the move has to be legal, so no capture was available
+            if generator
+                .legal_moves(&before)
+                .iter()
+                .any(|mv| mv.from() == from && mv.to() == to && mv.num_taken() == 0)
+            {
+                result.push(before);
+            }
+        }
+    }
+    result
+}
+
+// solves a material together with its colour reversed counterpart, as they refer to each other
+pub fn solve(solved: &Tablebase, material: Material) -> Result<Vec<Table>, String> {
+    solve_within(solved, material, MAX_PLIES)
+}
+
+// fails when a win or loss takes max_plies or more
+fn solve_within(
+    solved: &Tablebase,
+    material: Material,
+    max_plies: usize,
+) -> Result<Vec<Table>, String> {
+    let mut materials = vec![material];
+    if material.flip() != material {
+        materials.push(material.flip());
+    }
+    let mut pair = Pair {
+        values: materials.iter().map(|m| vec![UNKNOWN; m.size()]).collect(),
+        counts: materials.iter().map(|m| vec![None; m.size()]).collect(),
+        longest: materials.iter().map(|m| vec![0; m.size()]).collect(),
+        buckets: vec![vec![]; max_plies],
+        beyond: vec![],
+        materials,
+    };
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let generator = Generator::create();
+
+    // one forward pass over all positions
+    for table in 0..pair.materials.len() {
+        let material = pair.materials[table];
+        let chunk_size = material.size().div_ceil(threads).max(1);
+        let materials = &pair.materials;
+        let starts: Vec<Vec<Option<Start>>> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..material.size())
+                .step_by(chunk_size)
+                .map(|first| {
+                    scope.spawn(move || {
+                        let generator = Generator::create();
+                        (first..(first + chunk_size).min(material.size()))
+                            .map(|index| {
+                                material
+                                    .position(index)
+                                    .map(|position| start(solved, materials, &generator, &position))
+                            })
+                            .collect()
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect()
+        });
+        for (index, start) in starts.into_iter().flatten().enumerate() {
+            let start = match start {
+                Some(start) => start,
+                None => {
+                    pair.values[table][index] = encode(Value::Draw);
+                    continue;
+                }
+            };
+            pair.counts[table][index] = if start.no_loss {
+                None
+            } else {
+                Some(start.count as u8)
+            };
+            pair.longest[table][index] = start.longest as u8;
+            if let Some(win) = start.win {
+                pair.schedule(table, index, Value::Win(win as u8));
+            } else if start.count == 0 && !start.no_loss {
+                pair.schedule(table, index, Value::Loss(start.longest as u8));
+            }
+        }
+    }
+
+    // then back from every resolved position, shortest distances first
+    for plies in 0..max_plies {
+        let bucket = mem::take(&mut pair.buckets[plies]);
+        for (table, index, code) in bucket {
+            if pair.values[table][index] != UNKNOWN {
+                continue;
+            }
+            pair.values[table][index] = code;
+            let position = match pair.materials[table].position(index) {
+                Some(position) => position,
+                None => continue,
+            };
+            for before in predecessors(&generator, &position) {
+                let material = Material::of(&before);
+                let previous = match pair.table(&material) {
+                    Some(previous) => previous,
+                    None => continue,
+                };
+                let previous_index = material.index(&before);
+                if pair.values[previous][previous_index] != UNKNOWN {
+                    continue;
+                }
+                match decode(code) {
+                    Value::Loss(plies) => {
+                        pair.schedule(previous, previous_index, Value::Win(plies + 1))
+                    }
+                    Value::Win(plies) => {
+                        let count = match &mut pair.counts[previous][previous_index] {
+                            Some(count) if *count > 0 => count,
+                            _ => continue,
+                        };
+                        *count -= 1;
+                        let left = *count;
+                        let longest = &mut pair.longest[previous][previous_index];
+                        *longest = (*longest).max(plies.saturating_add(1));
+                        if left == 0 {
+                            let longest = *longest;
+                            pair.schedule(previous, previous_index, Value::Loss(longest));
+                        }
+                    }
+                    Value::Draw => (),
+                }
+            }
+        }
+    }
+
+    if pair
+        .beyond
+        .iter()
+        .any(|&(table, index)| pair.values[table][index] == UNKNOWN)
+    {
+        return Err(format!(
+            "{}: a win takes {} plies or more",
+            Table::file_name(&material),
+            max_plies
+        ));
+    }
+
+    // whatever is left can never be forced
+    Ok(pair
+        .materials
+        .into_iter()
+        .zip(pair.values)
+        .map(|(material, values)| Table {
+            material,
+            values: values
+                .into_iter()
+                .map(|value| if value == UNKNOWN { 0 } else { value })
+                .collect(),
+        })
+        .collect())
+}
+
+// adds every missing table up to the number of pieces
+pub fn solve_all(
+    tablebase: &mut Tablebase,
+    pieces: usize,
+    on_table: &mut dyn FnMut(&Table) -> Result<(), String>,
+) -> Result<(), String> {
+    for material in materials(pieces) {
+        if tablebase.contains(&material) {
+            continue;
+        }
+        for table in solve(tablebase, material)? {
+            on_table(&table)?;
+            tablebase.insert(table);
+        }
+    }
+    Ok(())
+}
+
+// resumes from the tables already in the directory
+pub fn generate(
+    dir: &Path,
+    pieces: usize,
+    progress: &mut dyn FnMut(&Material),
+) -> Result<Tablebase, String> {
+    let mut tablebase = Tablebase::load(dir)?;
+    solve_all(&mut tablebase, pieces, &mut |table| {
+        progress(&table.material);
+        table.write(dir)
+    })?;
+    Ok(tablebase)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn order() {
+        let materials = materials(3);
+        assert_eq!(materials.len(), 4 + 12);
+        for (i, material) in materials.iter().enumerate() {
+            let later = &materials[i + 1..];
+            assert!(later.iter().all(|m| m.pieces() >= material.pieces()));
+        }
+    }
+
+    fn check(tablebase: &Tablebase, material: &Material, step: usize) {
+        let generator = Generator::create();
+        for index in (0..material.size()).step_by(step) {
+            let position = match material.position(index) {
+                Some(position) => position,
+                None => continue,
+            };
+            let answers: Vec<Value> = generator
+                .legal_moves(&position)
+                .iter()
+                .map(|mv| tablebase.probe(&position.go(mv)).unwrap())
+                .collect();
+            let win = answers
+                .iter()
+                .filter_map(|answer| match answer {
+                    Value::Loss(plies) => Some(plies + 1),
+                    _ => None,
+                })
+                .min();
+            let longest = answers
+                .iter()
+                .map(|answer| match answer {
+                    Value::Win(plies) => Some(plies + 1),
+                    _ => None,
+                })
+                .try_fold(0, |longest, plies| plies.map(|plies| longest.max(plies)));
+            let expected = match (win, longest) {
+                (Some(plies), _) => Value::Win(plies),
+                (None, Some(plies)) => Value::Loss(plies),
+                (None, None) => Value::Draw,
+            };
+            assert_eq!(
+                tablebase.probe(&position),
+                Some(expected),
+                "{}",
+                position.fen()
+            );
+        }
+    }
+
+    #[test]
+    fn consistent() {
+        let mut tablebase = Tablebase::create();
+        for material in materials(3) {
+            if (material.pieces() == 2 || material.men() == 0) && !tablebase.contains(&material) {
+                for table in solve(&tablebase, material).unwrap() {
+                    tablebase.insert(table);
+                }
+            }
+        }
+
+        let capture = Position::parse_pdn("W:W28:B22").unwrap();
+        assert_eq!(tablebase.probe(&capture), Some(Value::Win(1)));
+        assert_eq!(tablebase.probe(&capture.toggle_side()), Some(Value::Win(1)));
+        let blocked = Position::parse_pdn("W:W46:B41,K37").unwrap();
+        assert_eq!(tablebase.probe(&blocked), None);
+
+        // every value follows from the values after each move
+        for material in materials(2) {
+            check(&tablebase, &material, 1);
+        }
+        let kings = Material {
+            white_men: 0,
+            white_kings: 2,
+            black_men: 0,
+            black_kings: 1,
+        };
+        check(&tablebase, &kings, 7);
+        check(&tablebase, &kings.flip(), 7);
+    }
+
+    #[test]
+    fn too_long() {
+        let mut tablebase = Tablebase::create();
+        for material in materials(2) {
+            if !tablebase.contains(&material) {
+                for table in solve(&tablebase, material).unwrap() {
+                    tablebase.insert(table);
+                }
+            }
+        }
+        let kings = Material {
+            white_men: 0,
+            white_kings: 2,
+            black_men: 0,
+            black_kings: 1,
+        };
+        assert!(solve_within(&tablebase, kings, 5).is_err());
+        assert!(solve_within(&tablebase, kings, MAX_PLIES).is_ok());
+    }
+}
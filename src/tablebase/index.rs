@@ -0,0 +1,232 @@
+use crate::board::piece::{Piece, BLACK_KING, BLACK_MAN, WHITE_KING, WHITE_MAN};
+use crate::board::position::{Field, Position};
+
+// men never stand on their own promotion row
+const MAN_FIELDS: usize = 45;
+const KING_FIELDS: usize = 50;
+
+fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |result, i| result * (n - i) / (i + 1))
+}
+
+// combinatorial number system over sorted squares
+fn rank(squares: &[usize]) -> usize {
+    squares
+        .iter()
+        .enumerate()
+        .map(|(i, &square)| binomial(square, i + 1))
+        .sum()
+}
+
+fn unrank(mut rank: usize, count: usize, fields: usize) -> Vec<usize> {
+    let mut squares = vec![0; count];
+    for i in (0..count).rev() {
+        let mut square = i;
+        while square + 1 < fields && binomial(square + 1, i + 1) <= rank {
+            square += 1;
+        }
+        rank -= binomial(square, i + 1);
+        squares[i] = square;
+    }
+    squares
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Material {
+    pub white_men: usize,
+    pub white_kings: usize,
+    pub black_men: usize,
+    pub black_kings: usize,
+}
+
+struct Group {
+    piece: Piece,
+    count: usize,
+    fields: usize,
+    offset: Field,
+}
+
+impl Material {
+    pub fn of(position: &Position) -> Material {
+        let mut counts = [0; 5];
+        for field in 0..50 {
+            counts[position.piece_at(field) as usize] += 1;
+        }
+        Material {
+            white_men: counts[WHITE_MAN as usize],
+            white_kings: counts[WHITE_KING as usize],
+            black_men: counts[BLACK_MAN as usize],
+            black_kings: counts[BLACK_KING as usize],
+        }
+    }
+
+    pub fn flip(&self) -> Material {
+        Material {
+            white_men: self.black_men,
+            white_kings: self.black_kings,
+            black_men: self.white_men,
+            black_kings: self.white_kings,
+        }
+    }
+
+    pub fn white(&self) -> usize {
+        self.white_men + self.white_kings
+    }
+
+    pub fn black(&self) -> usize {
+        self.black_men + self.black_kings
+    }
+
+    pub fn pieces(&self) -> usize {
+        self.white() + self.black()
+    }
+
+    pub fn men(&self) -> usize {
+        self.white_men + self.black_men
+    }
+
+    pub fn name(&self) -> String {
+        format!(
+            "{}{}{}{}",
+            self.white_men, self.white_kings, self.black_men, self.black_kings
+        )
+    }
+
+    // white men skip row 1, black men skip row 10
+    fn groups(&self) -> [Group; 4] {
+        [
+            Group {
+                piece: WHITE_MAN,
+                count: self.white_men,
+                fields: MAN_FIELDS,
+                offset: 5,
+            },
+            Group {
+                piece: WHITE_KING,
+                count: self.white_kings,
+                fields: KING_FIELDS,
+                offset: 0,
+            },
+            Group {
+                piece: BLACK_MAN,
+                count: self.black_men,
+                fields: MAN_FIELDS,
+                offset: 0,
+            },
+            Group {
+                piece: BLACK_KING,
+                count: self.black_kings,
+                fields: KING_FIELDS,
+                offset: 0,
+            },
+        ]
+    }
+
+    // the number of white to move positions, including some with pieces on the same square
+    pub fn size(&self) -> usize {
+        self.groups()
+            .iter()
+            .map(|group| binomial(group.fields, group.count))
+            .product()
+    }
+
+    pub fn index(&self, position: &Position) -> usize {
+        self.groups().iter().fold(0, |index, group| {
+            let squares: Vec<usize> = (group.offset..group.offset + group.fields)
+                .filter(|&field| position.piece_at(field) == group.piece)
+                .map(|field| field - group.offset)
+                .collect();
+            index * binomial(group.fields, group.count) + rank(&squares)
+        })
+    }
+
+    // None when two pieces would share a square
+    pub fn position(&self, mut index: usize) -> Option<Position> {
+        let groups = self.groups();
+        let mut ranks = [0; 4];
+        for (i, group) in groups.iter().enumerate().rev() {
+            let size = binomial(group.fields, group.count);
+            ranks[i] = index % size;
+            index /= size;
+        }
+        let mut position = Position::create();
+        for (group, &rank) in groups.iter().zip(ranks.iter()) {
+            for square in unrank(rank, group.count, group.fields) {
+                let field = square + group.offset;
+                if !position.is_empty(field) {
+                    return None;
+                }
+                position = position.put_piece(field, group.piece);
+            }
+        }
+        Some(position)
+    }
+}
+
+// rotates the board and swaps the colours, so black to move becomes white to move
+pub fn flip(position: &Position) -> Position {
+    let mut flipped = Position::create();
+    for field in 0..50 {
+        let piece = match position.piece_at(field) {
+            WHITE_MAN => BLACK_MAN,
+            WHITE_KING => BLACK_KING,
+            BLACK_MAN => WHITE_MAN,
+            BLACK_KING => WHITE_KING,
+            _ => continue,
+        };
+        flipped = flipped.put_piece(49 - field, piece);
+    }
+    if flipped.side_to_move() == position.side_to_move() {
+        flipped.toggle_side()
+    } else {
+        flipped
+    }
+}
+
+#[test]
+fn ranks() {
+    assert_eq!(binomial(45, 2), 990);
+    assert_eq!(binomial(3, 5), 0);
+    for rank_ in [0, 1, 7, 989] {
+        let squares = unrank(rank_, 2, MAN_FIELDS);
+        assert!(squares[0] < squares[1]);
+        assert_eq!(rank(&squares), rank_);
+    }
+}
+
+#[test]
+fn index_position() {
+    let position = Position::parse_pdn("W:W28,K3:B22,K49").unwrap();
+    let material = Material::of(&position);
+    assert_eq!(material.name(), "1111");
+    assert_eq!(material.size(), 45 * 50 * 45 * 50);
+    let index = material.index(&position);
+    assert!(material.position(index).unwrap() == position);
+
+    let material = Material {
+        white_men: 0,
+        white_kings: 2,
+        black_men: 1,
+        black_kings: 0,
+    };
+    let mut valid = 0;
+    for index in 0..material.size() {
+        if let Some(position) = material.position(index) {
+            assert_eq!(material.index(&position), index);
+            valid += 1;
+        }
+    }
+    assert_eq!(valid, 45 * 49 * 48 / 2);
+}
+
+#[test]
+fn flip_position() {
+    let position = Position::parse_pdn("B:W28,K3:B22,K49").unwrap();
+    let flipped = flip(&position);
+    assert!(flipped == Position::parse_pdn("W:W29,K2:B23,K48").unwrap());
+    assert!(flip(&flipped) == position);
+    assert_eq!(Material::of(&flipped), Material::of(&position).flip());
+}
@@ -0,0 +1,199 @@
+pub mod generate;
+pub mod index;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use self::index::{flip, Material};
+use crate::algorithm::judge::{backup, loss_eval, win_eval, Eval, DRAW_EVAL};
+use crate::algorithm::multipv::Ranking;
+use crate::board::generator::Generator;
+use crate::board::piece::Color;
+use crate::board::position::{Position, Size};
+use crate::board::rules::Rules;
+
+const MAGIC: &[u8; 4] = b"DTW1";
+const EXTENSION: &str = "dtw";
+// the longest distance a table stores
+const MAX_WIN: u8 = 253;
+
+// distances are in plies, for the side to move
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Value {
+    Draw,
+    Win(u8),
+    Loss(u8),
+}
+
+impl Value {
+    pub fn eval(&self) -> Eval {
+        match *self {
+            Value::Draw => DRAW_EVAL,
+            Value::Win(plies) => win_eval(plies as Eval),
+            Value::Loss(plies) => loss_eval(plies as Eval),
+        }
+    }
+}
+
+// a win takes an odd number of plies, a loss an even number: 0 draw, odd win, even loss + 2
+pub fn encode(value: Value) -> u8 {
+    match value {
+        Value::Draw => 0,
+        Value::Win(plies) => plies.min(MAX_WIN) | 1,
+        Value::Loss(plies) => plies.min(MAX_WIN - 1) + 2,
+    }
+}
+
+pub fn decode(code: u8) -> Value {
+    match code {
+        0 => Value::Draw,
+        code if code % 2 == 1 => Value::Win(code),
+        code => Value::Loss(code - 2),
+    }
+}
+
+// the values of all white to move positions of one material, one byte each
+pub struct Table {
+    pub material: Material,
+    pub values: Vec<u8>,
+}
+
+impl Table {
+    pub fn value(&self, position: &Position) -> Value {
+        decode(self.values[self.material.index(position)])
+    }
+
+    pub fn file_name(material: &Material) -> String {
+        format!("{}.{}", material.name(), EXTENSION)
+    }
+
+    pub fn read(path: &Path) -> Result<Table, String> {
+        let bytes = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if bytes.len() < 8 || &bytes[..4] != MAGIC {
+            return Err(format!("{}: not a tablebase", path.display()));
+        }
+        let material = Material {
+            white_men: bytes[4] as usize,
+            white_kings: bytes[5] as usize,
+            black_men: bytes[6] as usize,
+            black_kings: bytes[7] as usize,
+        };
+        let values = bytes[8..].to_vec();
+        if values.len() != material.size() {
+            return Err(format!("{}: truncated", path.display()));
+        }
+        Ok(Table { material, values })
+    }
+
+    pub fn write(&self, dir: &Path) -> Result<(), String> {
+        let path = dir.join(Table::file_name(&self.material));
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&[
+            self.material.white_men as u8,
+            self.material.white_kings as u8,
+            self.material.black_men as u8,
+            self.material.black_kings as u8,
+        ]);
+        bytes.extend_from_slice(&self.values);
+        fs::write(&path, bytes).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+#[derive(Default)]
+pub struct Tablebase {
+    tables: HashMap<Material, Table>,
+    pieces: usize,
+}
+
+impl Tablebase {
+    pub fn create() -> Tablebase {
+        Tablebase::default()
+    }
+
+    pub fn load(dir: &Path) -> Result<Tablebase, String> {
+        let mut tablebase = Tablebase::create();
+        let entries = fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == EXTENSION)
+            {
+                tablebase.insert(Table::read(&path)?);
+            }
+        }
+        Ok(tablebase)
+    }
+
+    pub fn insert(&mut self, table: Table) {
+        self.pieces = self.pieces.max(table.material.pieces());
+        self.tables.insert(table.material, table);
+    }
+
+    pub fn contains(&self, material: &Material) -> bool {
+        self.tables.contains_key(material)
+    }
+
+    // the largest number of pieces in any table
+    pub fn pieces(&self) -> usize {
+        self.pieces
+    }
+
+    pub fn probe(&self, position: &Position) -> Option<Value> {
+        // the tables are for the big board
+        if position.count_pieces() > self.pieces || position.size() != Size::Ten {
+            return None;
+        }
+        let position = match position.side_to_move() {
+            Color::White => *position,
+            Color::Black => flip(position),
+        };
+        let material = Material::of(&position);
+        if material.white() == 0 {
+            return Some(Value::Loss(0));
+        }
+        self.tables
+            .get(&material)
+            .map(|table| table.value(&position))
+    }
+
+    // the tables are solved under international rules only
+    pub fn solves(rules: &Rules) -> bool {
+        *rules == Rules::international()
+    }
+
+    // the legal moves with their exact evaluations, best first, if every resulting position is known
+    pub fn rank_moves(&self, generator: &Generator, position: &Position) -> Option<Ranking> {
+        if !Tablebase::solves(generator.rules()) {
+            return None;
+        }
+        let mut ranking = generator
+            .legal_moves(position)
+            .into_iter()
+            .map(|mv| {
+                self.probe(&position.go(&mv))
+                    .map(|value| (mv, backup(value.eval())))
+            })
+            .collect::<Option<Ranking>>()?;
+        ranking.sort_by_key(|&(_, evaluation)| -evaluation);
+        Some(ranking)
+    }
+}
+
+#[test]
+fn codes() {
+    for value in [
+        Value::Draw,
+        Value::Win(1),
+        Value::Win(MAX_WIN),
+        Value::Loss(0),
+        Value::Loss(MAX_WIN - 1),
+    ] {
+        assert_eq!(decode(encode(value)), value);
+    }
+    assert_eq!(encode(Value::Loss(0)), 2);
+    assert!(Value::Win(1).eval() > Value::Win(3).eval());
+    assert!(Value::Loss(4).eval() > Value::Loss(2).eval());
+    assert_eq!(Value::Draw.eval(), DRAW_EVAL);
+}
@@ -0,0 +1,392 @@
+pub mod stats;
+
+use self::stats::{Score, Sprt, SprtResult};
+use crate::board::game::{Game, GameResult, Outcome, Rule, ScoreAdjudication};
+use crate::board::piece::Color;
+use crate::board::position::Position;
+use crate::engine::time::TimeControl;
+use crate::engine::{Engine, EngineResult};
+use crate::pdn::game::{PdnGame, BLACK, EVENT, RESULT, WHITE};
+
+pub type TournamentEngine = Box<dyn Engine<Item = EngineResult>>;
+
+const MOVE_LIMIT: usize = 150;
+const ROUND: &str = "Round";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    RoundRobin,
+    // the first engine against each of the others
+    Gauntlet,
+}
+
+pub struct GameRecord {
+    pub round: usize,
+    pub white: usize,
+    pub black: usize,
+    pub outcome: Outcome,
+    pub rule: Rule,
+    pub game: PdnGame,
+}
+
+impl GameRecord {
+    pub fn score(&self, engine: usize) -> Score {
+        let mut score = Score::create();
+        if engine != self.white && engine != self.black {
+            return score;
+        }
+        match (self.outcome, engine == self.white) {
+            (Outcome::Draw, _) => score.draws += 1,
+            (Outcome::WhiteWins, true) | (Outcome::BlackWins, false) => score.wins += 1,
+            _ => score.losses += 1,
+        }
+        score
+    }
+}
+
+// one position in our FEN dialect per line; blank lines and `//` comments are skipped
+pub fn parse_openings(text: &str) -> Result<Vec<Position>, String> {
+    text.lines()
+        .map(|line| line.split("//").next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(Position::parse)
+        .collect()
+}
+
+// a side without an answer or with an illegal move forfeits
+pub fn play_game(
+    white: &mut dyn Engine<Item = EngineResult>,
+    black: &mut dyn Engine<Item = EngineResult>,
+    game: &mut Game,
+) -> GameResult {
+    loop {
+        if let Some(result) = game.result() {
+            return result;
+        }
+        let position = *game.position();
+        let legal = game.legal_moves();
+        let (mv, evaluation) = if legal.len() == 1 {
+            (legal[0], None)
+        } else {
+            let result = match position.side_to_move() {
+                Color::White => {
+                    white.set_position(&position);
+                    white.last()
+                }
+                Color::Black => {
+                    black.set_position(&position);
+                    black.last()
+                }
+            };
+            match result {
+                Some(result) => (result.mv, Some(result.evaluation)),
+                None => return game.forfeit(),
+            }
+        };
+        if game.play_scored(&mv, evaluation).is_err() {
+            return game.forfeit();
+        }
+    }
+}
+
+fn pair(
+    engines: &mut [TournamentEngine],
+    white: usize,
+    black: usize,
+) -> (&mut TournamentEngine, &mut TournamentEngine) {
+    if white < black {
+        let (left, right) = engines.split_at_mut(black);
+        (&mut left[white], &mut right[0])
+    } else {
+        let (left, right) = engines.split_at_mut(white);
+        (&mut right[0], &mut left[black])
+    }
+}
+
+pub struct Tournament {
+    engines: Vec<TournamentEngine>,
+    format: Format,
+    openings: Vec<Position>,
+    rounds: usize,
+    time_control: TimeControl,
+    move_limit: usize,
+    adjudication: Option<ScoreAdjudication>,
+    sprt: Option<Sprt>,
+    event: String,
+    games: Vec<GameRecord>,
+}
+
+impl Tournament {
+    pub fn create(engines: Vec<TournamentEngine>, format: Format) -> Tournament {
+        Tournament {
+            engines,
+            format,
+            openings: vec![Position::initial()],
+            rounds: 1,
+            time_control: TimeControl::Unlimited,
+            move_limit: MOVE_LIMIT,
+            adjudication: None,
+            sprt: None,
+            event: String::from("Tournament"),
+            games: vec![],
+        }
+    }
+
+    pub fn set_openings(&mut self, openings: Vec<Position>) {
+        self.openings = openings;
+    }
+
+    // every round plays each opening twice for each pairing, once with either colour
+    pub fn set_rounds(&mut self, rounds: usize) {
+        self.rounds = rounds;
+    }
+
+    pub fn set_time_control(&mut self, time_control: &TimeControl) {
+        self.time_control = *time_control;
+    }
+
+    pub fn set_move_limit(&mut self, move_limit: usize) {
+        self.move_limit = move_limit;
+    }
+
+    pub fn set_adjudication(&mut self, adjudication: ScoreAdjudication) {
+        self.adjudication = Some(adjudication);
+    }
+
+    // stops early once the first engine is proven better or not better than its opponents
+    pub fn set_sprt(&mut self, sprt: Sprt) {
+        self.sprt = Some(sprt);
+    }
+
+    pub fn set_event(&mut self, event: &str) {
+        self.event = event.to_owned();
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.engines
+            .iter()
+            .map(|engine| engine.display_name().to_owned())
+            .collect()
+    }
+
+    pub fn games(&self) -> &[GameRecord] {
+        &self.games
+    }
+
+    pub fn pairings(&self) -> Vec<(usize, usize)> {
+        let count = self.engines.len();
+        match self.format {
+            Format::RoundRobin => (0..count)
+                .flat_map(|one| (one + 1..count).map(move |two| (one, two)))
+                .collect(),
+            Format::Gauntlet => (1..count).map(|two| (0, two)).collect(),
+        }
+    }
+
+    pub fn run(&mut self, on_game: &mut dyn FnMut(&Tournament, &GameRecord)) -> SprtResult {
+        for engine in &mut self.engines {
+            engine.set_time_control(&self.time_control);
+        }
+        let pairings = self.pairings();
+        let openings = self.openings.clone();
+        for round in 1..=self.rounds {
+            for opening in &openings {
+                for &(one, two) in &pairings {
+                    for &(white, black) in &[(one, two), (two, one)] {
+                        let record = self.play(round, white, black, opening);
+                        on_game(self, &record);
+                        self.games.push(record);
+                    }
+                    if let Some(sprt) = self.sprt {
+                        let result = sprt.test(&self.score(0, None));
+                        if result != SprtResult::Continue {
+                            return result;
+                        }
+                    }
+                }
+            }
+        }
+        SprtResult::Continue
+    }
+
+    fn play(&mut self, round: usize, white: usize, black: usize, opening: &Position) -> GameRecord {
+        let (white_engine, black_engine) = pair(&mut self.engines, white, black);
+        let mut played = Game::create(opening);
+        played.set_move_limit(self.move_limit);
+        if let Some(adjudication) = self.adjudication {
+            played.set_adjudication(adjudication);
+        }
+        let result = play_game(&mut **white_engine, &mut **black_engine, &mut played);
+
+        let mut game = PdnGame::create();
+        game.set_tag(EVENT, &self.event);
+        game.set_tag(ROUND, &round.to_string());
+        game.set_tag(WHITE, white_engine.display_name());
+        game.set_tag(BLACK, black_engine.display_name());
+        game.set_tag(RESULT, result.outcome.pdn());
+        game.set_initial_position(opening);
+        for mv in played.moves() {
+            game.push(*mv);
+        }
+        game.comment = Some(result.rule.description().to_owned());
+        GameRecord {
+            round,
+            white,
+            black,
+            outcome: result.outcome,
+            rule: result.rule,
+            game,
+        }
+    }
+
+    // of an engine against one opponent, or against all
+    pub fn score(&self, engine: usize, opponent: Option<usize>) -> Score {
+        let mut score = Score::create();
+        for record in &self.games {
+            let against = if record.white == engine {
+                record.black
+            } else {
+                record.white
+            };
+            if opponent.is_none_or(|opponent| opponent == against) {
+                score.add(&record.score(engine));
+            }
+        }
+        score
+    }
+
+    pub fn crosstable(&self) -> String {
+        let names = self.names();
+        let count = names.len();
+        let width = names
+            .iter()
+            .map(|name| name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(6);
+        let mut table = format!("{:>3}  {:width$}", "#", "Engine", width = width);
+        for column in 1..=count {
+            table.push_str(&format!(" {:>5}", column));
+        }
+        table.push_str("  Points  Games\n");
+        for (row, name) in names.iter().enumerate() {
+            table.push_str(&format!("{:>3}  {:width$}", row + 1, name, width = width));
+            for column in 0..count {
+                let score = self.score(row, Some(column));
+                if row == column {
+                    table.push_str(&format!(" {:>5}", "."));
+                } else if score.games() == 0 {
+                    table.push_str(&format!(" {:>5}", "-"));
+                } else {
+                    table.push_str(&format!(" {:>5}", score.points()));
+                }
+            }
+            let score = self.score(row, None);
+            table.push_str(&format!("  {:>6}  {:>5}\n", score.points(), score.games()));
+        }
+        table
+    }
+
+    // wins, draws, losses and the Elo difference of the first engine against each opponent
+    pub fn summary(&self) -> String {
+        let names = self.names();
+        let mut summary = String::new();
+        for opponent in 1..names.len() {
+            let score = self.score(0, Some(opponent));
+            summary.push_str(&format!(
+                "{} - {}: +{} ={} -{}",
+                names[0], names[opponent], score.wins, score.draws, score.losses
+            ));
+            if let Some((difference, error)) = score.elo() {
+                summary.push_str(&format!(", Elo {:+.1} +/- {:.1}", difference, error));
+            }
+            summary.push('\n');
+        }
+        if let Some(sprt) = self.sprt {
+            let (lower, upper) = sprt.bounds();
+            summary.push_str(&format!(
+                "SPRT [{}, {}]: LLR {:.2} ({:.2}, {:.2})\n",
+                sprt.elo0,
+                sprt.elo1,
+                sprt.llr(&self.score(0, None)),
+                lower,
+                upper
+            ));
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::generator::Generator;
+    use crate::engine::randaap::RandAap;
+    use crate::pdn::reader::parse_game;
+    use crate::pdn::writer::write_game;
+
+    fn engines(count: usize) -> Vec<TournamentEngine> {
+        (0..count)
+            .map(|_| Box::new(RandAap::create(100)) as TournamentEngine)
+            .collect()
+    }
+
+    #[test]
+    fn openings() {
+        let openings = parse_openings(
+            "// starting positions\n\nw kcekaeb2b2/5rweirr //20449\nw kbeakk2b2/eh2ethehrr\n",
+        )
+        .unwrap();
+        assert_eq!(openings.len(), 2);
+        assert!(parse_openings("w nonsense").is_err());
+    }
+
+    #[test]
+    fn pairings() {
+        let tournament = Tournament::create(engines(4), Format::RoundRobin);
+        assert_eq!(tournament.pairings().len(), 6);
+        let tournament = Tournament::create(engines(4), Format::Gauntlet);
+        assert_eq!(tournament.pairings(), vec![(0, 1), (0, 2), (0, 3)]);
+    }
+
+    #[test]
+    fn round_robin() {
+        let generator = Generator::create();
+        let mut tournament = Tournament::create(engines(3), Format::RoundRobin);
+        tournament.set_openings(parse_openings("w kcekaeb2b2/5rweirr\nw kkcece3l4wrrter").unwrap());
+        tournament.set_move_limit(40);
+        let mut played = 0;
+        let result = tournament.run(&mut |_, record| {
+            played += 1;
+            let pdn = write_game(&generator, &record.game).unwrap();
+            let game = parse_game(&generator, &pdn).unwrap();
+            assert_eq!(game.result(), record.outcome.pdn());
+            assert_eq!(game.moves.len(), record.game.moves.len());
+        });
+        assert_eq!(result, SprtResult::Continue);
+        assert_eq!(played, 12);
+
+        let games = tournament.games();
+        assert_eq!((games[0].white, games[0].black), (0, 1));
+        assert_eq!((games[1].white, games[1].black), (1, 0));
+        let total: usize = (0..3)
+            .map(|engine| tournament.score(engine, None).points())
+            .sum();
+        assert_eq!(total, 24);
+        assert_eq!(tournament.crosstable().lines().count(), 4);
+    }
+
+    #[test]
+    fn sprt_stops() {
+        // white has no pieces, so both engines win once per pair
+        let lost = Position::parse(&format!("wb{}", "e".repeat(49))).unwrap();
+        let mut tournament = Tournament::create(engines(2), Format::Gauntlet);
+        tournament.set_openings(vec![lost]);
+        tournament.set_rounds(1_000);
+        tournament.set_sprt(Sprt::create(0.0, 200.0));
+        let result = tournament.run(&mut |_, _| ());
+        assert_eq!(result, SprtResult::AcceptH0);
+        assert!(tournament.games().len() < 100);
+        assert!(tournament.summary().contains("+"));
+    }
+}
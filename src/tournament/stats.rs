@@ -0,0 +1,200 @@
+// 95% confidence
+const Z: f64 = 1.96;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Score {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl Score {
+    pub fn create() -> Score {
+        Score::default()
+    }
+
+    pub fn add(&mut self, other: &Score) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    // two points for a win, one for a draw
+    pub fn points(&self) -> usize {
+        2 * self.wins + self.draws
+    }
+
+    pub fn ratio(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+    }
+
+    // of a single game result scored 1, 1/2 or 0
+    fn variance(&self) -> f64 {
+        let ratio = self.ratio();
+        let games = self.games() as f64;
+        (self.wins as f64 * (1.0 - ratio).powi(2)
+            + self.draws as f64 * (0.5 - ratio).powi(2)
+            + self.losses as f64 * ratio.powi(2))
+            / games
+    }
+
+    // the difference with its 95% error margin, a sweep counts as if half a game went the other way
+    pub fn elo(&self) -> Option<(f64, f64)> {
+        if self.games() == 0 {
+            return None;
+        }
+        let edge = 0.5 / self.games() as f64;
+        let clamp = |ratio: f64| ratio.max(edge).min(1.0 - edge);
+        let ratio = self.ratio();
+        let margin = Z * (self.variance() / self.games() as f64).sqrt();
+        let low = elo_difference(clamp(ratio - margin));
+        let high = elo_difference(clamp(ratio + margin));
+        Some((elo_difference(clamp(ratio)), (high - low) / 2.0))
+    }
+}
+
+pub fn elo_difference(ratio: f64) -> f64 {
+    -400.0 * (1.0 / ratio - 1.0).log10()
+}
+
+pub fn expected_ratio(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SprtResult {
+    Continue,
+    // the patch is no better than elo0
+    AcceptH0,
+    // the patch gains at least elo1
+    AcceptH1,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Sprt {
+    pub fn create(elo0: f64, elo1: f64) -> Sprt {
+        Sprt {
+            elo0,
+            elo1,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    // log-likelihood ratio in the normal approximation
+    pub fn llr(&self, score: &Score) -> f64 {
+        if score.games() == 0 {
+            return 0.0;
+        }
+        let s0 = expected_ratio(self.elo0);
+        let s1 = expected_ratio(self.elo1);
+        let games = score.games() as f64;
+        // equal results have no variance, so count at least that of one decisive game under elo0
+        let variance = score.variance().max(s0 * (1.0 - s0) / games);
+        (s1 - s0) * (2.0 * score.ratio() - s0 - s1) * games / (2.0 * variance)
+    }
+
+    pub fn test(&self, score: &Score) -> SprtResult {
+        let llr = self.llr(score);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtResult::AcceptH1
+        } else if llr <= lower {
+            SprtResult::AcceptH0
+        } else {
+            SprtResult::Continue
+        }
+    }
+}
+
+#[test]
+fn elo() {
+    assert!(Score::create().elo().is_none());
+    let even = Score {
+        wins: 10,
+        draws: 20,
+        losses: 10,
+    };
+    let (difference, error) = even.elo().unwrap();
+    assert!(difference.abs() < 1e-9);
+    assert!(error > 0.0 && error < 100.0);
+
+    let better = Score {
+        wins: 60,
+        draws: 30,
+        losses: 10,
+    };
+    let (difference, _) = better.elo().unwrap();
+    assert!((difference - 190.8).abs() < 0.1);
+    assert_eq!(better.points(), 150);
+    assert!((expected_ratio(difference) - 0.75).abs() < 1e-9);
+
+    let sweep = Score {
+        wins: 10,
+        draws: 0,
+        losses: 0,
+    };
+    let (difference, error) = sweep.elo().unwrap();
+    assert!((difference - 511.5).abs() < 0.1);
+    assert!(error.is_finite());
+}
+
+#[test]
+fn sprt() {
+    let sprt = Sprt::create(0.0, 10.0);
+    let (lower, upper) = sprt.bounds();
+    assert!((lower + 2.944).abs() < 0.001);
+    assert!((upper - 2.944).abs() < 0.001);
+    assert_eq!(sprt.test(&Score::create()), SprtResult::Continue);
+    let winning = Score {
+        wins: 700,
+        draws: 1000,
+        losses: 500,
+    };
+    assert_eq!(sprt.test(&winning), SprtResult::AcceptH1);
+    let losing = Score {
+        wins: 500,
+        draws: 1000,
+        losses: 700,
+    };
+    assert_eq!(sprt.test(&losing), SprtResult::AcceptH0);
+    let close = Score {
+        wins: 11,
+        draws: 20,
+        losses: 10,
+    };
+    assert_eq!(sprt.test(&close), SprtResult::Continue);
+
+    // runs of equal results still decide
+    let sprt = Sprt::create(0.0, 200.0);
+    for (wins, draws, losses, expected) in [
+        (10, 0, 0, SprtResult::AcceptH1),
+        (0, 0, 10, SprtResult::AcceptH0),
+        (0, 10, 0, SprtResult::AcceptH0),
+    ] {
+        let score = Score {
+            wins,
+            draws,
+            losses,
+        };
+        assert_eq!(sprt.test(&score), expected);
+    }
+}
@@ -0,0 +1,261 @@
+use std::io::BufRead;
+
+use crate::algorithm::judge::{Eval, Judge};
+use crate::board::game::Outcome;
+use crate::board::piece::Color;
+use crate::board::position::Position;
+use crate::engine::sherlock::{SherlockJudge, SherlockWeights};
+use crate::engine::slonenok::{SlonenokJudge, SlonenokWeights};
+use crate::engine::weights::Weights;
+use crate::pdn::game::PdnGame;
+
+// the first step of the local search, halved whenever a pass finds nothing better
+const STEP: Eval = 8;
+const SCALE_ROUNDS: usize = 40;
+const MAX_SCALE: f64 = 4.0;
+
+// a judge whose evaluation can be tuned
+pub trait Tunable: Judge {
+    type Weights: Weights;
+    fn weights(&self) -> &Self::Weights;
+    fn set_weights(&mut self, weights: Self::Weights);
+}
+
+impl Tunable for SherlockJudge {
+    type Weights = SherlockWeights;
+    fn weights(&self) -> &SherlockWeights {
+        SherlockJudge::weights(self)
+    }
+    fn set_weights(&mut self, weights: SherlockWeights) {
+        SherlockJudge::set_weights(self, weights)
+    }
+}
+
+impl Tunable for SlonenokJudge {
+    type Weights = SlonenokWeights;
+    fn weights(&self) -> &SlonenokWeights {
+        SlonenokJudge::weights(self)
+    }
+    fn set_weights(&mut self, weights: SlonenokWeights) {
+        SlonenokJudge::set_weights(self, weights)
+    }
+}
+
+// a position with the result of its game, 1 when white won, 0 when black did
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Sample {
+    pub position: Position,
+    pub result: f64,
+}
+
+fn score(outcome: Outcome) -> f64 {
+    match outcome {
+        Outcome::WhiteWins => 1.0,
+        Outcome::Draw => 0.5,
+        Outcome::BlackWins => 0.0,
+    }
+}
+
+// one position per line: the pdn fen and the result, as in "W:W31,32:B19 2-0"
+pub fn read_samples<R: BufRead>(reader: R) -> Result<Vec<Sample>, String> {
+    let mut samples = vec![];
+    for line in reader.lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let fen = words.next().unwrap_or("");
+        let result = words.next().unwrap_or("");
+        let outcome =
+            Outcome::parse_pdn(result).ok_or_else(|| format!("Invalid result in {}", line))?;
+        samples.push(Sample {
+            position: Position::parse_pdn(fen)?,
+            result: score(outcome),
+        });
+    }
+    Ok(samples)
+}
+
+// the positions of a game after the opening plies, none when it has no result
+pub fn game_samples(game: &PdnGame, skip: usize) -> Result<Vec<Sample>, String> {
+    let outcome = match Outcome::parse_pdn(game.result()) {
+        Some(outcome) => outcome,
+        None => return Ok(vec![]),
+    };
+    Ok(game
+        .positions()?
+        .into_iter()
+        .skip(skip)
+        .map(|position| Sample {
+            position,
+            result: score(outcome),
+        })
+        .collect())
+}
+
+// plays out the captures, which are forced, and returns the evaluation and the position it was made in
+pub fn quiescence<J: Judge>(judge: &J, position: &Position) -> (Eval, Position) {
+    let moves = judge.moves(position, 0);
+    if moves.first().is_none_or(|mv| mv.num_taken() == 0) {
+        return (judge.evaluate(position), *position);
+    }
+    moves
+        .iter()
+        .map(|mv| {
+            let (evaluation, leaf) = quiescence(judge, &position.go(mv));
+            (-evaluation, leaf)
+        })
+        .max_by_key(|&(evaluation, _)| evaluation)
+        .unwrap()
+}
+
+// replaces the positions by their quiet ones, dropping those where the game is over
+pub fn resolve<J: Judge>(judge: &J, samples: &[Sample]) -> Vec<Sample> {
+    samples
+        .iter()
+        .map(|sample| Sample {
+            position: quiescence(judge, &sample.position).1,
+            result: sample.result,
+        })
+        .filter(|sample| !judge.moves(&sample.position, 0).is_empty())
+        .collect()
+}
+
+// the expected score for white, the scale turns evaluations into odds
+pub fn win_probability(evaluation: Eval, scale: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-scale * evaluation as f64 / 400.0))
+}
+
+fn white_evaluation<J: Judge>(judge: &J, position: &Position) -> Eval {
+    match position.side_to_move() {
+        Color::White => judge.evaluate(position),
+        Color::Black => -judge.evaluate(position),
+    }
+}
+
+// the mean squared distance between the results and the predicted scores
+pub fn loss<J: Judge>(judge: &J, samples: &[Sample], scale: f64) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples
+        .iter()
+        .map(|sample| {
+            let error =
+                sample.result - win_probability(white_evaluation(judge, &sample.position), scale);
+            error * error
+        })
+        .sum::<f64>()
+        / samples.len() as f64
+}
+
+// the scale that fits the current weights best, by golden section search
+pub fn fit_scale<J: Judge>(judge: &J, samples: &[Sample]) -> f64 {
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut low, mut high) = (0.0, MAX_SCALE);
+    for _ in 0..SCALE_ROUNDS {
+        let left = high - ratio * (high - low);
+        let right = low + ratio * (high - low);
+        if loss(judge, samples, left) < loss(judge, samples, right) {
+            high = right;
+        } else {
+            low = left;
+        }
+    }
+    (low + high) / 2.0
+}
+
+// moves one weight at a time while the loss goes down, and reports the weights after every pass
+pub fn tune<J, F>(
+    judge: &mut J,
+    samples: &[Sample],
+    scale: f64,
+    passes: usize,
+    mut report: F,
+) -> f64
+where
+    J: Tunable,
+    F: FnMut(usize, f64, &J::Weights),
+{
+    let mut values = judge.weights().values();
+    let mut best = loss(judge, samples, scale);
+    let mut step = STEP;
+    let mut pass = 0;
+    while pass < passes && step > 0 {
+        let mut improved = false;
+        for i in 0..values.len() {
+            for &delta in &[step, -step] {
+                let mut tried = values.clone();
+                tried[i] = tried[i].saturating_add(delta);
+                let mut weights = judge.weights().clone();
+                weights.set_values(&tried);
+                judge.set_weights(weights);
+                let tried_loss = loss(judge, samples, scale);
+                if tried_loss < best {
+                    best = tried_loss;
+                    values = tried;
+                    improved = true;
+                    break;
+                }
+            }
+            let mut weights = judge.weights().clone();
+            weights.set_values(&values);
+            judge.set_weights(weights);
+        }
+        pass += 1;
+        report(pass, best, judge.weights());
+        if !improved {
+            step /= 2;
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::generator::Generator;
+
+    #[test]
+    fn quiet_positions() {
+        let judge = SherlockJudge::create(Generator::create(), SherlockWeights::default());
+        let position = Position::parse_pdn("W:W28:B22,23").unwrap();
+        let (_, leaf) = quiescence(&judge, &position);
+        assert_eq!(leaf.count_pieces(), 2);
+        assert_eq!(leaf.side_to_move(), Color::Black);
+
+        // the game is over after the capture
+        let samples = read_samples("W:W28:B22 2-0\nW:W31,32:B19 2-0\n".as_bytes()).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[1].result, 1.0);
+        assert_eq!(resolve(&judge, &samples).len(), 1);
+        assert!(read_samples("W:W31:B19 3-0".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn tuning() {
+        let mut judge = SherlockJudge::create(Generator::create(), SherlockWeights::default());
+        let samples = read_samples(
+            "W:W31,32,33,34:B16,17,18 2-0\n\
+             B:W36,37,38:B11,12,13,14 0-2\n\
+             W:W40,41,42:B6,7,8 1-1\n\
+             B:W31,32,33,34,35:B11,12,13 2-0\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        let mut weights = *judge.weights();
+        weights.man = 100;
+        judge.set_weights(weights);
+        let scale = 1.0;
+        let before = loss(&judge, &samples, scale);
+        let mut passes = 0;
+        let after = tune(&mut judge, &samples, scale, 3, |_, _, _| passes += 1);
+        assert_eq!(passes, 3);
+        assert!(after < before);
+        assert!(judge.weights().man > 100);
+        assert_eq!(loss(&judge, &samples, scale), after);
+        assert!(fit_scale(&judge, &samples) > 0.0);
+    }
+}
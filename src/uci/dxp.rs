@@ -0,0 +1,881 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use crate::algorithm::meta::Meta;
+use crate::board::generator::Generator;
+use crate::board::mv::Move;
+use crate::board::piece::{Color, BLACK_KING, BLACK_MAN, EMPTY, WHITE_KING, WHITE_MAN};
+use crate::board::position::{Field, Position};
+use crate::engine::time::TimeControl;
+use crate::engine::{Engine, EngineResult};
+
+pub const VERSION: u8 = 1;
+const NAME_LENGTH: usize = 32;
+const TERMINATOR: u8 = 0;
+
+pub const ACCEPTED: u8 = 0;
+pub const REJECTED_VERSION: u8 = 1;
+pub const REJECTED_OTHER: u8 = 3;
+pub const BACK_ACCEPTED: u8 = 0;
+pub const BACK_UNSUPPORTED: u8 = 1;
+pub const BACK_REJECTED: u8 = 2;
+
+pub const END_UNKNOWN: u8 = 0;
+pub const END_LOSS: u8 = 1;
+pub const END_DRAW: u8 = 2;
+pub const END_WIN: u8 = 3;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Message {
+    Chat(String),
+    GameRequest {
+        version: u8,
+        name: String,
+        follower: Color,
+        minutes: u16,
+        moves: u16,
+        position: Option<Position>,
+    },
+    GameAccept {
+        name: String,
+        code: u8,
+    },
+    Move {
+        seconds: u16,
+        from: Field,
+        to: Field,
+        taken: Vec<Field>,
+    },
+    GameEnd {
+        reason: u8,
+        stop: bool,
+    },
+    BackRequest {
+        number: u16,
+        side: Color,
+    },
+    BackAccept {
+        code: u8,
+    },
+}
+
+fn color_char(color: Color) -> char {
+    match color {
+        Color::White => 'W',
+        Color::Black => 'Z',
+    }
+}
+
+fn parse_color(c: char) -> Result<Color, String> {
+    match c {
+        'W' => Ok(Color::White),
+        'Z' => Ok(Color::Black),
+        _ => Err(format!("Invalid color {}", c)),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(
+    text: &str,
+    start: usize,
+    length: usize,
+) -> Result<T, String> {
+    text.get(start..start + length)
+        .and_then(|digits| digits.trim().parse().ok())
+        .ok_or_else(|| format!("Invalid number at {} in {}", start, text))
+}
+
+fn char_at(text: &str, index: usize) -> Result<char, String> {
+    text.chars()
+        .nth(index)
+        .ok_or_else(|| format!("Message too short: {}", text))
+}
+
+fn parse_field(text: &str, start: usize) -> Result<Field, String> {
+    match parse_number::<Field>(text, start, 2)? {
+        field @ 1..=50 => Ok(field - 1),
+        field => Err(format!("Invalid field {}", field)),
+    }
+}
+
+fn write_position(position: &Position) -> String {
+    let mut text = String::new();
+    text.push(color_char(position.side_to_move()));
+    for field in 0..50 {
+        text.push(match position.piece_at(field) {
+            WHITE_MAN => 'w',
+            BLACK_MAN => 'z',
+            WHITE_KING => 'W',
+            BLACK_KING => 'Z',
+            _ => 'e',
+        });
+    }
+    text
+}
+
+fn parse_position(text: &str) -> Result<Position, String> {
+    let mut chars = text.chars();
+    let mut position = Position::create();
+    if parse_color(chars.next().unwrap_or(' '))? == Color::Black {
+        position = position.toggle_side();
+    }
+    for field in 0..50 {
+        let piece = match chars.next() {
+            Some('e') => EMPTY,
+            Some('w') => WHITE_MAN,
+            Some('z') => BLACK_MAN,
+            Some('W') => WHITE_KING,
+            Some('Z') => BLACK_KING,
+            _ => return Err(format!("Invalid position {}", text)),
+        };
+        position = position.put_piece(field, piece);
+    }
+    Ok(position)
+}
+
+impl Message {
+    pub fn from_move(seconds: u16, mv: &Move) -> Message {
+        Message::Move {
+            seconds,
+            from: mv.from(),
+            to: mv.to(),
+            taken: mv.taken().to_vec(),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        match self {
+            Message::Chat(text) => format!("C{}", text),
+            Message::GameRequest {
+                version,
+                name,
+                follower,
+                minutes,
+                moves,
+                position,
+            } => {
+                let start = match position {
+                    Some(position) => format!("B{}", write_position(position)),
+                    None => String::from("A"),
+                };
+                format!(
+                    "R{:02}{:<width$.width$}{}{:03}{:03}{}",
+                    version,
+                    name,
+                    color_char(*follower),
+                    minutes,
+                    moves,
+                    start,
+                    width = NAME_LENGTH
+                )
+            }
+            Message::GameAccept { name, code } => {
+                format!("A{:<width$.width$}{}", name, code, width = NAME_LENGTH)
+            }
+            Message::Move {
+                seconds,
+                from,
+                to,
+                taken,
+            } => {
+                let mut text = format!(
+                    "M{:04}{:02}{:02}{:02}",
+                    seconds,
+                    from + 1,
+                    to + 1,
+                    taken.len()
+                );
+                for field in taken {
+                    text.push_str(&format!("{:02}", field + 1));
+                }
+                text
+            }
+            Message::GameEnd { reason, stop } => {
+                format!("E{}{}", reason, if *stop { 1 } else { 0 })
+            }
+            Message::BackRequest { number, side } => format!("B{:03}{}", number, color_char(*side)),
+            Message::BackAccept { code } => format!("K{}", code),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Message, String> {
+        match text.chars().next() {
+            Some('C') => Ok(Message::Chat(text[1..].to_owned())),
+            Some('R') => Ok(Message::GameRequest {
+                version: parse_number(text, 1, 2)?,
+                name: text
+                    .get(3..3 + NAME_LENGTH)
+                    .ok_or_else(|| format!("Message too short: {}", text))?
+                    .trim_end()
+                    .to_owned(),
+                follower: parse_color(char_at(text, 35)?)?,
+                minutes: parse_number(text, 36, 3)?,
+                moves: parse_number(text, 39, 3)?,
+                position: match char_at(text, 42)? {
+                    'A' => None,
+                    'B' => Some(parse_position(text.get(43..).unwrap_or_default())?),
+                    c => return Err(format!("Invalid start position {}", c)),
+                },
+            }),
+            Some('A') => Ok(Message::GameAccept {
+                name: text
+                    .get(1..1 + NAME_LENGTH)
+                    .ok_or_else(|| format!("Message too short: {}", text))?
+                    .trim_end()
+                    .to_owned(),
+                code: parse_number(text, 1 + NAME_LENGTH, 1)?,
+            }),
+            Some('M') => {
+                let count: usize = parse_number(text, 9, 2)?;
+                Ok(Message::Move {
+                    seconds: parse_number(text, 1, 4)?,
+                    from: parse_field(text, 5)?,
+                    to: parse_field(text, 7)?,
+                    taken: (0..count)
+                        .map(|i| parse_field(text, 11 + 2 * i))
+                        .collect::<Result<_, _>>()?,
+                })
+            }
+            Some('E') => Ok(Message::GameEnd {
+                reason: parse_number(text, 1, 1)?,
+                stop: parse_number::<u8>(text, 2, 1)? == 1,
+            }),
+            Some('B') => Ok(Message::BackRequest {
+                number: parse_number(text, 1, 3)?,
+                side: parse_color(char_at(text, 4)?)?,
+            }),
+            Some('K') => Ok(Message::BackAccept {
+                code: parse_number(text, 1, 1)?,
+            }),
+            _ => Err(format!("Unknown message {}", text)),
+        }
+    }
+}
+
+// captures are matched on their set of taken pieces, which is all DXP sends
+fn find_move(
+    generator: &Generator,
+    position: &Position,
+    from: Field,
+    to: Field,
+    taken: &[Field],
+) -> Result<Move, String> {
+    let mut taken = taken.to_vec();
+    taken.sort_unstable();
+    generator
+        .legal_moves(position)
+        .into_iter()
+        .find(|mv| {
+            let mut mv_taken = mv.taken().to_vec();
+            mv_taken.sort_unstable();
+            mv.from() == from && mv.to() == to && mv_taken == taken
+        })
+        .ok_or_else(|| format!("Illegal move {}-{}", from + 1, to + 1))
+}
+
+fn mirror(reason: u8) -> u8 {
+    match reason {
+        END_LOSS => END_WIN,
+        END_WIN => END_LOSS,
+        reason => reason,
+    }
+}
+
+pub struct DxpConnection {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>,
+}
+
+impl DxpConnection {
+    pub fn create(stream: TcpStream) -> Result<DxpConnection, String> {
+        let reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+        Ok(DxpConnection { stream, reader })
+    }
+
+    pub fn connect(address: &str) -> Result<DxpConnection, String> {
+        DxpConnection::create(TcpStream::connect(address).map_err(|e| e.to_string())?)
+    }
+
+    pub fn set_timeout(&self, timeout: Option<Duration>) -> Result<(), String> {
+        self.stream
+            .set_read_timeout(timeout)
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn send(&mut self, message: &Message) -> Result<(), String> {
+        let mut bytes = message.encode().into_bytes();
+        bytes.push(TERMINATOR);
+        self.stream.write_all(&bytes).map_err(|e| e.to_string())
+    }
+
+    pub fn receive(&mut self) -> Result<Message, String> {
+        let mut bytes = vec![];
+        let read = self
+            .reader
+            .read_until(TERMINATOR, &mut bytes)
+            .map_err(|e| e.to_string())?;
+        if read == 0 || bytes.last() != Some(&TERMINATOR) {
+            return Err(String::from("Connection closed"));
+        }
+        bytes.pop();
+        Message::parse(&String::from_utf8_lossy(&bytes))
+    }
+}
+
+struct Game {
+    positions: Vec<Position>,
+    numbers: Vec<u16>,
+}
+
+impl Game {
+    fn create(position: Position) -> Game {
+        Game {
+            positions: vec![position],
+            numbers: vec![1],
+        }
+    }
+
+    fn position(&self) -> Position {
+        *self.positions.last().unwrap()
+    }
+
+    fn push(&mut self, mv: &Move) {
+        let position = self.position();
+        let number = *self.numbers.last().unwrap();
+        self.numbers
+            .push(if position.side_to_move() == Color::Black {
+                number + 1
+            } else {
+                number
+            });
+        self.positions.push(position.go(mv));
+    }
+
+    fn back(&mut self, number: u16, side: Color) -> bool {
+        match (0..self.positions.len())
+            .find(|&i| self.numbers[i] == number && self.positions[i].side_to_move() == side)
+        {
+            Some(i) => {
+                self.positions.truncate(i + 1);
+                self.numbers.truncate(i + 1);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// plays one game as follower, returning whether the initiator wants to stop
+fn play_game(
+    connection: &mut DxpConnection,
+    engine: &mut dyn Engine<Item = EngineResult>,
+    generator: &Generator,
+    mut game: Game,
+    own: Color,
+    minutes: u16,
+    moves: u16,
+) -> Result<bool, String> {
+    let mut remaining = Duration::from_secs(60 * u64::from(minutes));
+    let mut played = 0;
+    loop {
+        let position = game.position();
+        if generator.legal_moves(&position).is_empty() {
+            let reason = if position.side_to_move() == own {
+                END_LOSS
+            } else {
+                END_WIN
+            };
+            connection.send(&Message::GameEnd {
+                reason,
+                stop: false,
+            })?;
+            loop {
+                if let Message::GameEnd { stop, .. } = connection.receive()? {
+                    return Ok(stop);
+                }
+            }
+        }
+
+        if position.side_to_move() == own {
+            let start = Instant::now();
+            engine.set_time_control(&if minutes == 0 {
+                TimeControl::Unlimited
+            } else {
+                TimeControl::Clock {
+                    remaining,
+                    increment: Duration::ZERO,
+                    moves_to_go: moves
+                        .checked_sub(played)
+                        .filter(|&left| left > 0)
+                        .map(u32::from),
+                }
+            });
+            engine.set_position(&position);
+            let mv = engine
+                .last()
+                .ok_or_else(|| String::from("Engine found no move"))?
+                .mv;
+            let used = start.elapsed();
+            remaining = remaining.saturating_sub(used);
+            played += 1;
+            connection.send(&Message::from_move(used.as_secs() as u16, &mv))?;
+            game.push(&mv);
+            continue;
+        }
+
+        match connection.receive()? {
+            Message::Move {
+                from, to, taken, ..
+            } => {
+                let mv = find_move(generator, &position, from, to, &taken)?;
+                game.push(&mv);
+            }
+            Message::BackRequest { number, side } => {
+                let code = if game.back(number, side) {
+                    BACK_ACCEPTED
+                } else {
+                    BACK_REJECTED
+                };
+                connection.send(&Message::BackAccept { code })?;
+            }
+            Message::GameEnd { reason, stop } => {
+                connection.send(&Message::GameEnd {
+                    reason: mirror(reason),
+                    stop,
+                })?;
+                return Ok(stop);
+            }
+            Message::Chat(_) => (),
+            message => return Err(format!("Unexpected message {}", message.encode())),
+        }
+    }
+}
+
+// answers game requests on the connection with the given engine until the peer stops
+pub fn serve(
+    connection: &mut DxpConnection,
+    engine: &mut dyn Engine<Item = EngineResult>,
+    name: &str,
+) -> Result<(), String> {
+    let generator = Generator::create();
+    loop {
+        match connection.receive()? {
+            Message::GameRequest {
+                version,
+                follower,
+                minutes,
+                moves,
+                position,
+                ..
+            } => {
+                if version != VERSION {
+                    connection.send(&Message::GameAccept {
+                        name: name.to_owned(),
+                        code: REJECTED_VERSION,
+                    })?;
+                    continue;
+                }
+                connection.send(&Message::GameAccept {
+                    name: name.to_owned(),
+                    code: ACCEPTED,
+                })?;
+                let game = Game::create(position.unwrap_or_else(Position::initial));
+                if play_game(
+                    connection, engine, &generator, game, follower, minutes, moves,
+                )? {
+                    return Ok(());
+                }
+            }
+            Message::GameEnd { reason, stop } => {
+                connection.send(&Message::GameEnd {
+                    reason: mirror(reason),
+                    stop,
+                })?;
+                if stop {
+                    return Ok(());
+                }
+            }
+            Message::BackRequest { .. } => connection.send(&Message::BackAccept {
+                code: BACK_REJECTED,
+            })?,
+            _ => (),
+        }
+    }
+}
+
+// a remote engine, which is asked for a new game whenever the position does not follow from the last move
+pub struct DxpClient {
+    connection: DxpConnection,
+    generator: Generator,
+    name: String,
+    peer: String,
+    minutes: u16,
+    moves: u16,
+    game: Option<Position>,
+    position: Option<Position>,
+    started: Instant,
+    error: Option<String>,
+}
+
+impl DxpClient {
+    pub fn connect(
+        address: &str,
+        name: &str,
+        minutes: u16,
+        moves: u16,
+    ) -> Result<DxpClient, String> {
+        Ok(DxpClient {
+            connection: DxpConnection::connect(address)?,
+            generator: Generator::create(),
+            name: name.to_owned(),
+            peer: String::from("DXP"),
+            minutes,
+            moves,
+            game: None,
+            position: None,
+            started: Instant::now(),
+            error: None,
+        })
+    }
+
+    // why the last move did not come
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    fn end_game(&mut self, stop: bool) -> Result<(), String> {
+        self.game = None;
+        self.connection.send(&Message::GameEnd {
+            reason: END_UNKNOWN,
+            stop,
+        })?;
+        loop {
+            if let Message::GameEnd { .. } = self.connection.receive()? {
+                return Ok(());
+            }
+        }
+    }
+
+    fn request_game(&mut self, position: &Position) -> Result<(), String> {
+        self.connection.send(&Message::GameRequest {
+            version: VERSION,
+            name: self.name.clone(),
+            follower: position.side_to_move(),
+            minutes: self.minutes,
+            moves: self.moves,
+            position: if *position == Position::initial() {
+                None
+            } else {
+                Some(*position)
+            },
+        })?;
+        loop {
+            match self.connection.receive()? {
+                Message::GameAccept { name, code } => {
+                    if code != ACCEPTED {
+                        return Err(format!("Game request rejected with code {}", code));
+                    }
+                    self.peer = name;
+                    return Ok(());
+                }
+                Message::Chat(_) => (),
+                message => return Err(format!("Unexpected message {}", message.encode())),
+            }
+        }
+    }
+
+    fn play(&mut self, position: &Position) -> Result<EngineResult, String> {
+        let own = self.game.and_then(|game| {
+            self.generator
+                .legal_moves(&game)
+                .into_iter()
+                .find(|mv| game.go(mv) == *position)
+        });
+        match own {
+            Some(mv) => {
+                let seconds = self.started.elapsed().as_secs() as u16;
+                self.connection.send(&Message::from_move(seconds, &mv))?;
+            }
+            None => {
+                if self.game.is_some() {
+                    self.end_game(false)?;
+                }
+                self.request_game(position)?;
+            }
+        }
+
+        loop {
+            match self.connection.receive()? {
+                Message::Move {
+                    from, to, taken, ..
+                } => {
+                    let mv = find_move(&self.generator, position, from, to, &taken)?;
+                    self.game = Some(position.go(&mv));
+                    self.started = Instant::now();
+                    return Ok(EngineResult::create(mv, 0, Meta::create()));
+                }
+                Message::BackRequest { .. } => self.connection.send(&Message::BackAccept {
+                    code: BACK_UNSUPPORTED,
+                })?,
+                Message::GameEnd { reason, stop } => {
+                    self.game = None;
+                    self.connection.send(&Message::GameEnd {
+                        reason: mirror(reason),
+                        stop,
+                    })?;
+                    return Err(String::from("Game ended by peer"));
+                }
+                Message::Chat(_) => (),
+                message => return Err(format!("Unexpected message {}", message.encode())),
+            }
+        }
+    }
+}
+
+impl Iterator for DxpClient {
+    type Item = EngineResult;
+    fn next(&mut self) -> Option<EngineResult> {
+        let position = self.position.take()?;
+        match self.play(&position) {
+            Ok(result) => Some(result),
+            Err(error) => {
+                self.error = Some(error);
+                None
+            }
+        }
+    }
+}
+
+impl Engine for DxpClient {
+    fn display_name(&self) -> &str {
+        &self.peer
+    }
+    fn set_position(&mut self, position: &Position) {
+        self.position = Some(*position);
+        self.error = None;
+    }
+}
+
+impl Drop for DxpClient {
+    fn drop(&mut self) {
+        self.connection
+            .send(&Message::GameEnd {
+                reason: END_UNKNOWN,
+                stop: true,
+            })
+            .ok();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::engine::randaap::RandAap;
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn roundtrip(message: Message, text: &str) {
+        assert_eq!(message.encode(), text);
+        assert_eq!(Message::parse(text).unwrap(), message);
+    }
+
+    #[test]
+    fn messages() {
+        roundtrip(Message::Chat(String::from("hello")), "Chello");
+        roundtrip(
+            Message::GameRequest {
+                version: 1,
+                name: String::from("Sherlock"),
+                follower: Color::Black,
+                minutes: 30,
+                moves: 75,
+                position: None,
+            },
+            "R01Sherlock                        Z030075A",
+        );
+        roundtrip(
+            Message::GameAccept {
+                name: String::from("Slonenok"),
+                code: ACCEPTED,
+            },
+            "ASlonenok                        0",
+        );
+        roundtrip(
+            Message::from_move(12, &Move::take(27, 9, &[22, 13])),
+            "M00122810021423",
+        );
+        roundtrip(
+            Message::GameEnd {
+                reason: END_WIN,
+                stop: true,
+            },
+            "E31",
+        );
+        roundtrip(
+            Message::BackRequest {
+                number: 12,
+                side: Color::Black,
+            },
+            "B012Z",
+        );
+        roundtrip(
+            Message::BackAccept {
+                code: BACK_ACCEPTED,
+            },
+            "K0",
+        );
+
+        let position = Position::parse_pdn("B:W31,K40:B2,K12").unwrap();
+        let request = Message::GameRequest {
+            version: 1,
+            name: String::from("Sherlock"),
+            follower: Color::White,
+            minutes: 5,
+            moves: 0,
+            position: Some(position),
+        };
+        assert_eq!(Message::parse(&request.encode()).unwrap(), request);
+        let text = request.encode();
+        assert_eq!(&text[42..46], "BZez");
+        assert_eq!(&text[55..56], "Z");
+        assert_eq!(&text[74..75], "w");
+        assert_eq!(&text[83..84], "W");
+
+        assert!(Message::parse("M0012").is_err());
+        assert!(Message::parse("X").is_err());
+    }
+
+    fn server(engine: RandAap) -> (String, thread::JoinHandle<Result<(), String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let thread = thread::spawn(move || {
+            let mut engine = engine;
+            let (stream, _) = listener.accept().map_err(|e| e.to_string())?;
+            let mut connection = DxpConnection::create(stream)?;
+            serve(&mut connection, &mut engine, "RandAap")
+        });
+        (address, thread)
+    }
+
+    #[test]
+    fn loopback_game() {
+        let generator = Generator::create();
+        let (address, thread) = server(RandAap::create(1_000));
+        {
+            let mut client = DxpClient::connect(&address, "Tester", 0, 0).unwrap();
+            let mut position = Position::initial();
+            for _ in 0..3 {
+                client.set_position(&position);
+                let result = client.next().unwrap();
+                assert!(generator.legal_moves(&position).contains(&result.mv));
+                assert!(client.next().is_none());
+                position = position.go(&result.mv);
+                let reply = generator.legal_moves(&position)[0];
+                position = position.go(&reply);
+            }
+            assert_eq!(client.display_name(), "RandAap");
+
+            // an unrelated position starts a new game
+            let position = Position::parse_pdn("B:W31,K40:B2,K12").unwrap();
+            client.set_position(&position);
+            let result = client.next().unwrap();
+            assert!(generator.legal_moves(&position).contains(&result.mv));
+            assert!(client.error().is_none());
+        }
+        thread.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn peer_hangs_up() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let thread = thread::spawn(move || drop(listener.accept()));
+        let mut client = DxpClient::connect(&address, "Tester", 0, 0).unwrap();
+        thread.join().unwrap();
+        client.set_position(&Position::initial());
+        assert!(client.next().is_none());
+        assert!(client.error().is_some());
+    }
+
+    #[test]
+    fn take_back() {
+        let generator = Generator::create();
+        let (address, thread) = server(RandAap::create(1_000));
+        let mut connection = DxpConnection::connect(&address).unwrap();
+        connection
+            .send(&Message::GameRequest {
+                version: VERSION,
+                name: String::from("Tester"),
+                follower: Color::White,
+                minutes: 0,
+                moves: 0,
+                position: None,
+            })
+            .unwrap();
+        assert!(matches!(
+            connection.receive().unwrap(),
+            Message::GameAccept { code: ACCEPTED, .. }
+        ));
+        let first = connection.receive().unwrap();
+        let initial = Position::initial();
+        let mv = match &first {
+            Message::Move {
+                from, to, taken, ..
+            } => find_move(&generator, &initial, *from, *to, taken).unwrap(),
+            message => panic!("Unexpected {:?}", message),
+        };
+
+        let position = initial.go(&mv);
+        let reply = generator.legal_moves(&position)[0];
+        connection.send(&Message::from_move(0, &reply)).unwrap();
+        assert!(matches!(
+            connection.receive().unwrap(),
+            Message::Move { .. }
+        ));
+
+        connection
+            .send(&Message::BackRequest {
+                number: 1,
+                side: Color::White,
+            })
+            .unwrap();
+        assert_eq!(
+            connection.receive().unwrap(),
+            Message::BackAccept {
+                code: BACK_ACCEPTED
+            }
+        );
+        assert!(matches!(
+            connection.receive().unwrap(),
+            Message::Move { .. }
+        ));
+
+        connection
+            .send(&Message::BackRequest {
+                number: 9,
+                side: Color::Black,
+            })
+            .unwrap();
+        assert_eq!(
+            connection.receive().unwrap(),
+            Message::BackAccept {
+                code: BACK_REJECTED
+            }
+        );
+
+        connection
+            .send(&Message::GameEnd {
+                reason: END_LOSS,
+                stop: true,
+            })
+            .unwrap();
+        assert_eq!(
+            connection.receive().unwrap(),
+            Message::GameEnd {
+                reason: END_WIN,
+                stop: true
+            }
+        );
+        thread.join().unwrap().unwrap();
+    }
+}
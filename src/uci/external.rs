@@ -0,0 +1,373 @@
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use super::hub::{argument, parse_hub_move, parse_line};
+use super::io::ChildProcess;
+use crate::algorithm::judge::Eval;
+use crate::algorithm::meta::{Meta, Nodes};
+use crate::algorithm::stop::Stop;
+use crate::board::generator::Generator;
+use crate::board::position::Position;
+use crate::engine::time::TimeControl;
+use crate::engine::{Engine, EngineResult};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+const POLL: Duration = Duration::from_millis(50);
+// scores with a decimal point are given in men
+const MAN: f64 = 500.0;
+
+#[derive(Clone, Debug)]
+pub struct HubConfig {
+    pub path: PathBuf,
+    pub args: Vec<String>,
+    pub working_dir: Option<PathBuf>,
+    pub params: Vec<(String, String)>,
+    // how long to wait for an answer beyond the time the engine was given, and between lines
+    pub timeout: Duration,
+}
+
+impl HubConfig {
+    pub fn create<P: Into<PathBuf>>(path: P) -> HubConfig {
+        HubConfig {
+            path: path.into(),
+            args: vec![],
+            working_dir: None,
+            params: vec![],
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+// any engine speaking the Hub protocol, such as Scan
+pub struct HubEngine {
+    process: ChildProcess,
+    name: String,
+    timeout: Duration,
+    position: Option<Position>,
+    generator: Generator,
+    max_nodes: Option<Nodes>,
+    time_control: TimeControl,
+    stop: Stop,
+    error: Option<String>,
+}
+
+fn quote(value: &str) -> String {
+    if value.contains(char::is_whitespace) {
+        format!("\"{}\"", value)
+    } else {
+        value.to_owned()
+    }
+}
+
+fn parse_score(value: &str) -> Result<Eval, String> {
+    let score = if value.contains('.') {
+        value.parse::<f64>().map(|men| (men * MAN).round() as Eval)
+    } else {
+        value.parse::<f64>().map(|score| score as Eval)
+    };
+    score.map_err(|_| format!("Invalid score {}", value))
+}
+
+fn parse_info(
+    generator: &Generator,
+    position: &Position,
+    arguments: &[(String, String)],
+    result: &mut EngineResult,
+) -> Result<(), String> {
+    let mut meta = Meta::create();
+    if let Some(depth) = argument(arguments, "depth") {
+        meta.put_depth(
+            depth
+                .parse()
+                .map_err(|_| format!("Invalid depth {}", depth))?,
+        );
+    }
+    if let Some(nodes) = argument(arguments, "nodes") {
+        meta.add_nodes(
+            nodes
+                .parse()
+                .map_err(|_| format!("Invalid nodes {}", nodes))?,
+        );
+    }
+    result.meta = meta;
+    if let Some(score) = argument(arguments, "score") {
+        result.evaluation = parse_score(score)?;
+    }
+    if let Some(pv) = argument(arguments, "pv") {
+        let mut current = *position;
+        result.pv = vec![];
+        for notation in pv.split_whitespace() {
+            match parse_hub_move(generator, &current, notation) {
+                Ok(mv) => {
+                    current = current.go(&mv);
+                    result.pv.push(mv);
+                }
+                Err(_) => break,
+            }
+        }
+        if let Some(mv) = result.pv.first() {
+            result.mv = *mv;
+        }
+    }
+    Ok(())
+}
+
+impl HubEngine {
+    pub fn create(config: &HubConfig) -> Result<HubEngine, String> {
+        let default_name = config
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| config.path.display().to_string());
+        let mut command = Command::new(&config.path);
+        command.args(&config.args);
+        if let Some(ref working_dir) = config.working_dir {
+            command.current_dir(working_dir);
+        }
+        let mut engine = HubEngine {
+            process: ChildProcess::spawn(&default_name, &mut command)?,
+            name: default_name,
+            timeout: config.timeout,
+            position: None,
+            generator: Generator::create(),
+            max_nodes: None,
+            time_control: TimeControl::Unlimited,
+            stop: Stop::create(),
+            error: None,
+        };
+
+        engine.process.send("hub")?;
+        loop {
+            let line = engine.process.receive(engine.timeout)?;
+            let (command, arguments) = parse_line(&line);
+            match command.as_str() {
+                "id" => {
+                    if let Some(name) = argument(&arguments, "name") {
+                        engine.name = name.to_owned();
+                    }
+                }
+                "wait" => break,
+                "error" => return Err(line),
+                _ => (),
+            }
+        }
+        for (name, value) in &config.params {
+            engine.process.send(&format!(
+                "param name={} value={}",
+                quote(name),
+                quote(value)
+            ))?;
+        }
+        engine.process.send("init")?;
+        loop {
+            let line = engine.process.receive(engine.timeout)?;
+            match parse_line(&line).0.as_str() {
+                "ready" => break,
+                "error" => return Err(line),
+                _ => (),
+            }
+        }
+        Ok(engine)
+    }
+
+    // only used when the engine has no time control
+    pub fn set_max_nodes(&mut self, max_nodes: Nodes) {
+        self.max_nodes = Some(max_nodes);
+    }
+
+    // why the last search did not produce a result
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    fn level(&self) -> String {
+        match self.time_control {
+            TimeControl::Unlimited => match self.max_nodes {
+                Some(nodes) => format!("level nodes={}", nodes),
+                None => String::from("level infinite"),
+            },
+            TimeControl::PerMove(time) => format!("level move-time={:.3}", time.as_secs_f64()),
+            TimeControl::Clock {
+                remaining,
+                increment,
+                moves_to_go,
+            } => format!(
+                "level time={:.3} inc={:.3} moves={}",
+                remaining.as_secs_f64(),
+                increment.as_secs_f64(),
+                moves_to_go.unwrap_or(0)
+            ),
+        }
+    }
+
+    fn budget(&self) -> Option<Duration> {
+        match self.time_control {
+            TimeControl::Unlimited => None,
+            TimeControl::PerMove(time) => Some(time),
+            TimeControl::Clock { remaining, .. } => Some(remaining),
+        }
+    }
+
+    fn think(&mut self, position: &Position) -> Result<EngineResult, String> {
+        self.process.send(&format!("pos pos={}", position.hfen()))?;
+        let level = self.level();
+        self.process.send(&level)?;
+        self.process.send("go think")?;
+
+        let mut result = EngineResult::empty();
+        // an engine without a budget must keep talking, every line buys it another timeout
+        let mut deadline = Instant::now() + self.budget().unwrap_or_default() + self.timeout;
+        let mut stopping = false;
+        loop {
+            if !stopping && self.stop.stopped() {
+                self.process.send("stop")?;
+                stopping = true;
+                deadline = Instant::now() + self.timeout;
+            }
+            if Instant::now() >= deadline {
+                return Err(format!("{} did not finish its search", self.name));
+            }
+            let line = match self.process.poll(POLL)? {
+                Some(line) => line,
+                None => continue,
+            };
+            deadline = deadline.max(Instant::now() + self.timeout);
+            let (command, arguments) = parse_line(&line);
+            match command.as_str() {
+                "info" => parse_info(&self.generator, position, &arguments, &mut result)?,
+                "done" => {
+                    let notation = argument(&arguments, "move")
+                        .ok_or_else(|| format!("{} did not play a move", self.name))?;
+                    let mv = parse_hub_move(&self.generator, position, notation)?;
+                    if result.pv.first() != Some(&mv) {
+                        result.pv = vec![mv];
+                    }
+                    result.mv = mv;
+                    return Ok(result);
+                }
+                "error" => return Err(line),
+                _ => (),
+            }
+        }
+    }
+}
+
+impl Iterator for HubEngine {
+    type Item = EngineResult;
+    fn next(&mut self) -> Option<EngineResult> {
+        let position = self.position.take()?;
+        match self.think(&position) {
+            Ok(result) => Some(result),
+            Err(error) => {
+                self.error = Some(error);
+                None
+            }
+        }
+    }
+}
+
+impl Engine for HubEngine {
+    fn display_name(&self) -> &str {
+        &self.name
+    }
+    fn set_position(&mut self, position: &Position) {
+        self.position = Some(*position);
+        self.error = None;
+        self.stop.restart(None);
+    }
+    fn set_time_control(&mut self, time_control: &TimeControl) {
+        self.time_control = *time_control;
+    }
+    fn stop_handle(&self) -> Option<Stop> {
+        Some(self.stop.clone())
+    }
+}
+
+impl Drop for HubEngine {
+    fn drop(&mut self) {
+        self.process.send("quit").ok();
+    }
+}
+
+#[cfg(unix)]
+#[cfg(test)]
+fn fake_engine(script: &str) -> HubConfig {
+    let mut config = HubConfig::create("sh");
+    config.args = vec![String::from("-c"), script.to_owned()];
+    config.timeout = Duration::from_secs(5);
+    config
+}
+
+#[cfg(unix)]
+#[test]
+fn think() {
+    let config = fake_engine(
+        r#"while read line; do
+             case "$line" in
+               hub) echo "id name=Fake version=1"; echo wait;;
+               init) echo ready;;
+               "go think") echo 'info depth=7 score=0.5 nodes=1234 pv="32-28 19-23"'; echo "done move=32-28 ponder=19-23";;
+               quit) exit 0;;
+             esac
+           done"#,
+    );
+    let mut engine = HubEngine::create(&config).unwrap();
+    assert_eq!(engine.display_name(), "Fake");
+
+    let generator = Generator::create();
+    let position = Position::initial();
+    engine.set_position(&position);
+    let result = engine.next().unwrap();
+    assert_eq!(generator.to_short_string(&position, &result.mv), "32-28");
+    assert_eq!(result.evaluation, 250);
+    assert_eq!(result.meta.get_depth(), 7);
+    assert_eq!(result.meta.get_nodes(), 1234);
+    assert_eq!(result.pv_string(&generator, &position), "32-28 19-23");
+    assert!(engine.next().is_none());
+    assert!(engine.error().is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn failures() {
+    let error = HubEngine::create(&fake_engine("read line; exit 3"))
+        .err()
+        .unwrap();
+    assert!(error.contains("exited"), "{}", error);
+
+    let mut config = fake_engine("sleep 5");
+    config.timeout = Duration::from_millis(50);
+    let error = HubEngine::create(&config).err().unwrap();
+    assert!(error.contains("did not answer"), "{}", error);
+
+    let mut engine = HubEngine::create(&fake_engine(
+        r#"while read line; do
+             case "$line" in
+               hub) echo wait;;
+               init) echo ready;;
+               "go think") exit 1;;
+             esac
+           done"#,
+    ))
+    .unwrap();
+    engine.set_position(&Position::initial());
+    assert!(engine.next().is_none());
+    assert!(engine.error().unwrap().contains("exited"));
+
+    // silent under a node level, which gives no time budget
+    let mut config = fake_engine(
+        r#"while read line; do
+             case "$line" in
+               hub) echo wait;;
+               init) echo ready;;
+             esac
+           done"#,
+    );
+    config.timeout = Duration::from_millis(200);
+    let mut engine = HubEngine::create(&config).unwrap();
+    engine.set_max_nodes(1_000);
+    engine.set_position(&Position::initial());
+    assert!(engine.next().is_none());
+    assert!(engine.error().unwrap().contains("did not finish"));
+}
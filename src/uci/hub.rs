@@ -0,0 +1,593 @@
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::algorithm::meta::Nodes;
+use crate::algorithm::scope::Depth;
+use crate::algorithm::stop::Stop;
+use crate::algorithm::tt::DEFAULT_MEGABYTES;
+use crate::board::generator::Generator;
+use crate::board::mv::Move;
+use crate::board::position::Position;
+use crate::engine::time::TimeControl;
+use crate::engine::{Engine, EngineResult};
+use crate::tablebase::Tablebase;
+
+pub type ServedEngine = Box<dyn Engine<Item = EngineResult> + Send>;
+pub type EngineFactory = dyn Fn(&HubOptions) -> ServedEngine;
+
+const MAX_HASH: usize = 4096;
+
+#[derive(Clone)]
+pub struct HubOptions {
+    pub hash: usize,
+    pub tablebase_dir: String,
+    pub tablebase: Option<Arc<Tablebase>>,
+}
+
+impl HubOptions {
+    pub fn create() -> HubOptions {
+        HubOptions {
+            hash: DEFAULT_MEGABYTES,
+            tablebase_dir: String::new(),
+            tablebase: None,
+        }
+    }
+
+    // an empty directory unloads the tablebase
+    fn set_tablebase_dir(&mut self, dir: &str) -> Result<(), String> {
+        self.tablebase = if dir.is_empty() {
+            None
+        } else {
+            Some(Arc::new(Tablebase::load(Path::new(dir))?))
+        };
+        self.tablebase_dir = dir.to_owned();
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Level {
+    time_control: TimeControl,
+    depth: Option<Depth>,
+    nodes: Option<Nodes>,
+}
+
+impl Level {
+    fn create() -> Level {
+        Level {
+            time_control: TimeControl::Unlimited,
+            depth: None,
+            nodes: None,
+        }
+    }
+
+    fn reached(&self, result: &EngineResult) -> bool {
+        self.depth
+            .is_some_and(|depth| result.meta.get_depth() >= depth)
+            || self
+                .nodes
+                .is_some_and(|nodes| result.meta.get_nodes() >= nodes)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Answer {
+    Done,
+    // the original protocol, as spoken by older versions of Scan
+    Move,
+}
+
+// splits `command key=value key="quoted value" flag` into the command and its arguments
+pub fn parse_line(line: &str) -> (String, Vec<(String, String)>) {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut quoted = false;
+    for c in line.trim().chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(word);
+                    word = String::new();
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    let mut words = words.into_iter();
+    let command = words.next().unwrap_or_default();
+    let arguments = words
+        .map(|word| match word.find('=') {
+            Some(index) => (word[..index].to_owned(), word[index + 1..].to_owned()),
+            None => (word, String::new()),
+        })
+        .collect();
+    (command, arguments)
+}
+
+pub fn argument<'a>(arguments: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    arguments
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+fn parse_seconds(value: &str) -> Result<Duration, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|seconds| *seconds >= 0.0)
+        .map(Duration::from_secs_f64)
+        .ok_or_else(|| format!("Invalid time {}", value))
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid number {}", value))
+}
+
+// hub positions are the side to move followed by 50 squares, e.g. Wbbbbbbbbbbbbbbbbbbbbeeeeeeeeeewwwwwwwwwwwwwwwwwwww
+fn parse_hub_position(fen: &str) -> Result<Position, String> {
+    let mut chars = fen.chars();
+    let side = chars
+        .next()
+        .ok_or_else(|| String::from("Empty position"))?
+        .to_ascii_lowercase();
+    Position::parse(&format!("{}{}", side, chars.as_str()))
+}
+
+pub fn parse_hub_move(
+    generator: &Generator,
+    position: &Position,
+    notation: &str,
+) -> Result<Move, String> {
+    match generator
+        .legal_moves(position)
+        .into_iter()
+        .find(|mv| mv.as_full_string() == notation)
+    {
+        Some(mv) => Ok(mv),
+        None => generator.parse_move(position, notation),
+    }
+}
+
+fn parse_position(
+    generator: &Generator,
+    arguments: &[(String, String)],
+) -> Result<Position, String> {
+    let mut position = match argument(arguments, "pos") {
+        Some(fen) => parse_hub_position(fen)?,
+        None => match arguments.first() {
+            Some((fen, value)) if value.is_empty() => parse_hub_position(fen)?,
+            _ => Position::initial(),
+        },
+    };
+    if let Some(moves) = argument(arguments, "moves") {
+        for notation in moves.split_whitespace() {
+            let mv = parse_hub_move(generator, &position, notation)?;
+            position = position.go(&mv);
+        }
+    }
+    Ok(position)
+}
+
+fn parse_level(arguments: &[(String, String)]) -> Result<Level, String> {
+    let mut level = Level::create();
+    let numbers: Vec<&str> = arguments
+        .iter()
+        .filter(|(_, value)| value.is_empty())
+        .map(|(key, _)| key.as_str())
+        .filter(|key| *key != "infinite")
+        .collect();
+    if numbers.len() == 3 {
+        let moves: u32 = parse_number(numbers[0])?;
+        level.time_control = TimeControl::Clock {
+            remaining: parse_seconds(numbers[1])?,
+            increment: parse_seconds(numbers[2])?,
+            moves_to_go: if moves > 0 { Some(moves) } else { None },
+        };
+        return Ok(level);
+    }
+
+    if let Some(depth) = argument(arguments, "depth") {
+        level.depth = Some(parse_number(depth)?);
+    }
+    if let Some(nodes) = argument(arguments, "nodes") {
+        level.nodes = Some(parse_number(nodes)?);
+    }
+    if let Some(time) = argument(arguments, "move-time") {
+        level.time_control = TimeControl::PerMove(parse_seconds(time)?);
+    } else if let Some(time) = argument(arguments, "time") {
+        level.time_control = TimeControl::Clock {
+            remaining: parse_seconds(time)?,
+            increment: match argument(arguments, "inc") {
+                Some(inc) => parse_seconds(inc)?,
+                None => Duration::ZERO,
+            },
+            moves_to_go: match argument(arguments, "moves") {
+                Some(moves) => Some(parse_number(moves)?).filter(|&moves| moves > 0),
+                None => None,
+            },
+        };
+    }
+    Ok(level)
+}
+
+fn info_line(result: &EngineResult, elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs_f64();
+    let nps = if seconds > 0.0 {
+        (result.meta.get_nodes() as f64 / seconds) as Nodes
+    } else {
+        0
+    };
+    let pv: Vec<String> = result.pv.iter().map(Move::as_full_string).collect();
+    format!(
+        "info depth={} score={} nodes={} time={:.3} nps={} pv=\"{}\"",
+        result.meta.get_depth(),
+        result.evaluation,
+        result.meta.get_nodes(),
+        seconds,
+        nps,
+        pv.join(" ")
+    )
+}
+
+fn send<W: Write>(output: &Mutex<W>, line: &str) {
+    let mut output = output.lock().unwrap();
+    writeln!(output, "{}", line).ok();
+    output.flush().ok();
+}
+
+struct Search {
+    thread: JoinHandle<ServedEngine>,
+    stop: Stop,
+    engine_stop: Option<Stop>,
+}
+
+impl Search {
+    fn stop(&self) {
+        self.stop.stop();
+        if let Some(engine_stop) = &self.engine_stop {
+            engine_stop.stop();
+        }
+    }
+}
+
+fn start_search<W>(
+    mut engine: ServedEngine,
+    position: Position,
+    level: Level,
+    answer: Answer,
+    output: Arc<Mutex<W>>,
+) -> Search
+where
+    W: 'static + Write + Send,
+{
+    let stop = Stop::create();
+    let engine_stop = engine.stop_handle();
+    let search_stop = stop.clone();
+    let thread = thread::spawn(move || {
+        let start = Instant::now();
+        let mut best = None;
+        engine.set_time_control(&level.time_control);
+        engine.set_position(&position);
+        // the first iteration always completes, so there is a move even after an early stop
+        for result in engine.by_ref() {
+            send(&output, &info_line(&result, start.elapsed()));
+            let reached = level.reached(&result);
+            best = Some(result);
+            if reached || search_stop.stopped() {
+                break;
+            }
+        }
+        let line = match (answer, best) {
+            (Answer::Move, Some(result)) => format!("move {}", result.mv.as_full_string()),
+            (Answer::Done, Some(result)) => match result.pv.get(1) {
+                Some(ponder) => format!(
+                    "done move={} ponder={}",
+                    result.mv.as_full_string(),
+                    ponder.as_full_string()
+                ),
+                None => format!("done move={}", result.mv.as_full_string()),
+            },
+            (_, None) => String::from("done"),
+        };
+        send(&output, &line);
+        engine
+    });
+    Search {
+        thread,
+        stop,
+        engine_stop,
+    }
+}
+
+fn finish(search: &mut Option<Search>, engine: &mut Option<ServedEngine>) {
+    if let Some(search) = search.take() {
+        *engine = Some(search.thread.join().expect("Search thread panicked"));
+    }
+}
+
+// speaks the Hub protocol on input and output, creating the engine with the given factory
+pub fn run<R, W>(factory: &EngineFactory, input: R, output: W)
+where
+    R: BufRead,
+    W: 'static + Write + Send,
+{
+    let output = Arc::new(Mutex::new(output));
+    let generator = Generator::create();
+    let mut options = HubOptions::create();
+    let mut engine = Some(factory(&options));
+    let mut dirty = false;
+    let mut position = Position::initial();
+    let mut level = Level::create();
+    let mut search: Option<Search> = None;
+
+    for line in input.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let (command, arguments) = parse_line(&line);
+        let result = match command.as_str() {
+            "" => Ok(()),
+            "hub" => {
+                finish(&mut search, &mut engine);
+                let name = engine.as_ref().unwrap().display_name().to_owned();
+                send(
+                    &output,
+                    &format!("id name={} version={}", name, env!("CARGO_PKG_VERSION")),
+                );
+                send(
+                    &output,
+                    &format!(
+                        "param name=hash value={} type=int min=1 max={}",
+                        options.hash, MAX_HASH
+                    ),
+                );
+                send(
+                    &output,
+                    &format!(
+                        "param name=tablebase value=\"{}\" type=string",
+                        options.tablebase_dir
+                    ),
+                );
+                send(&output, "wait");
+                Ok(())
+            }
+            "init" => {
+                send(&output, "ready");
+                Ok(())
+            }
+            "new-game" | "ponder-hit" => Ok(()),
+            "ping" => {
+                send(&output, "pong");
+                Ok(())
+            }
+            "param" => match (argument(&arguments, "name"), argument(&arguments, "value")) {
+                (Some("hash"), Some(value)) => parse_number(value)
+                    .and_then(|hash: usize| {
+                        if (1..=MAX_HASH).contains(&hash) {
+                            Ok(hash)
+                        } else {
+                            Err(format!("Invalid hash size {}", hash))
+                        }
+                    })
+                    .map(|hash| {
+                        dirty = dirty || hash != options.hash;
+                        options.hash = hash;
+                    }),
+                (Some("tablebase"), Some(dir)) => {
+                    options.set_tablebase_dir(dir).map(|()| dirty = true)
+                }
+                (Some(name), _) => Err(format!("Unknown parameter {}", name)),
+                _ => Err(String::from("Invalid parameter")),
+            },
+            "pos" => parse_position(&generator, &arguments).map(|parsed| position = parsed),
+            "level" => parse_level(&arguments).map(|parsed| level = parsed),
+            "go" | "analyse" => {
+                finish(&mut search, &mut engine);
+                if dirty {
+                    engine = Some(factory(&options));
+                    dirty = false;
+                }
+                let (answer, level) = match (command.as_str(), arguments.first()) {
+                    ("analyse", _) => (Answer::Move, level),
+                    (_, Some((mode, _))) if mode == "analyze" => (
+                        Answer::Done,
+                        Level {
+                            time_control: TimeControl::Unlimited,
+                            ..level
+                        },
+                    ),
+                    _ => (Answer::Done, level),
+                };
+                if generator.legal_moves(&position).is_empty() {
+                    send(&output, "done");
+                } else {
+                    search = Some(start_search(
+                        engine.take().unwrap(),
+                        position,
+                        level,
+                        answer,
+                        Arc::clone(&output),
+                    ));
+                }
+                Ok(())
+            }
+            "stop" => {
+                if let Some(search) = &search {
+                    search.stop();
+                }
+                Ok(())
+            }
+            "quit" => {
+                if let Some(search) = &search {
+                    search.stop();
+                }
+                break;
+            }
+            _ => Err(format!("Unknown command {}", command)),
+        };
+        if let Err(message) = result {
+            send(&output, &format!("error {}", message));
+        }
+    }
+    finish(&mut search, &mut engine);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::engine::randaap::RandAap;
+    use std::io::Cursor;
+
+    #[derive(Clone)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(bytes)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn session(commands: &str) -> Vec<String> {
+        let buffer = Buffer(Arc::new(Mutex::new(vec![])));
+        let factory = |_: &HubOptions| -> ServedEngine { Box::new(RandAap::create(Nodes::MAX)) };
+        run(&factory, Cursor::new(commands.to_owned()), buffer.clone());
+        let bytes = buffer.0.lock().unwrap().clone();
+        String::from_utf8(bytes)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn split_arguments() {
+        let (command, arguments) = parse_line("pos pos=Wbe moves=\"32-28 19-23\" start");
+        assert_eq!(command, "pos");
+        assert_eq!(argument(&arguments, "pos"), Some("Wbe"));
+        assert_eq!(argument(&arguments, "moves"), Some("32-28 19-23"));
+        assert_eq!(argument(&arguments, "start"), Some(""));
+        assert_eq!(argument(&arguments, "depth"), None);
+    }
+
+    #[test]
+    fn levels() {
+        let level = parse_level(&parse_line("level depth=5 nodes=1000").1).unwrap();
+        assert_eq!(level.depth, Some(5));
+        assert_eq!(level.nodes, Some(1000));
+        assert_eq!(level.time_control, TimeControl::Unlimited);
+
+        let level = parse_level(&parse_line("level move-time=1.5").1).unwrap();
+        assert_eq!(
+            level.time_control,
+            TimeControl::PerMove(Duration::from_millis(1500))
+        );
+
+        let level = parse_level(&parse_line("level time=60 inc=1 moves=40").1).unwrap();
+        assert_eq!(
+            level.time_control,
+            TimeControl::Clock {
+                remaining: Duration::from_secs(60),
+                increment: Duration::from_secs(1),
+                moves_to_go: Some(40),
+            }
+        );
+
+        let level = parse_level(&parse_line("level 1 3 0").1).unwrap();
+        assert_eq!(
+            level.time_control,
+            TimeControl::Clock {
+                remaining: Duration::from_secs(3),
+                increment: Duration::ZERO,
+                moves_to_go: Some(1),
+            }
+        );
+
+        assert!(parse_level(&parse_line("level depth=x").1).is_err());
+    }
+
+    #[test]
+    fn positions() {
+        let generator = Generator::create();
+        let initial = Position::initial();
+        let position = parse_position(
+            &generator,
+            &parse_line(&format!(
+                "pos pos={} moves=\"32-28 19-23 28x19\"",
+                initial.hfen()
+            ))
+            .1,
+        )
+        .unwrap();
+        assert!(position == Position::parse_pdn("B:W19,31,33-50:B1-18,20").unwrap());
+        assert!(
+            parse_position(&generator, &parse_line(&format!("pos {}", initial.fen())).1).unwrap()
+                == initial
+        );
+        assert!(parse_position(&generator, &parse_line("pos moves=32-22").1).is_err());
+    }
+
+    #[test]
+    fn think() {
+        let lines = session(
+            "hub\ninit\nparam name=hash value=8\nparam name=threads value=2\n\
+             param name=tablebase value=/nonexistent\n\
+             pos moves=\"32-28 19-23\"\nlevel depth=2\ngo think\nping\n",
+        );
+        assert_eq!(
+            lines[0],
+            format!("id name=RandAap version={}", env!("CARGO_PKG_VERSION"))
+        );
+        assert!(lines.contains(&String::from("wait")));
+        assert!(lines.contains(&String::from("ready")));
+        assert!(lines.contains(&String::from("error Unknown parameter threads")));
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("error /nonexistent")));
+        assert!(lines.contains(&String::from("pong")));
+        assert!(lines.iter().any(|line| line.starts_with("info depth=2 ")));
+        assert!(!lines.iter().any(|line| line.starts_with("info depth=3 ")));
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("done move=28x19x23")));
+    }
+
+    #[test]
+    fn legacy() {
+        let initial = Position::initial();
+        let lines = session(&format!(
+            "init\npos {}\nlevel 1 1 0\nanalyse\n",
+            initial.fen()
+        ));
+        let answer = lines.last().unwrap();
+        assert!(answer.starts_with("move "));
+        let generator = Generator::create();
+        assert!(parse_hub_move(&generator, &initial, &answer[5..]).is_ok());
+    }
+
+    #[test]
+    fn early_stop() {
+        let lines = session("go analyze\nstop\n");
+        assert!(lines.last().unwrap().starts_with("done move="));
+    }
+
+    #[test]
+    fn game_over() {
+        let lines = session(&format!("pos pos=Wb{}\ngo think\nfoo\n", "e".repeat(49)));
+        assert!(lines == ["done", "error Unknown command foo"]);
+    }
+}
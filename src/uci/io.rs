@@ -1,6 +1,11 @@
 use std::io;
 use std::io::{BufRead, BufReader, Write};
-use std::process::ChildStdout;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const EXIT_GRACE: Duration = Duration::from_millis(200);
 
 fn trim_eol(mut s: String) -> String {
     let len = s.trim().len();
@@ -81,3 +86,115 @@ impl<'a, 'b> Iterator for LineReader<'a, 'b> {
         Some(line)
     }
 }
+
+// a child engine whose output is read on a separate thread, so reads can time out
+pub struct ChildProcess {
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl ChildProcess {
+    pub fn spawn(name: &str, command: &mut Command) -> Result<ChildProcess, String> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Cannot start {}: {}", name, e))?;
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take();
+        let (stdin, stdout) = match (stdin, stdout) {
+            (Some(stdin), Some(stdout)) => (stdin, stdout),
+            _ => {
+                child.kill().ok();
+                child.wait().ok();
+                return Err(format!("No pipes to {}", name));
+            }
+        };
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let sent = match line {
+                    Ok(line) => sender.send(trim_eol(line)),
+                    Err(_) => break,
+                };
+                if sent.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(ChildProcess {
+            name: name.to_owned(),
+            child,
+            stdin,
+            lines,
+        })
+    }
+
+    pub fn send(&mut self, line: &str) -> Result<(), String> {
+        match writeln!(self.stdin, "{}", line).and_then(|_| self.stdin.flush()) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(self.crashed()),
+        }
+    }
+
+    // Ok(None) when nothing arrived within the timeout
+    pub fn poll(&mut self, timeout: Duration) -> Result<Option<String>, String> {
+        match self.lines.recv_timeout(timeout) {
+            Ok(line) => Ok(Some(line)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(self.crashed()),
+        }
+    }
+
+    pub fn receive(&mut self, timeout: Duration) -> Result<String, String> {
+        self.poll(timeout)?
+            .ok_or_else(|| format!("{} did not answer within {:?}", self.name, timeout))
+    }
+
+    fn crashed(&mut self) -> String {
+        let start = Instant::now();
+        while start.elapsed() < EXIT_GRACE {
+            if let Ok(Some(status)) = self.child.try_wait() {
+                return format!("{} exited ({})", self.name, status);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        format!("{} closed its pipes", self.name)
+    }
+}
+
+impl Drop for ChildProcess {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn child_process() {
+    let mut process = ChildProcess::spawn(
+        "cat",
+        Command::new("sh").args(["-c", "read line; echo $line"]),
+    )
+    .unwrap();
+    assert_eq!(process.poll(Duration::from_millis(10)), Ok(None));
+    process.send("hello").unwrap();
+    assert_eq!(
+        process.receive(Duration::from_secs(5)),
+        Ok(String::from("hello"))
+    );
+    let error = process.receive(Duration::from_secs(5)).unwrap_err();
+    assert!(error.contains("exited"), "{}", error);
+
+    let mut process = ChildProcess::spawn("sleep", Command::new("sleep").arg("5")).unwrap();
+    let error = process.receive(Duration::from_millis(50)).unwrap_err();
+    assert!(error.contains("did not answer"), "{}", error);
+
+    assert!(ChildProcess::spawn("nothing", &mut Command::new("/nonexistent/engine")).is_err());
+}
@@ -1,3 +1,6 @@
+pub mod dxp;
+pub mod external;
+pub mod hub;
 pub mod io;
 pub mod scan;
 pub mod slagzet;
@@ -1,60 +1,64 @@
-use std::io::{BufReader, Write};
-use std::process::{ChildStdin, ChildStdout, Command, Stdio};
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
 
-use super::io::read_stdout;
+use super::io::ChildProcess;
 use crate::algorithm::meta::{Meta, Nodes};
 use crate::board::generator::Generator;
 use crate::board::position::Position;
 use crate::engine::{Engine, EngineResult};
 
+const TIMEOUT: Duration = Duration::from_secs(60);
+
 pub struct Slagzet {
-    stdin: ChildStdin,
-    stdout: BufReader<ChildStdout>,
+    process: ChildProcess,
     position: Option<Position>,
     generator: Generator,
+    error: Option<String>,
 }
 
 impl Slagzet {
-    pub fn create(max_nodes: Nodes) -> Slagzet {
-        let mut child = Command::new("node")
-            .arg("/mnt/c/develop/extern/slagzet/slagzet.js")
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .expect("Failed to execute child");
-
-        let mut stdin = child.stdin.take().expect("No stdin on Slagzet");
-        let stdout = BufReader::new(child.stdout.take().expect("No stdout on Slagzet"));
-        stdin.write(format!("{}\n", max_nodes).as_bytes()).ok();
-        Slagzet {
-            stdin,
-            stdout,
+    // runs slagzet.js with node
+    pub fn create(script: &Path, max_nodes: Nodes) -> Result<Slagzet, String> {
+        let mut process = ChildProcess::spawn(NAME, Command::new("node").arg(script))?;
+        process.send(&max_nodes.to_string())?;
+        Ok(Slagzet {
+            process,
             position: None,
             generator: Generator::create(),
-        }
+            error: None,
+        })
+    }
+
+    // why the last search did not produce a result
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    fn think(&mut self, position: &Position) -> Result<EngineResult, String> {
+        self.process.send(&position.fen())?;
+        let move_string = self.process.receive(TIMEOUT)?;
+        let mv = self
+            .generator
+            .legal_moves(position)
+            .into_iter()
+            .find(|m| m.as_string() == move_string)
+            .ok_or_else(|| format!("{} played an illegal move {}", NAME, move_string))?;
+        Ok(EngineResult::create(mv, 0, Meta::create()))
     }
 }
 
 impl Iterator for Slagzet {
     type Item = EngineResult;
     fn next(&mut self) -> Option<EngineResult> {
-        let result = if let Some(ref position) = self.position {
-            self.stdin.write(position.fen().as_bytes()).ok();
-            self.stdin.write(b"\n").ok();
-            let move_string = read_stdout(&mut self.stdout);
-            let mv = self
-                .generator
-                .legal_moves(position)
-                .into_iter()
-                .find(|m| m.as_string() == move_string)
-                .expect("No move found");
-            Some(EngineResult::create(mv, 0, Meta::create()))
-        } else {
-            None
-        };
-        self.position = None;
-        result
+        let position = self.position.take()?;
+        match self.think(&position) {
+            Ok(result) => Some(result),
+            Err(error) => {
+                self.error = Some(error);
+                None
+            }
+        }
     }
 }
 
@@ -65,5 +69,6 @@ impl Engine for Slagzet {
     }
     fn set_position(&mut self, position: &Position) {
         self.position = Some(*position);
+        self.error = None;
     }
 }